@@ -5,5 +5,10 @@ fn main() {
         Windows::Win32::System::Registry::*,
         Windows::Win32::System::SystemInformation::*,
         Windows::Win32::System::Diagnostics::Debug::*,
+        Windows::Win32::System::Diagnostics::ToolHelp::*,
+        Windows::Win32::Security::*,
+        Windows::Win32::Security::Authorization::*,
+        Windows::Win32::Security::Cryptography::Core::*,
+        Windows::Win32::System::Memory::*,
     );
 }
@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use log::{info, warn};
+
+use crate::file_provider::FileProvider;
+
+const CACHE_FILE_NAME: &str = "directories.txt";
+const CACHE_HEADER: &str = "dllwalk directory cache 1";
+
+/// What the cache holds, printed by the cache command
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub directories: usize,
+    pub files: usize,
+    /// Directories modified or deleted since they were listed, read again on the next run
+    pub stale_directories: usize,
+    /// Size in bytes of the cache file
+    pub size: u64,
+}
+
+#[derive(Debug)]
+struct CachedDirectory {
+    modified: Duration,
+    names: Vec<String>,
+    used: bool,
+}
+
+/// Names of the files in the directories searched by previous runs. A listing is valid
+/// as long as the modification time of its directory, which changes when a file is
+/// added, removed or renamed, is the same.
+#[derive(Debug, Default)]
+pub struct DirectoryCache {
+    file: Option<PathBuf>,
+    directories: HashMap<PathBuf, CachedDirectory>,
+    modified: bool,
+}
+
+impl DirectoryCache {
+    /// Loads the cache stored in `directory`, or an empty cache that is never saved
+    /// when there is none
+    pub fn load(directory: Option<&Path>) -> Self {
+        let file = match directory {
+            Some(directory) => cache_file(directory),
+            None => return DirectoryCache::default(),
+        };
+
+        let directories = match std::fs::read_to_string(&file) {
+            Ok(text) => DirectoryCache::parse(&text).unwrap_or_else(|| {
+                info!("Ignoring invalid directory cache {}", file.to_string_lossy());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        DirectoryCache {
+            file: Some(file),
+            directories,
+            modified: false,
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let stale_directories = self
+            .directories
+            .iter()
+            .filter(|(path, directory)| modified_time(path).ok() != Some(directory.modified))
+            .count();
+
+        CacheStats {
+            directories: self.directories.len(),
+            files: self.directories.values().map(|directory| directory.names.len()).sum(),
+            stale_directories,
+            size: self
+                .file
+                .as_ref()
+                .and_then(|file| std::fs::metadata(file).ok())
+                .map_or(0, |metadata| metadata.len()),
+        }
+    }
+
+    /// Names of the files in `path` read through `files`, from the cache when the directory
+    /// did not change
+    pub fn file_names(
+        &mut self,
+        files: &dyn FileProvider,
+        path: &Path,
+    ) -> std::io::Result<Vec<String>> {
+        let modified = files
+            .metadata(path)?
+            .modified
+            .map(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default());
+        if let (Some(directory), Some(modified)) = (self.directories.get_mut(path), modified) {
+            if directory.modified == modified {
+                directory.used = true;
+                return Ok(directory.names.clone());
+            }
+        }
+
+        let names: Vec<String> = files
+            .list_dir(path)?
+            .into_iter()
+            .filter(|name| files.is_file(&path.join(name)))
+            .collect();
+
+        // Only what the text format can hold is cached
+        let cacheable = path
+            .to_str()
+            .map_or(false, |path| !path.contains('\n') && !path.starts_with('\t'))
+            && names.iter().all(|name| !name.contains('\n'));
+        let modified = match modified {
+            Some(modified) if cacheable => modified,
+            _ => return Ok(names),
+        };
+        if self.file.is_some() {
+            self.directories.insert(
+                path.to_owned(),
+                CachedDirectory {
+                    modified,
+                    names: names.clone(),
+                    used: true,
+                },
+            );
+            self.modified = true;
+        }
+
+        Ok(names)
+    }
+
+    // A directory line with its modification time is followed by one line per file,
+    // starting with a tab
+    fn parse(text: &str) -> Option<HashMap<PathBuf, CachedDirectory>> {
+        let mut lines = text.lines();
+        if lines.next()? != CACHE_HEADER {
+            return None;
+        }
+
+        let mut directories = HashMap::new();
+        let mut current: Option<(PathBuf, CachedDirectory)> = None;
+        for line in lines {
+            if let Some(name) = line.strip_prefix('\t') {
+                current.as_mut()?.1.names.push(name.to_owned());
+                continue;
+            }
+
+            let mut fields = line.splitn(3, ' ');
+            let seconds = fields.next()?.parse().ok()?;
+            let nanoseconds = fields.next()?.parse().ok()?;
+            let path = PathBuf::from(fields.next()?);
+
+            let directory = CachedDirectory {
+                modified: Duration::new(seconds, nanoseconds),
+                names: Vec::new(),
+                used: false,
+            };
+            if let Some((path, directory)) = current.replace((path, directory)) {
+                directories.insert(path, directory);
+            }
+        }
+
+        if let Some((path, directory)) = current {
+            directories.insert(path, directory);
+        }
+
+        Some(directories)
+    }
+
+    /// Writes the directories listed since the cache was loaded, if any. The cache is also
+    /// saved when it is dropped, which a process that exits does not do.
+    pub fn save(&mut self) {
+        if let (Some(file), true) = (&self.file, self.modified) {
+            match self.write(file) {
+                Ok(()) => self.modified = false,
+                Err(err) => warn!("Failed to save {}: {}", file.to_string_lossy(), err),
+            }
+        }
+    }
+
+    fn write(&self, file: &Path) -> std::io::Result<()> {
+        let mut text = String::new();
+        text.push_str(CACHE_HEADER);
+        text.push('\n');
+
+        // Directories of other runs are kept until they are deleted
+        for (path, directory) in &self.directories {
+            if !directory.used && !path.is_dir() {
+                continue;
+            }
+
+            text.push_str(&format!(
+                "{} {} {}\n",
+                directory.modified.as_secs(),
+                directory.modified.subsec_nanos(),
+                path.to_string_lossy()
+            ));
+            for name in &directory.names {
+                text.push('\t');
+                text.push_str(name);
+                text.push('\n');
+            }
+        }
+
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Written next to the cache then renamed over it, so that concurrent runs and
+        // interrupted writes never leave a truncated cache
+        let mut temporary = file.as_os_str().to_owned();
+        temporary.push(format!(".{}.tmp", std::process::id()));
+        let temporary = PathBuf::from(temporary);
+        let written = std::fs::File::create(&temporary)
+            .and_then(|mut writer| writer.write_all(text.as_bytes()))
+            .and_then(|_| std::fs::rename(&temporary, file));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
+        written
+    }
+}
+
+impl Drop for DirectoryCache {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
+
+fn modified_time(path: &Path) -> std::io::Result<Duration> {
+    Ok(std::fs::metadata(path)?
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default())
+}
+
+/// Cache file stored in `directory`
+pub fn cache_file(directory: &Path) -> PathBuf {
+    directory.join(CACHE_FILE_NAME)
+}
+
+/// Deletes the cache stored in `directory`, returns whether there was one
+pub fn clear(directory: &Path) -> std::io::Result<bool> {
+    match std::fs::remove_file(cache_file(directory)) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// %LOCALAPPDATA%\dllwalk on Windows, the XDG cache directory elsewhere
+pub fn default_directory() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".cache")))
+    };
+
+    Some(base?.join("dllwalk"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::file_provider::LocalFiles;
+
+    #[test]
+    fn directory_cache() {
+        let root = std::env::temp_dir().join("dllwalk-directory-cache");
+        let directory = root.join("system32");
+        std::fs::create_dir_all(directory.join("drivers")).unwrap();
+        std::fs::write(directory.join("kernel32.dll"), []).unwrap();
+
+        let mut cache = DirectoryCache::load(Some(&root));
+        assert_eq!(cache.file_names(&LocalFiles, &directory).unwrap(), vec!["kernel32.dll"]);
+        cache.save();
+        assert!(!cache.modified);
+        // The cache and the directory, without the temporary file
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 2);
+        drop(cache);
+
+        // Listings are read back until the directory changes
+        let mut cache = DirectoryCache::load(Some(&root));
+        assert_eq!(cache.directories[&directory].names, vec!["kernel32.dll"]);
+        cache.directories.get_mut(&directory).unwrap().names = vec!["cached.dll".to_owned()];
+        assert_eq!(cache.file_names(&LocalFiles, &directory).unwrap(), vec!["cached.dll"]);
+
+        cache.directories.get_mut(&directory).unwrap().modified = Duration::ZERO;
+        assert_eq!(cache.file_names(&LocalFiles, &directory).unwrap(), vec!["kernel32.dll"]);
+        drop(cache);
+
+        let cache = DirectoryCache::load(Some(&root));
+        let stats = cache.stats();
+        assert_eq!((stats.directories, stats.files, stats.stale_directories), (1, 1, 0));
+        drop(cache);
+        assert!(clear(&root).unwrap());
+        assert!(!clear(&root).unwrap());
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(DirectoryCache::parse("dllwalk directory cache 0\n").is_none());
+    }
+}
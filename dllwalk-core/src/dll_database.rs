@@ -0,0 +1,917 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, error, info, warn};
+use petgraph::graph::{DiGraph, NodeIndex};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    inventory::InventoryFile,
+    manifest::AssemblyIdentity,
+    pe::{self, Architecture, Dependency, DependencyKind, Export, File, ImportedDll},
+    hijack::{self, UnknownPermissions},
+    search_path::{is_path_qualified, same_directory, SearchPath, SearchStep},
+    signature::SignatureStatus,
+    DllType,
+};
+
+/// A dll found by the walk, with its parsed headers
+#[derive(Debug)]
+pub struct DllInfo {
+    pub path: PathBuf,
+    pub dll_type: DllType,
+    pub file: File,
+    /// Size in bytes of the file, None for umbrella dlls and the dlls of an inventory
+    pub size: Option<u64>,
+    pub signature: Option<SignatureStatus>,
+    /// Subject of the signing certificate, or of the certificate signing its catalog
+    pub signer: Option<String>,
+    pub checksum_valid: Option<bool>,
+    /// Lowercase hexadecimal SHA-256 of the file, once `compute_hashes` was called
+    pub sha256: Option<String>,
+    pub api_set_host: Option<String>,
+    pub from_inventory: bool,
+}
+
+impl DllInfo {
+    pub fn architecture(&self) -> Option<Architecture> {
+        self.file.architecture
+    }
+
+    /// Whether the headers of the dll were parsed. Umbrella dlls are not backed by a
+    /// file and only the dependencies of the dlls of an inventory are known.
+    pub fn has_headers(&self) -> bool {
+        self.dll_type != DllType::Umbrella && !self.from_inventory
+    }
+
+    /// Whether a module mapped as a dependency is an executable. Kernel modules such
+    /// as ntoskrnl.exe are executables by design.
+    pub fn is_executable(&self) -> bool {
+        let exempt = matches!(self.dll_type, DllType::Kernel | DllType::Driver);
+        self.has_headers() && !exempt && !self.file.is_dll()
+    }
+
+    /// Dlls loaded along with this one. An umbrella dll has no file of its own and
+    /// depends on the host implementing its api set instead.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut dependencies = self.file.dependencies();
+        if let Some(host) = &self.api_set_host {
+            dependencies.push(Dependency {
+                name: host.to_owned(),
+                kind: DependencyKind::Import,
+            });
+        }
+
+        dependencies
+    }
+}
+
+/// A dll of the graph of a walk, by the name it was imported with. Missing dlls and the
+/// ones ignored by the configuration have no info.
+#[derive(Clone, Copy, Debug)]
+pub struct DllNode<'a> {
+    pub name: &'a str,
+    pub info: Option<&'a DllInfo>,
+}
+
+/// How the importer of an edge of the graph of a walk loads the imported dll
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImportEdge {
+    pub kind: DependencyKind,
+}
+
+/// What a walk does, in order. Each dll searched gets one event telling how it was
+/// resolved, followed by the edges to its dependencies when it was found.
+#[derive(Clone, Copy, Debug)]
+pub enum WalkEvent<'a> {
+    Resolved {
+        name: &'a str,
+        info: &'a DllInfo,
+    },
+    Missing {
+        name: &'a str,
+    },
+    /// Found at `path` but the file could not be read or is not a PE image
+    ParseError {
+        name: &'a str,
+        path: &'a Path,
+        error: &'a str,
+    },
+    /// Not searched, the configuration ignores it
+    Ignored {
+        name: &'a str,
+    },
+    /// `importer` loads `imported`, which is searched after this event unless it already
+    /// was
+    EdgeVisited {
+        importer: &'a str,
+        imported: &'a str,
+        kind: DependencyKind,
+    },
+}
+
+impl WalkEvent<'_> {
+    /// Name of the dll searched, or of the importer of an edge
+    pub fn name(&self) -> &str {
+        match self {
+            WalkEvent::Resolved { name, .. }
+            | WalkEvent::Missing { name }
+            | WalkEvent::ParseError { name, .. }
+            | WalkEvent::Ignored { name } => name,
+            WalkEvent::EdgeVisited { importer, .. } => importer,
+        }
+    }
+}
+
+/// The dlls searched by a walk, by the name they were imported with, found or missing
+pub struct DllDatabase {
+    files: HashMap<String, Option<DllInfo>>,
+    missing: HashSet<String>,
+    /// Path and error of the dlls found but not parsed
+    unreadable: HashMap<String, (PathBuf, String)>,
+    depths: HashMap<String, u32>,
+    load_flags: HashMap<String, u32>,
+    shadowed: HashMap<String, Vec<PathBuf>>,
+    ignored: HashSet<String>,
+    expected_directories: HashMap<String, PathBuf>,
+    search_path: SearchPath,
+    kernel_mode: bool,
+}
+
+impl DllDatabase {
+    /// Resolves the dlls with `search_path`, see `SearchPath::builder`
+    pub fn new(search_path: SearchPath) -> Self {
+        Self {
+            files: HashMap::new(),
+            missing: HashSet::new(),
+            unreadable: HashMap::new(),
+            depths: HashMap::new(),
+            load_flags: HashMap::new(),
+            shadowed: HashMap::new(),
+            ignored: HashSet::new(),
+            expected_directories: HashMap::new(),
+            search_path,
+            kernel_mode: false,
+        }
+    }
+
+    pub fn save_directory_cache(&mut self) {
+        self.search_path.save_directory_cache();
+    }
+
+    /// Resolves the dlls of another application of the same system, in `base_directory`.
+    /// The dlls found so far are dropped with the settings of the previous application: its
+    /// ignored dlls, expected locations, kernel mode, private directories and assemblies.
+    pub fn set_base_directory(&mut self, base_directory: &Path) -> Result<(), Box<dyn Error>> {
+        self.search_path.set_base_directory(base_directory)?;
+        self.files.clear();
+        self.missing.clear();
+        self.unreadable.clear();
+        self.depths.clear();
+        self.load_flags.clear();
+        self.shadowed.clear();
+        self.ignored.clear();
+        self.expected_directories.clear();
+        self.kernel_mode = false;
+        Ok(())
+    }
+
+    /// Resolves dlls with the kernel loader rules instead of the user mode search
+    /// order, for drivers
+    pub fn set_kernel_mode(&mut self, kernel_mode: bool) {
+        self.kernel_mode = kernel_mode;
+    }
+
+    pub fn add_private_directory(&mut self, directory: &Path) -> Result<(), Box<dyn Error>> {
+        self.search_path.add_private_directory(directory)
+    }
+
+    pub fn add_user_directory(&mut self, directory: &Path) -> Result<(), Box<dyn Error>> {
+        self.search_path.add_user_directory(directory)
+    }
+
+    pub fn set_safe_search_enabled(&mut self, enabled: bool) {
+        self.search_path.set_safe_search_enabled(enabled);
+    }
+
+    pub fn set_default_load_flags(&mut self, load_flags: u32) {
+        self.search_path.set_default_load_flags(load_flags);
+    }
+
+    pub fn add_side_by_side_assemblies(&mut self, assemblies: &[AssemblyIdentity]) {
+        self.search_path.add_side_by_side_assemblies(assemblies);
+    }
+
+    /// Never searches `name`, a dll that is not expected to be found
+    pub fn ignore_dll(&mut self, name: &str) {
+        self.ignored.insert(name.to_lowercase());
+    }
+
+    pub fn is_ignored(&self, name: &str) -> bool {
+        self.ignored.contains(&name.to_lowercase())
+    }
+
+    /// Expects `name` to be loaded from `directory`, a Windows path that is absolute or
+    /// relative to the application directory
+    pub fn expect_directory(&mut self, name: &str, directory: &str) {
+        let directory = self.search_path.application_path(directory);
+        self.expected_directories.insert(name.to_lowercase(), directory);
+    }
+
+    /// Directory `name` was expected to be loaded from, when it was loaded from another
+    pub fn get_unexpected_location(&self, name: &str) -> Option<&Path> {
+        let expected = self.expected_directories.get(&name.to_lowercase())?;
+        let directory = self.get_dll_info(name)?.path.parent()?;
+        (!same_directory(directory, expected)).then_some(expected.as_path())
+    }
+
+    pub fn search_path(&self) -> &SearchPath {
+        &self.search_path
+    }
+
+    /// Steps of the search of `name` with the search flags of its importer, or of the
+    /// process when the walk did not import it
+    pub fn explain(&self, name: &str) -> Vec<SearchStep> {
+        if self.kernel_mode {
+            return self.search_path.explain_kernel(name);
+        }
+
+        let load_flags = self
+            .load_flags
+            .iter()
+            .find(|(imported, _)| imported.eq_ignore_ascii_case(name))
+            .map_or(0, |(_, load_flags)| *load_flags);
+        self.search_path.explain(name, load_flags)
+    }
+
+    pub fn get_dll_info(&self, name: &str) -> Option<&DllInfo> {
+        if let Some(Some(info)) = self.files.get(name) {
+            return Some(info);
+        }
+
+        None
+    }
+
+    /// Records that the walk reached `name` through `depth` imports, the shortest chain
+    /// is kept
+    pub fn record_depth(&mut self, name: &str, depth: u32) {
+        let recorded = self.depths.entry(name.to_string()).or_insert(depth);
+        *recorded = (*recorded).min(depth);
+    }
+
+    /// Number of imports from the root to `name` on the shortest chain, 0 for the root
+    pub fn get_depth(&self, name: &str) -> Option<u32> {
+        self.depths.get(name).copied()
+    }
+
+    /// Whether `name` was searched and not found, as opposed to found but unreadable
+    pub fn is_missing(&self, name: &str) -> bool {
+        self.missing.contains(name)
+    }
+
+    /// Path and error of `name` when it was found but could not be read or parsed
+    pub fn get_parse_error(&self, name: &str) -> Option<(&Path, &str)> {
+        let (path, error) = self.unreadable.get(name)?;
+        Some((path.as_path(), error.as_str()))
+    }
+
+    pub fn search_dll(&mut self, name: &str, load_flags: u32) -> Option<&DllInfo> {
+        if self.is_ignored(name) {
+            self.files.entry(name.to_string()).or_insert(None);
+        }
+
+        if !self.files.contains_key(name) {
+            debug!("Searching for {}", name);
+
+            let found = if self.kernel_mode {
+                self.search_path.search_kernel(name)
+            } else {
+                self.load_flags.insert(name.to_string(), load_flags);
+                self.search_path.search(name, load_flags)
+            };
+
+            let info = match found {
+                Some((path, dll_type)) => {
+                    let path_str = path.to_string_lossy();
+                    info!(
+                        "Found {} ({})",
+                        if path_str.is_empty() { name } else { &path_str },
+                        dll_type
+                    );
+
+                    let mut info = match self.search_path.inventory_file(&path) {
+                        Some(file) => Some(DllDatabase::inventory_dll(path, dll_type, file)),
+                        None => self.parse_dll(name, path, dll_type),
+                    };
+                    if let Some(info) = info.as_mut() {
+                        if info.dll_type == DllType::Umbrella {
+                            info.api_set_host =
+                                self.search_path.resolve_api_set(name).map(str::to_owned);
+                        }
+                        if let Some(host) = &info.api_set_host {
+                            info!("Api set {} is implemented by {}", name, host);
+                        }
+                    }
+                    info
+                }
+                None => {
+                    error!("Could not find {}", name);
+                    self.missing.insert(name.to_string());
+                    None
+                }
+            };
+
+            self.files.insert(name.to_string(), info);
+        }
+
+        self.get_dll_info(name)
+    }
+
+    pub fn search_assembly(&mut self, name: &str) -> Option<&DllInfo> {
+        if self.is_ignored(name) {
+            self.files.entry(name.to_string()).or_insert(None);
+        }
+
+        if !self.files.contains_key(name) {
+            debug!("Searching for assembly {}", name);
+
+            let info = match self.search_path.search_assembly(name) {
+                Some((path, dll_type)) => {
+                    info!("Found {} ({})", path.to_string_lossy(), dll_type);
+                    self.parse_dll(name, path, dll_type)
+                }
+                None => {
+                    error!("Could not find assembly {}", name);
+                    self.missing.insert(name.to_string());
+                    None
+                }
+            };
+
+            self.files.insert(name.to_string(), info);
+        }
+
+        self.get_dll_info(name)
+    }
+
+    /// Searches `name` and the dlls it loads, directly or not, and calls `visit` with the
+    /// events of each dll right after it is searched. The depth of each dll is recorded, a
+    /// dll found again through a shorter chain is walked again to update the depth of its
+    /// imports, without new events.
+    pub fn walk(&mut self, name: &str, mut visit: impl FnMut(WalkEvent)) {
+        let mut visited = HashSet::new();
+        let mut queue = Vec::new();
+        queue.push((
+            Dependency {
+                name: name.to_owned(),
+                kind: DependencyKind::Import,
+            },
+            0,
+            0,
+        ));
+
+        while let Some((dependency, load_flags, depth)) = queue.pop() {
+            self.record_depth(&dependency.name, depth);
+
+            let info = match dependency.kind {
+                DependencyKind::Assembly => self.search_assembly(&dependency.name),
+                _ => self.search_dll(&dependency.name, load_flags),
+            };
+
+            // The importer's DependentLoadFlags drive the search for its own imports
+            let (dependencies, load_flags) = match info {
+                Some(info) => (info.dependencies(), info.file.dependent_load_flags as u32),
+                None => (Vec::new(), 0),
+            };
+            for dll in dependencies {
+                let shorter = self.get_depth(&dll.name).map_or(true, |known| known > depth + 1);
+                if !visited.contains(&dll.name) || shorter {
+                    queue.push((dll, load_flags, depth + 1));
+                }
+            }
+
+            if !visited.contains(&dependency.name) {
+                self.emit_events(&dependency.name, &mut visit);
+            }
+            visited.insert(dependency.name);
+        }
+    }
+
+    /// Graph of the dlls searched, with an edge from each dll to each of its dependencies,
+    /// for the algorithms of petgraph. An umbrella dll has an import edge to the host of its
+    /// api set. Names are compared ignoring case, a dll imported with several spellings is a
+    /// single node, and nodes are added in the order of their names.
+    ///
+    /// Nodes borrow from the database rather than owning a `DllInfo`: missing and ignored
+    /// dlls have no info, and the parsed headers are not copied for each graph.
+    ///
+    /// ```no_run
+    /// # fn print_cycles(database: &dllwalk_core::DllDatabase) {
+    /// use dllwalk_core::petgraph::algo::kosaraju_scc;
+    ///
+    /// let graph = database.to_graph();
+    /// for component in kosaraju_scc(&graph).into_iter().filter(|dlls| dlls.len() > 1) {
+    ///     let names: Vec<&str> = component.iter().map(|&index| graph[index].name).collect();
+    ///     println!("Import cycle: {}", names.join(", "));
+    /// }
+    /// # }
+    /// ```
+    pub fn to_graph(&self) -> DiGraph<DllNode<'_>, ImportEdge> {
+        // The spelling that was found is the node of its dll, the first one in byte order
+        // when several were, so that the graph does not depend on the order of the map
+        let mut names: Vec<(&String, &Option<DllInfo>)> = self.files.iter().collect();
+        names.sort_by_key(|(name, info)| (name.to_lowercase(), info.is_none(), *name));
+        names.dedup_by_key(|(name, _)| name.to_lowercase());
+
+        let mut graph = DiGraph::with_capacity(names.len(), 0);
+        let indices: HashMap<String, NodeIndex> = names
+            .iter()
+            .map(|(name, info)| {
+                let node = DllNode {
+                    name,
+                    info: info.as_ref(),
+                };
+                (name.to_lowercase(), graph.add_node(node))
+            })
+            .collect();
+
+        for (name, info) in names {
+            let info = match info {
+                Some(info) => info,
+                None => continue,
+            };
+            // Dependencies not searched yet have no node
+            for dependency in info.dependencies() {
+                if let Some(&imported) = indices.get(&dependency.name.to_lowercase()) {
+                    let edge = ImportEdge {
+                        kind: dependency.kind,
+                    };
+                    graph.add_edge(indices[&name.to_lowercase()], imported, edge);
+                }
+            }
+        }
+        graph
+    }
+
+    fn emit_events(&self, name: &str, visit: &mut impl FnMut(WalkEvent)) {
+        let info = match self.get_dll_info(name) {
+            Some(info) => info,
+            None => {
+                let event = if let Some((path, error)) = self.get_parse_error(name) {
+                    WalkEvent::ParseError { name, path, error }
+                } else if self.is_ignored(name) {
+                    WalkEvent::Ignored { name }
+                } else {
+                    WalkEvent::Missing { name }
+                };
+                return visit(event);
+            }
+        };
+
+        visit(WalkEvent::Resolved { name, info });
+        for dependency in info.dependencies() {
+            visit(WalkEvent::EdgeVisited {
+                importer: name,
+                imported: &dependency.name,
+                kind: dependency.kind,
+            });
+        }
+    }
+
+    /// Names of the dlls searched, sorted by name so the output does not change between runs
+    pub fn get_all_dlls(&self) -> Vec<String> {
+        let mut names = self.files.keys().map(|key| key.to_owned()).collect::<Vec<_>>();
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    }
+
+    #[cfg(windows)]
+    pub fn verify_signatures(&mut self) {
+        for info in self.files.values_mut().flatten() {
+            if info.has_headers() {
+                debug!("Verifying the signature of {}", info.path.to_string_lossy());
+                let signature = crate::signature::verify(&info.path);
+                info.signature = Some(signature.status);
+                info.signer = signature.signer;
+            }
+        }
+    }
+
+    // Signatures are checked by WinTrust, which only exists on Windows
+    #[cfg(not(windows))]
+    pub fn verify_signatures(&mut self) {
+        warn!("Signatures can only be verified on Windows");
+    }
+
+    pub fn verify_checksums(&mut self) {
+        let files = self.search_path.file_provider();
+        for info in self.files.values_mut().flatten() {
+            // A zero checksum means the linker did not set one
+            if info.path.as_os_str().is_empty() || info.file.checksum == 0 {
+                continue;
+            }
+
+            match files.read(&info.path) {
+                Ok(data) => {
+                    info.checksum_valid = pe::compute_checksum(&data)
+                        .map(|checksum| checksum == info.file.checksum);
+                }
+                Err(err) => error!("Failed to read {}: {}", info.path.to_string_lossy(), err),
+            }
+        }
+    }
+
+    /// Hashes the files of the dlls, the dlls of an inventory are on another machine
+    pub fn compute_hashes(&mut self) {
+        let files = self.search_path.file_provider();
+        for info in self.files.values_mut().flatten() {
+            if !info.has_headers() {
+                continue;
+            }
+
+            match files.read(&info.path) {
+                Ok(data) => info.sha256 = Some(format!("{:x}", Sha256::digest(&data[..]))),
+                Err(err) => error!("Failed to read {}: {}", info.path.to_string_lossy(), err),
+            }
+        }
+    }
+
+    /// Finds the other files named like each dll in its search order, which the loaded
+    /// one shadows
+    pub fn find_shadowed_dlls(&mut self) {
+        for (name, info) in &self.files {
+            let info = match info {
+                Some(info) if !matches!(info.dll_type, DllType::Umbrella | DllType::Managed) => {
+                    info
+                }
+                _ => continue,
+            };
+
+            let candidates = if self.kernel_mode {
+                self.search_path.search_all_kernel(name)
+            } else {
+                let load_flags = self.load_flags.get(name).copied().unwrap_or_default();
+                self.search_path.search_all(name, load_flags)
+            };
+
+            let shadowed: Vec<PathBuf> = candidates
+                .into_iter()
+                .map(|(path, _)| path)
+                .filter(|path| !same_directory(path, &info.path))
+                .collect();
+            if !shadowed.is_empty() {
+                debug!("{} shadows {} other files", info.path.to_string_lossy(), shadowed.len());
+                self.shadowed.insert(name.to_owned(), shadowed);
+            }
+        }
+    }
+
+    /// Files named like `name` that are found after the loaded one, once
+    /// `find_shadowed_dlls` was called
+    pub fn get_shadowed_dlls(&self, name: &str) -> &[PathBuf] {
+        self.shadowed.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Number of functions imported from `name` by all the dlls of the walk
+    pub fn get_imported_symbol_count(&self, name: &str) -> usize {
+        self.files
+            .values()
+            .flatten()
+            .filter_map(|info| info.file.imported_symbol_count(name))
+            .sum()
+    }
+
+    /// Dlls of the walk exporting the function `symbol`, sorted by name. The exports of the
+    /// dlls of an inventory are not known.
+    pub fn find_exporters(&self, symbol: &str) -> Vec<(&str, &DllInfo, &Export)> {
+        let mut exporters: Vec<_> = self
+            .files
+            .iter()
+            .filter_map(|(name, info)| {
+                let info = info.as_ref().filter(|info| info.has_headers())?;
+                Some((name.as_str(), info, info.file.exports.find(symbol)?))
+            })
+            .collect();
+        exporters.sort_by_key(|(name, ..)| name.to_lowercase());
+        exporters
+    }
+
+    pub fn get_architecture_mismatches(&self) -> Vec<(&str, &str)> {
+        let mut mismatches = Vec::new();
+        for (importer, info) in &self.files {
+            if let Some(info) = info {
+                for dll in info.dependencies() {
+                    if let Some((imported, imported_info)) = self.files.get_key_value(&dll.name) {
+                        if let (Some(expected), Some(found)) = (
+                            info.architecture(),
+                            imported_info.as_ref().and_then(|info| info.architecture()),
+                        ) {
+                            if !expected.is_compatible_with(found) {
+                                mismatches.push((importer.as_str(), imported.as_str()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        mismatches
+    }
+
+    /// Dependencies that are executables: the loader maps them but never runs
+    /// their entry point, so their runtime is left uninitialized
+    pub fn get_executable_imports(&self) -> Vec<(&str, &str)> {
+        let mut executables = Vec::new();
+        for (importer, info) in &self.files {
+            if let Some(info) = info {
+                for dll in info.dependencies() {
+                    if let Some((imported, Some(imported_info))) =
+                        self.files.get_key_value(&dll.name)
+                    {
+                        if imported_info.is_executable() {
+                            executables.push((importer.as_str(), imported.as_str()));
+                        }
+                    }
+                }
+            }
+        }
+
+        executables
+    }
+
+    /// Directories where a standard user can plant a dll that is loaded instead of the
+    /// resolved one, the directories searched before it or every directory for a
+    /// missing dll
+    pub fn get_hijackable_dlls(&self) -> Result<Vec<(&str, &Path)>, UnknownPermissions> {
+        let mut hijackable = Vec::new();
+        let windows_directory = self.search_path.windows_directory();
+
+        // Known dlls, activation contexts, api sets and path-qualified imports are resolved
+        // without searching
+        for (name, load_flags) in &self.load_flags {
+            if is_path_qualified(name) {
+                continue;
+            }
+
+            let resolved_directory = match self.files.get(name) {
+                Some(Some(info)) => match info.dll_type {
+                    DllType::User | DllType::Path | DllType::System => info.path.parent(),
+                    _ => continue,
+                },
+                _ => None,
+            };
+
+            for location in self.search_path.search_order(*load_flags) {
+                let directory = location.directory;
+                if resolved_directory.map_or(false, |resolved| same_directory(directory, resolved))
+                {
+                    break;
+                }
+
+                if !directory.as_os_str().is_empty()
+                    && hijack::is_writable(directory, windows_directory)?
+                {
+                    hijackable.push((name.as_str(), directory));
+                }
+            }
+        }
+
+        Ok(hijackable)
+    }
+
+    // The dll is on another machine, only its architecture and dependencies are known
+    fn inventory_dll(path: PathBuf, dll_type: DllType, inventory_file: &InventoryFile) -> DllInfo {
+        let mut file = File::new();
+        file.architecture = inventory_file.machine.and_then(Architecture::from_machine);
+        file.imports = inventory_file
+            .dependencies
+            .iter()
+            .map(|name| ImportedDll {
+                name: name.to_owned(),
+                symbols: Vec::new(),
+            })
+            .collect();
+
+        DllInfo {
+            path,
+            dll_type,
+            file,
+            size: None,
+            signature: None,
+            signer: None,
+            checksum_valid: None,
+            sha256: None,
+            api_set_host: None,
+            from_inventory: true,
+        }
+    }
+
+    // Errors are recorded for the walk events
+    fn parse_dll(&mut self, name: &str, path: PathBuf, dll_type: DllType) -> Option<DllInfo> {
+        if dll_type == DllType::Umbrella {
+            return Some(DllInfo {
+                path,
+                dll_type,
+                file: File::new(),
+                size: None,
+                signature: None,
+                signer: None,
+                checksum_valid: None,
+                sha256: None,
+                api_set_host: None,
+                from_inventory: false,
+            });
+        }
+
+        debug!("Parsing {}", path.to_string_lossy());
+        match self.search_path.file_provider().read(&path) {
+            Ok(data) => match File::parse_from(&data) {
+                Ok(file) => {
+                    for warning in &file.warnings {
+                        warn!("Partially parsed {}: {}", path.to_string_lossy(), warning);
+                    }
+
+                    Some(DllInfo {
+                        path,
+                        dll_type,
+                        file,
+                        size: Some(data.len() as u64),
+                        signature: None,
+                        signer: None,
+                        checksum_valid: None,
+                        sha256: None,
+                        api_set_host: None,
+                        from_inventory: false,
+                    })
+                }
+                Err(err) => {
+                    error!("Failed to parse {}: {}", path.to_string_lossy(), err);
+                    self.unreadable.insert(name.to_string(), (path, err.to_string()));
+                    None
+                }
+            },
+            Err(err) => {
+                error!("Failed to read {}: {}", path.to_string_lossy(), err);
+                self.unreadable.insert(name.to_string(), (path, err.to_string()));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::file_provider::MemoryFiles;
+
+    fn append(image: &mut Vec<u8>, data: &[u8]) -> u32 {
+        let rva = image.len() as u32;
+        image.extend_from_slice(data);
+        rva
+    }
+
+    // x64 dll without sections, its tables are in the headers, which are mapped as is
+    fn image(imports: &[&str], exports: &[&str]) -> Vec<u8> {
+        let mut image = vec![0; 0x148];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c] = 0x40;
+        image[0x40..0x46].copy_from_slice(&[0x50, 0x45, 0x00, 0x00, 0x64, 0x86]);
+        image[0x54..0x58].copy_from_slice(&[0xf0, 0x00, 0x00, 0x20]);
+        image[0x58..0x5a].copy_from_slice(&0x20bu16.to_le_bytes());
+        image[0xc4] = 16;
+
+        if !imports.is_empty() {
+            let mut descriptors = Vec::new();
+            for dll in imports {
+                let name = append(&mut image, format!("{}\0", dll).as_bytes());
+                let lookup_table = append(&mut image, &[0; 8]);
+                descriptors.extend_from_slice(&lookup_table.to_le_bytes());
+                descriptors.extend_from_slice(&[0; 8]);
+                descriptors.extend_from_slice(&name.to_le_bytes());
+                descriptors.extend_from_slice(&lookup_table.to_le_bytes());
+            }
+            descriptors.extend_from_slice(&[0; 20]);
+            let rva = append(&mut image, &descriptors);
+            image[0xd0..0xd4].copy_from_slice(&rva.to_le_bytes());
+            image[0xd4..0xd8].copy_from_slice(&(descriptors.len() as u32).to_le_bytes());
+        }
+
+        if !exports.is_empty() {
+            let name = append(&mut image, b"image.dll\0");
+            let (mut functions, mut names, mut ordinals) = (Vec::new(), Vec::new(), Vec::new());
+            for (index, export) in exports.iter().enumerate() {
+                let rva = append(&mut image, format!("{}\0", export).as_bytes());
+                functions.extend_from_slice(&(0x1000 + index as u32).to_le_bytes());
+                names.extend_from_slice(&rva.to_le_bytes());
+                ordinals.extend_from_slice(&(index as u16).to_le_bytes());
+            }
+            let count = exports.len() as u32;
+            let tables = [
+                append(&mut image, &functions),
+                append(&mut image, &names),
+                append(&mut image, &ordinals),
+            ];
+
+            let mut directory = vec![0; 12];
+            for value in [name, 1, count, count, tables[0], tables[1], tables[2]] {
+                directory.extend_from_slice(&value.to_le_bytes());
+            }
+            let rva = append(&mut image, &directory);
+            image[0xc8..0xcc].copy_from_slice(&rva.to_le_bytes());
+            image[0xcc..0xd0].copy_from_slice(&(directory.len() as u32).to_le_bytes());
+        }
+
+        let size_of_headers = image.len() as u32;
+        image[0x94..0x98].copy_from_slice(&size_of_headers.to_le_bytes());
+        image
+    }
+
+    // The files are in the application directory
+    fn database(files: &[(&str, &[u8])]) -> DllDatabase {
+        let system_root = Path::new("/image");
+        let mut memory_files = MemoryFiles::new();
+        memory_files.insert("/image/Windows/System32/ntdll.dll", image(&[], &[]));
+        for (name, data) in files {
+            memory_files.insert(system_root.join("App").join(name), *data);
+        }
+
+        let search_path = SearchPath::builder(&system_root.join("App"))
+            .system_root(Some(system_root))
+            .file_provider(memory_files)
+            .build()
+            .unwrap();
+        DllDatabase::new(search_path)
+    }
+
+    #[test]
+    fn hashes() {
+        let app = image(&["zlib1.dll"], &[]);
+        let mut database = database(&[("app.exe", &app), ("zlib1.dll", b"MZ")]);
+        database.walk("app.exe", |_| {});
+        assert_eq!(database.get_dll_info("app.exe").unwrap().sha256, None);
+
+        database.compute_hashes();
+        let sha256 = database.get_dll_info("app.exe").unwrap().sha256.clone().unwrap();
+        assert_eq!(sha256, format!("{:x}", Sha256::digest(&app)));
+    }
+    #[test]
+    fn exporters() {
+        let app = image(&["zlib1.dll", "zlib2.dll"], &[]);
+        let zlib1 = image(&[], &["deflate", "inflate"]);
+        let zlib2 = image(&[], &["inflate"]);
+        let files: [(&str, &[u8]); 3] =
+            [("app.exe", &app), ("zlib1.dll", &zlib1), ("zlib2.dll", &zlib2)];
+        let mut database = database(&files);
+        database.walk("app.exe", |_| {});
+
+        let exporters: Vec<_> = database
+            .find_exporters("inflate")
+            .into_iter()
+            .map(|(name, _, export)| (name, export.ordinal))
+            .collect();
+        assert_eq!(exporters, [("zlib1.dll", 2), ("zlib2.dll", 1)]);
+        assert!(database.find_exporters("Inflate").is_empty());
+    }
+    #[test]
+    fn walk_events() {
+        let app = image(&["zlib1.dll", "missing.dll", "plugin.dll", "broken.dll"], &[]);
+        let zlib1 = image(&["app.exe"], &[]);
+        let files: [(&str, &[u8]); 3] =
+            [("app.exe", &app), ("zlib1.dll", &zlib1), ("broken.dll", b"MZ")];
+        let mut database = database(&files);
+        database.ignore_dll("plugin.dll");
+
+        let mut events = Vec::new();
+        database.walk("app.exe", |event| {
+            events.push(match event {
+                WalkEvent::Resolved { name, info } => format!("{} {:?}", name, info.dll_type),
+                WalkEvent::Missing { name } => format!("{} missing", name),
+                WalkEvent::ParseError { name, .. } => format!("{} unreadable", name),
+                WalkEvent::Ignored { name } => format!("{} ignored", name),
+                WalkEvent::EdgeVisited {
+                    importer, imported, ..
+                } => format!("{} -> {}", importer, imported),
+            })
+        });
+
+        // Dependencies are searched last first, the cycle back to app.exe has no new events
+        assert_eq!(
+            events,
+            [
+                "app.exe User",
+                "app.exe -> zlib1.dll",
+                "app.exe -> missing.dll",
+                "app.exe -> plugin.dll",
+                "app.exe -> broken.dll",
+                "broken.dll unreadable",
+                "plugin.dll ignored",
+                "missing.dll missing",
+                "zlib1.dll User",
+                "zlib1.dll -> app.exe",
+            ]
+        );
+        assert_eq!(database.get_depth("zlib1.dll"), Some(1));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, ErrorKind},
+    ops::Deref,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use memmap2::Mmap;
+
+/// What the resolution needs to know of a file or directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    /// Size in bytes of a file
+    pub len: u64,
+    /// None when the provider does not track modifications, the listings of its
+    /// directories are then never cached
+    pub modified: Option<SystemTime>,
+}
+
+/// Contents of a file, mapped in memory when it is read from a disk
+pub enum FileData {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Mapped(data) => data,
+            FileData::Owned(data) => data,
+        }
+    }
+}
+
+impl AsRef<[u8]> for FileData {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Where `SearchPath` lists the directories it searches and where `DllDatabase` reads the
+/// files it parses. Paths are the ones given to them, like the directories of a system root.
+/// The registry of an image and the signatures of the files are still read from the disk.
+pub trait FileProvider: std::fmt::Debug {
+    /// Names of the files and subdirectories of a directory
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<String>>;
+
+    fn read(&self, path: &Path) -> io::Result<FileData>;
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).map_or(false, |metadata| !metadata.is_dir)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).map_or(false, |metadata| metadata.is_dir)
+    }
+}
+
+/// The file system of the machine running dllwalk
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalFiles;
+
+impl FileProvider for LocalFiles {
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        Ok(std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<FileData> {
+        map_file(path).map(FileData::Mapped)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Maps a file in memory rather than reading it, dlls can be huge and only their
+/// headers and a few directories are looked at
+pub fn map_file(path: &Path) -> std::io::Result<Mmap> {
+    let file = std::fs::File::open(path)?;
+
+    // Safety: the mapping is read only and short lived, like any memory mapped
+    // reader this assumes the file is not truncated while it is parsed
+    unsafe { Mmap::map(&file) }
+}
+
+/// Files held in memory, like the ones of an archive or the tree of a test. Directories
+/// exist as long as they hold a file.
+#[derive(Debug, Default)]
+pub struct MemoryFiles {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryFiles {
+    pub fn new() -> Self {
+        MemoryFiles::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<PathBuf>, data: impl Into<Vec<u8>>) {
+        self.files.insert(path.into(), data.into());
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(ErrorKind::NotFound, format!("{} not found", path.to_string_lossy()))
+    }
+}
+
+impl FileProvider for MemoryFiles {
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        let names: BTreeSet<String> = self
+            .files
+            .keys()
+            .filter_map(|file| file.strip_prefix(path).ok()?.iter().next())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        if names.is_empty() {
+            return Err(MemoryFiles::not_found(path));
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn read(&self, path: &Path) -> io::Result<FileData> {
+        match self.files.get(path) {
+            Some(data) => Ok(FileData::Owned(data.clone())),
+            None => Err(MemoryFiles::not_found(path)),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if let Some(data) = self.files.get(path) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: data.len() as u64,
+                modified: None,
+            });
+        }
+
+        if !self.files.keys().any(|file| file.starts_with(path)) {
+            return Err(MemoryFiles::not_found(path));
+        }
+        Ok(Metadata {
+            is_dir: true,
+            len: 0,
+            modified: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_files() {
+        let mut files = MemoryFiles::new();
+        files.insert("/app/app.exe", b"MZ".to_vec());
+        files.insert("/app/plugins/engine.dll", []);
+
+        assert_eq!(files.list_dir(Path::new("/app")).unwrap(), vec!["app.exe", "plugins"]);
+        assert!(files.list_dir(Path::new("/missing")).is_err());
+        assert!(files.is_file(Path::new("/app/app.exe")));
+        assert!(files.is_dir(Path::new("/app/plugins")));
+        assert!(!files.is_dir(Path::new("/ap")));
+        assert_eq!(&*files.read(Path::new("/app/app.exe")).unwrap(), b"MZ");
+        assert_eq!(files.metadata(Path::new("/app/app.exe")).unwrap().len, 2);
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+
+#[cfg(windows)]
+mod acl;
+
+/// Why the directories a standard user can write to are not known
+#[derive(Debug)]
+pub enum UnknownPermissions {
+    /// Outside of Windows, the default permissions are assumed from the Windows directory
+    /// of a system root
+    NoSystemRoot,
+    /// The DACL of a directory could not be read
+    UnreadableDacl(PathBuf, std::io::Error),
+}
+
+impl std::error::Error for UnknownPermissions {}
+
+impl std::fmt::Display for UnknownPermissions {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnknownPermissions::NoSystemRoot => {
+                write!(
+                    formatter,
+                    "the permissions are unknown without a system root"
+                )
+            }
+            UnknownPermissions::UnreadableDacl(directory, error) => write!(
+                formatter,
+                "cannot read the permissions of {}: {}",
+                directory.display(),
+                error
+            ),
+        }
+    }
+}
+
+/// Whether a standard user can create files in `directory`, or create the directory
+/// when it does not exist yet, and plant a dll there
+#[cfg(windows)]
+pub fn is_writable(
+    directory: &Path,
+    _windows_directory: &Path,
+) -> Result<bool, UnknownPermissions> {
+    acl::is_writable(directory)
+}
+
+// Without ACLs, the default ones of a Windows installation are assumed: the Windows and
+// program files directories are reserved to administrators, users can create and write
+// the directories created anywhere else on the system drive. Without the Windows directory
+// of a system root, nothing is known of the system drive.
+#[cfg(not(windows))]
+pub fn is_writable(directory: &Path, windows_directory: &Path) -> Result<bool, UnknownPermissions> {
+    let system_drive = windows_directory
+        .parent()
+        .ok_or(UnknownPermissions::NoSystemRoot)?;
+
+    let protected = [
+        windows_directory.to_owned(),
+        system_drive.join("Program Files"),
+        system_drive.join("Program Files (x86)"),
+    ];
+    Ok(!protected
+        .iter()
+        .any(|protected| is_within(directory, protected)))
+}
+
+// Windows paths are case insensitive, whatever the file system they are stored on
+#[cfg(not(windows))]
+fn is_within(path: &Path, parent: &Path) -> bool {
+    let mut components = path.components();
+    parent.components().all(|parent_component| {
+        components.next().map_or(false, |component| {
+            let component = component.as_os_str().to_string_lossy();
+            component.eq_ignore_ascii_case(&parent_component.as_os_str().to_string_lossy())
+        })
+    })
+}
+
+#[cfg(all(test, not(windows)))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_writable() {
+        let windows_directory = Path::new("/mnt/image/Windows");
+        let is_writable =
+            |directory: &str| super::is_writable(Path::new(directory), windows_directory).unwrap();
+
+        assert!(!is_writable("/mnt/image/windows/System32"));
+        assert!(!is_writable("/mnt/image/PROGRAM FILES (X86)/App"));
+        assert!(is_writable("/mnt/image/Python39/Scripts"));
+        assert!(is_writable("/mnt/image/Program Files Data"));
+
+        // No system root
+        assert!(matches!(
+            super::is_writable(Path::new("/opt/App"), Path::new("")),
+            Err(UnknownPermissions::NoSystemRoot)
+        ));
+    }
+}
@@ -0,0 +1,136 @@
+use std::{path::Path, ptr};
+
+use bindings::Windows::Win32::{
+    Foundation::{PSID, PWSTR},
+    Security::{
+        Authorization::{ConvertStringSidToSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT},
+        EqualSid, GetAce, ACCESS_ALLOWED_ACE, ACL,
+    },
+    System::{Diagnostics::Debug::ERROR_SUCCESS, Memory::LocalFree},
+};
+
+use super::UnknownPermissions;
+use crate::long_path::to_extended_length;
+
+// Access rights, ACE fields and security information of winnt.h
+const DACL_SECURITY_INFORMATION: u32 = 0x00000004;
+
+const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+const ACCESS_DENIED_ACE_TYPE: u8 = 1;
+const INHERIT_ONLY_ACE: u8 = 0x08;
+
+const FILE_ADD_FILE: u32 = 0x00000002;
+const FILE_ADD_SUBDIRECTORY: u32 = 0x00000004;
+const GENERIC_ALL: u32 = 0x10000000;
+const GENERIC_WRITE: u32 = 0x40000000;
+
+// Everyone, Interactive, Authenticated Users and Users, the groups of any standard user
+const STANDARD_USER_SIDS: [&str; 4] = ["S-1-1-0", "S-1-5-4", "S-1-5-11", "S-1-5-32-545"];
+
+/// Whether the DACL of `directory` lets a standard user create files in it, or create
+/// it in the closest existing parent
+pub fn is_writable(directory: &Path) -> Result<bool, UnknownPermissions> {
+    let mut rights = FILE_ADD_FILE;
+    let mut directory = directory;
+    while !directory.is_dir() {
+        rights = FILE_ADD_SUBDIRECTORY;
+        directory = match directory.parent() {
+            Some(parent) => parent,
+            None => return Ok(false),
+        };
+    }
+
+    let sids: Vec<PSID> = STANDARD_USER_SIDS
+        .iter()
+        .filter_map(|sid| {
+            let mut sid_wide = to_wide(sid);
+            let mut converted = PSID::default();
+            let result =
+                unsafe { ConvertStringSidToSidW(PWSTR(sid_wide.as_mut_ptr()), &mut converted) };
+            result.as_bool().then_some(converted)
+        })
+        .collect();
+
+    let mut path_wide = to_wide(&to_extended_length(directory).to_string_lossy());
+    let mut dacl = ptr::null_mut();
+    let mut security_descriptor = ptr::null_mut();
+    let error_code = unsafe {
+        GetNamedSecurityInfoW(
+            PWSTR(path_wide.as_mut_ptr()),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut dacl,
+            ptr::null_mut(),
+            &mut security_descriptor,
+        )
+    };
+
+    // A null DACL grants full access to everyone
+    let writable = if error_code == ERROR_SUCCESS.0 {
+        Ok(dacl.is_null() || grants(dacl, &sids, rights))
+    } else {
+        let error = std::io::Error::from_raw_os_error(error_code as i32);
+        Err(UnknownPermissions::UnreadableDacl(
+            directory.to_owned(),
+            error,
+        ))
+    };
+
+    unsafe {
+        if !security_descriptor.is_null() {
+            LocalFree(security_descriptor as isize);
+        }
+        for sid in sids {
+            LocalFree(sid.0);
+        }
+    }
+
+    writable
+}
+
+// Denied rights win over allowed ones whatever the order of the entries, like in a
+// canonical DACL. Allowed and denied entries share the layout of ACCESS_ALLOWED_ACE.
+fn grants(dacl: *mut ACL, sids: &[PSID], rights: u32) -> bool {
+    let mut allowed = false;
+    let ace_count = unsafe { (*dacl).AceCount };
+
+    for index in 0..ace_count as u32 {
+        let mut ace = ptr::null_mut();
+        if !unsafe { GetAce(dacl, index, &mut ace) }.as_bool() {
+            continue;
+        }
+        let ace = ace as *const ACCESS_ALLOWED_ACE;
+        let header = unsafe { &(*ace).Header };
+
+        if header.AceFlags & INHERIT_ONLY_ACE != 0 {
+            continue;
+        }
+
+        let sid = PSID(unsafe { ptr::addr_of!((*ace).SidStart) } as isize);
+        if !sids
+            .iter()
+            .any(|user_sid| unsafe { EqualSid(sid, *user_sid) }.as_bool())
+        {
+            continue;
+        }
+
+        let mask = unsafe { (*ace).Mask };
+        if mask & (rights | GENERIC_WRITE | GENERIC_ALL) == 0 {
+            continue;
+        }
+
+        match header.AceType {
+            ACCESS_DENIED_ACE_TYPE => return false,
+            ACCESS_ALLOWED_ACE_TYPE => allowed = true,
+            _ => {}
+        }
+    }
+
+    allowed
+}
+
+fn to_wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    file_provider::FileProvider,
+    pe::{ApiSetSchema, Architecture, DependencyKind, File},
+    search_path::{find_directory, same_directory, SearchPath},
+};
+
+const INVENTORY_VERSION: u32 = 1;
+
+/// Directories and loader settings of a machine, captured by the snapshot command to
+/// resolve dlls like on that machine
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Inventory {
+    pub version: u32,
+    pub windows_directory: PathBuf,
+    pub system_directory: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wow64_directory: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arm32_directory: Option<PathBuf>,
+    pub drivers_directory: PathBuf,
+    pub safe_search_enabled: bool,
+    pub known_dlls: Vec<String>,
+    pub path: Vec<PathBuf>,
+    /// Hosts of the api sets, by api set name without its revision
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub api_sets: BTreeMap<String, String>,
+    pub directories: BTreeMap<PathBuf, BTreeMap<String, InventoryFile>>,
+}
+
+/// A file of the machine, with the headers needed to walk its dependencies when it is
+/// an image
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct InventoryFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub machine: Option<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+impl Inventory {
+    /// Captures the directories searched on the system `search_path` was created for
+    pub fn capture(search_path: &SearchPath) -> Self {
+        let files = search_path.file_provider();
+        let windows_directory = search_path.windows_directory();
+        let existing = |name| {
+            let directory = find_directory(files, windows_directory, name);
+            files.is_dir(&directory).then_some(directory)
+        };
+
+        let mut inventory = Inventory {
+            version: INVENTORY_VERSION,
+            windows_directory: windows_directory.to_owned(),
+            system_directory: search_path.system_directory().to_owned(),
+            wow64_directory: existing("SysWOW64"),
+            arm32_directory: existing("SysArm32"),
+            drivers_directory: search_path.drivers_directory().to_owned(),
+            safe_search_enabled: search_path.is_safe_search_enabled(),
+            known_dlls: search_path
+                .known_dlls()
+                .into_iter()
+                .filter(|(_, _, importer)| importer.is_none())
+                .map(|(name, _, _)| name.to_owned())
+                .collect(),
+            path: search_path
+                .path_directories()
+                .into_iter()
+                .map(Path::to_owned)
+                .collect(),
+            api_sets: search_path
+                .api_set_schema()
+                .into_iter()
+                .flat_map(ApiSetSchema::hosts)
+                .map(|(name, host)| (name.to_owned(), host.to_owned()))
+                .collect(),
+            directories: BTreeMap::new(),
+        };
+
+        let mut directories = vec![
+            inventory.windows_directory.clone(),
+            inventory.system_directory.clone(),
+            inventory.drivers_directory.clone(),
+        ];
+        directories.extend(inventory.wow64_directory.clone());
+        directories.extend(inventory.arm32_directory.clone());
+        directories.extend(inventory.path.iter().cloned());
+
+        for directory in directories {
+            if let Entry::Vacant(entry) = inventory.directories.entry(directory) {
+                let directory_files = read_directory(files, entry.key());
+                entry.insert(directory_files);
+            }
+        }
+
+        inventory
+    }
+
+    /// System directory of the processes of `architecture`, redirected by WOW64
+    pub fn process_system_directory(&self, architecture: Option<Architecture>) -> &Path {
+        let redirected = match architecture {
+            Some(Architecture::X86) => self.wow64_directory.as_deref(),
+            Some(Architecture::Arm) => self.arm32_directory.as_deref(),
+            _ => None,
+        };
+        redirected.unwrap_or(&self.system_directory)
+    }
+
+    /// Api set schema of the machine, None for the inventories captured without it
+    pub fn api_set_schema(&self) -> Option<ApiSetSchema> {
+        if self.api_sets.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.api_sets
+                .iter()
+                .map(|(name, host)| (name.clone(), host.clone()))
+                .collect(),
+        )
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let inventory: Inventory = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if inventory.version != INVENTORY_VERSION {
+            return Err(format!("unsupported inventory version {}", inventory.version).into());
+        }
+
+        Ok(inventory)
+    }
+
+    pub fn save(&self, mut writer: impl Write) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Files of `directory` by name, the paths of the machine are case insensitive
+    pub fn directory(&self, directory: &Path) -> Option<&BTreeMap<String, InventoryFile>> {
+        self.directories
+            .iter()
+            .find(|(path, _)| same_directory(path, directory))
+            .map(|(_, files)| files)
+    }
+
+    pub fn file(&self, path: &Path) -> Option<&InventoryFile> {
+        let name = path.file_name()?.to_string_lossy();
+        self.directory(path.parent()?)?
+            .iter()
+            .find(|(file_name, _)| file_name.eq_ignore_ascii_case(&name))
+            .map(|(_, file)| file)
+    }
+}
+
+// Missing directories are captured as empty ones, the loader skips them all the same
+fn read_directory(
+    file_provider: &dyn FileProvider,
+    directory: &Path,
+) -> BTreeMap<String, InventoryFile> {
+    let mut files = BTreeMap::new();
+    let entries = match file_provider.list_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return files,
+    };
+
+    for name in entries {
+        let path = directory.join(&name);
+        if !file_provider.is_file(&path) {
+            continue;
+        }
+
+        debug!("Capturing {}", path.to_string_lossy());
+        let file = file_provider
+            .read(&path)
+            .ok()
+            .and_then(|data| File::parse_from(&data).ok())
+            .map(|file| InventoryFile {
+                machine: file.architecture.map(Architecture::machine),
+                dependencies: file
+                    .dependencies()
+                    .into_iter()
+                    .filter(|dependency| dependency.kind != DependencyKind::Assembly)
+                    .map(|dependency| dependency.name)
+                    .collect(),
+            })
+            .unwrap_or_default();
+        files.insert(name, file);
+    }
+
+    files
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inventory() {
+        let text = r#"{
+            "version": 1,
+            "windows_directory": "C:\\Windows",
+            "system_directory": "C:\\Windows\\System32",
+            "drivers_directory": "C:\\Windows\\System32\\drivers",
+            "safe_search_enabled": true,
+            "known_dlls": ["kernel32.dll"],
+            "path": [],
+            "api_sets": { "api-ms-win-core-file-l1-2": "kernelbase.dll" },
+            "directories": {
+                "C:\\Windows\\System32": {
+                    "KERNEL32.dll": { "machine": 34404, "dependencies": ["ntdll.dll"] },
+                    "license.rtf": {}
+                }
+            }
+        }"#;
+        let inventory: Inventory = serde_json::from_str(text).unwrap();
+
+        let system_directory = Path::new(r"C:\Windows\System32");
+        let kernel32 = inventory.file(&system_directory.join("kernel32.dll")).unwrap();
+        assert_eq!(kernel32.machine, Some(0x8664));
+        assert_eq!(kernel32.dependencies, vec!["ntdll.dll"]);
+        assert!(inventory.directory(Path::new(r"c:\windows\system32\")).is_some());
+        assert!(inventory.file(&system_directory.join("ntdll.dll")).is_none());
+        assert_eq!(
+            inventory.api_set_schema().unwrap().resolve("api-ms-win-core-file-l1-2-0.dll"),
+            Some("kernelbase.dll")
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Resolves the dlls a Windows executable loads, the way the Windows loader does.
+//!
+//! [`SearchPath`] finds a dll by name with the search order of a process: the known dlls,
+//! api sets, side-by-side assemblies, the application and system directories and the PATH.
+//! [`DllDatabase`] walks the imports of a file with it and records what each dll resolved
+//! to, and [`pe`] parses the headers, imports, exports and resources of the files found.
+//!
+//! The dlls of a running system are resolved on Windows, elsewhere they are resolved on a
+//! mounted Windows image or on an [`inventory`] captured on another machine.
+//! [`SearchPath::builder`] also replaces the search order, known dlls or api set schema of
+//! the system, to see where dlls would be loaded from with other settings, and the
+//! [`file_provider`] the files are read from, like an in-memory tree in tests.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use dllwalk_core::{DllDatabase, SearchPath, WalkEvent};
+//!
+//! let directory = Path::new(r"C:\Program Files\App");
+//! let search_path = SearchPath::builder(directory).build().unwrap();
+//! let mut database = DllDatabase::new(search_path);
+//! database.walk("app.exe", |event| match event {
+//!     WalkEvent::Resolved { name, info } => println!("{}: {}", name, info.path.display()),
+//!     WalkEvent::Missing { name } => println!("{}: not found", name),
+//!     _ => {}
+//! });
+//! ```
+//!
+//! The parts of a [`pe::File`] are public and their types are all exported by [`pe`]:
+//!
+//! ```
+//! use dllwalk_core::pe::{
+//!     ApiSetSchema, AssemblyReference, BoundImport, ExportTable, File, ImportedDll,
+//!     ImportedSymbol, Mitigation, PdbInfo, RichHeader, VersionInfo,
+//! };
+//!
+//! let file = File::new();
+//! let imports: &[ImportedDll] = &file.imports;
+//! let delay_imports: &[ImportedDll] = &file.delay_imports;
+//! let exports: &ExportTable = &file.exports;
+//! let bound_imports: &[BoundImport] = &file.bound_imports;
+//! let assembly_references: &[AssemblyReference] = &file.assembly_references;
+//! let version_info: Option<&VersionInfo> = file.version_info.as_ref();
+//! let api_set_schema: Option<&ApiSetSchema> = file.api_set_schema.as_ref();
+//! let rich_header: Option<&RichHeader> = file.rich_header.as_ref();
+//! let pdb_info: Option<&PdbInfo> = file.pdb_info.as_ref();
+//! let mitigations: Vec<Mitigation> = file.missing_mitigations();
+//!
+//! let symbols: Vec<&ImportedSymbol> =
+//!     imports.iter().chain(delay_imports).flat_map(|dll| &dll.symbols).collect();
+//! assert!(symbols.is_empty() && exports.exports.is_empty());
+//! assert!(bound_imports.is_empty() && assembly_references.is_empty());
+//! assert!(version_info.is_none() && api_set_schema.is_none());
+//! assert!(rich_header.is_none() && pdb_info.is_none());
+//! assert!(mitigations.contains(&Mitigation::Aslr));
+//! ```
+
+pub mod directory_cache;
+pub mod dll_database;
+#[cfg(windows)]
+mod error;
+pub mod file_provider;
+pub mod hijack;
+pub mod inventory;
+pub mod long_path;
+pub mod manifest;
+pub mod pe;
+#[cfg(windows)]
+mod registry;
+pub mod search_path;
+pub mod signature;
+
+pub use dll_database::{DllDatabase, DllInfo, DllNode, ImportEdge, WalkEvent};
+pub use search_path::{SearchPath, SearchPathBuilder, SearchRule};
+
+/// The graph library of `DllDatabase::to_graph`
+pub use petgraph;
+
+/// Where a dll was found, which tells how the loader found it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "clap", derive(clap::ArgEnum))]
+pub enum DllType {
+    User,
+    Path,
+    System,
+    Known,
+    Umbrella,
+    Managed,
+    #[cfg_attr(feature = "clap", clap(name = "sxs"))]
+    SxS,
+    Kernel,
+    Driver,
+}
+
+impl std::fmt::Display for DllType {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DllType::User => write!(formatter, "user-dll"),
+            DllType::Path => write!(formatter, "path-dll"),
+            DllType::System => write!(formatter, "system-dll"),
+            DllType::Known => write!(formatter, "known-dll"),
+            DllType::Umbrella => write!(formatter, "umbrella-dll"),
+            DllType::Managed => write!(formatter, "managed-dll"),
+            DllType::SxS => write!(formatter, "sxs-dll"),
+            DllType::Kernel => write!(formatter, "kernel-module"),
+            DllType::Driver => write!(formatter, "driver"),
+        }
+    }
+}
+
+/// Serialized as displayed, the names reports use
+impl serde::Serialize for DllType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
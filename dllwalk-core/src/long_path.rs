@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+const VERBATIM_PREFIX: &str = r"\\?\";
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+
+/// Path with the \\?\ prefix that lifts the MAX_PATH limit of the Win32 functions, for
+/// the ones called directly since the standard library adds it on its own. Relative
+/// paths and paths with . or .. components are left as is, a prefixed path is not
+/// normalized anymore.
+#[cfg(windows)]
+pub fn to_extended_length(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let normalized = path
+        .components()
+        .all(|component| !matches!(component, Component::CurDir | Component::ParentDir));
+    let text = path.to_string_lossy();
+    if !normalized || text.starts_with(VERBATIM_PREFIX) {
+        return path.to_owned();
+    }
+
+    if let Some(share) = text.strip_prefix(r"\\") {
+        return PathBuf::from(format!("{}{}", VERBATIM_UNC_PREFIX, share));
+    }
+
+    let mut characters = text.chars();
+    match (characters.next(), characters.next(), characters.next()) {
+        (Some(drive), Some(':'), Some('\\')) if drive.is_ascii_alphabetic() => {
+            PathBuf::from(format!("{}{}", VERBATIM_PREFIX, text))
+        }
+        _ => path.to_owned(),
+    }
+}
+
+/// Path without its \\?\ prefix, the way it is usually written
+pub fn strip_extended_length(path: &Path) -> PathBuf {
+    let text = path.to_string_lossy();
+    if let Some(share) = text.strip_prefix(VERBATIM_UNC_PREFIX) {
+        PathBuf::from(format!(r"\\{}", share))
+    } else if let Some(path) = text.strip_prefix(VERBATIM_PREFIX) {
+        PathBuf::from(path)
+    } else {
+        path.to_owned()
+    }
+}
+
+/// Absolute path of a file given on the command line, a relative path has no parent
+/// directory to search
+pub fn absolute(path: &Path, current_directory: &Path) -> PathBuf {
+    if path.has_root() {
+        path.to_owned()
+    } else {
+        current_directory.join(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(windows)]
+    #[test]
+    fn to_extended_length() {
+        let extend = |path: &str| super::to_extended_length(Path::new(path));
+        assert_eq!(extend(r"C:\Windows"), PathBuf::from(r"\\?\C:\Windows"));
+        assert_eq!(extend(r"\\server\share\app"), PathBuf::from(r"\\?\UNC\server\share\app"));
+        assert_eq!(extend(r"\\?\C:\Windows"), PathBuf::from(r"\\?\C:\Windows"));
+        assert_eq!(extend(r"C:\App\..\Windows"), PathBuf::from(r"C:\App\..\Windows"));
+        assert_eq!(extend(r"app\bin"), PathBuf::from(r"app\bin"));
+    }
+
+    #[test]
+    fn strip_extended_length() {
+        let strip = |path: &str| super::strip_extended_length(Path::new(path));
+        assert_eq!(strip(r"\\?\C:\Windows"), PathBuf::from(r"C:\Windows"));
+        assert_eq!(strip(r"\\?\UNC\server\share"), PathBuf::from(r"\\server\share"));
+        assert_eq!(strip(r"\\server\share"), PathBuf::from(r"\\server\share"));
+        assert_eq!(strip("/mnt/image"), PathBuf::from("/mnt/image"));
+    }
+}
@@ -0,0 +1,243 @@
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssemblyIdentity {
+    pub name: String,
+    pub version: Option<String>,
+    pub processor_architecture: Option<String>,
+    pub public_key_token: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Version redirection of a publisher policy, `old_version` is a single version or
+/// an inclusive range such as "6.0.0.0-6.0.22621.1"
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingRedirect {
+    pub old_version: String,
+    pub new_version: String,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub dependent_assemblies: Vec<AssemblyIdentity>,
+    pub binding_redirects: Vec<BindingRedirect>,
+    pub private_paths: Vec<String>,
+    /// asInvoker, highestAvailable or requireAdministrator
+    pub execution_level: Option<String>,
+    pub ui_access: Option<String>,
+    /// Compatibility GUIDs of the Windows versions the application supports
+    pub supported_os: Vec<String>,
+    /// Settings like dpiAware, dpiAwareness, longPathAware or activeCodePage, by name
+    pub windows_settings: Vec<(String, String)>,
+}
+
+// Compatibility GUIDs of the supportedOS elements
+const SUPPORTED_OS: [(&str, &str); 5] = [
+    ("{e2011457-1546-43c5-a5fe-008deee3d3f0}", "Windows Vista"),
+    ("{35138b9a-5d96-4fbd-8e2d-a2440225f93a}", "Windows 7"),
+    ("{4a2f28e3-53b9-4441-ba9c-d69d4a4a6e38}", "Windows 8"),
+    ("{1f676c76-80e1-4239-95bb-83d0f6d0da78}", "Windows 8.1"),
+    ("{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}", "Windows 10 and 11"),
+];
+
+/// Windows version of a supportedOS GUID, None for unknown GUIDs
+pub fn supported_os_name(id: &str) -> Option<&'static str> {
+    SUPPORTED_OS
+        .iter()
+        .find(|(guid, _)| guid.eq_ignore_ascii_case(id))
+        .map(|(_, name)| *name)
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Self, roxmltree::Error> {
+        let document = roxmltree::Document::parse(text)?;
+
+        // Elements are matched by local name, manifests freely mix asm.v1/v2/v3 namespaces
+        let dependent_assemblies = document
+            .descendants()
+            .filter(|node| node.tag_name().name() == "dependentAssembly")
+            .filter_map(|node| {
+                node.children()
+                    .find(|child| child.tag_name().name() == "assemblyIdentity")
+            })
+            .filter_map(|identity| {
+                Some(AssemblyIdentity {
+                    name: identity.attribute("name")?.to_owned(),
+                    version: identity.attribute("version").map(str::to_owned),
+                    processor_architecture: identity
+                        .attribute("processorArchitecture")
+                        .map(str::to_owned),
+                    public_key_token: identity.attribute("publicKeyToken").map(str::to_owned),
+                    language: identity.attribute("language").map(str::to_owned),
+                })
+            })
+            .collect();
+
+        let binding_redirects = document
+            .descendants()
+            .filter(|node| node.tag_name().name() == "bindingRedirect")
+            .filter_map(|node| {
+                Some(BindingRedirect {
+                    old_version: node.attribute("oldVersion")?.to_owned(),
+                    new_version: node.attribute("newVersion")?.to_owned(),
+                })
+            })
+            .collect();
+
+        // Subdirectories of the application directory, separated by semicolons
+        let private_paths = document
+            .descendants()
+            .filter(|node| node.tag_name().name() == "probing")
+            .filter_map(|node| node.attribute("privatePath"))
+            .flat_map(|paths| paths.split(';'))
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        let execution_level = document
+            .descendants()
+            .find(|node| node.tag_name().name() == "requestedExecutionLevel");
+
+        let supported_os = document
+            .descendants()
+            .filter(|node| node.tag_name().name() == "supportedOS")
+            .filter_map(|node| node.attribute("Id"))
+            .map(str::to_owned)
+            .collect();
+
+        let windows_settings = document
+            .descendants()
+            .filter(|node| node.tag_name().name() == "windowsSettings")
+            .flat_map(|node| node.children().filter(|child| child.is_element()))
+            .map(|setting| {
+                let value = setting.text().unwrap_or_default().trim().to_owned();
+                (setting.tag_name().name().to_owned(), value)
+            })
+            .collect();
+
+        Ok(Manifest {
+            dependent_assemblies,
+            binding_redirects,
+            private_paths,
+            execution_level: execution_level
+                .and_then(|node| node.attribute("level"))
+                .map(str::to_owned),
+            ui_access: execution_level
+                .and_then(|node| node.attribute("uiAccess"))
+                .map(str::to_owned),
+            supported_os,
+            windows_settings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dependent_assemblies() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <dependency>
+    <dependentAssembly>
+      <assemblyIdentity type="win32" name="Microsoft.Windows.Common-Controls" version="6.0.0.0" processorArchitecture="*" publicKeyToken="6595b64144ccf1df" language="*"/>
+    </dependentAssembly>
+  </dependency>
+</assembly>"#;
+
+        assert_eq!(
+            Manifest::parse(text).unwrap(),
+            Manifest {
+                dependent_assemblies: vec![AssemblyIdentity {
+                    name: "Microsoft.Windows.Common-Controls".to_owned(),
+                    version: Some("6.0.0.0".to_owned()),
+                    processor_architecture: Some("*".to_owned()),
+                    public_key_token: Some("6595b64144ccf1df".to_owned()),
+                    language: Some("*".to_owned()),
+                }],
+                binding_redirects: vec![],
+                private_paths: vec![],
+                ..Manifest::default()
+            }
+        );
+
+        assert_eq!(Manifest::parse("<assembly></dependency>").is_err(), true);
+    }
+
+    #[test]
+    fn private_paths() {
+        let text = r#"<?xml version="1.0" encoding="utf-8"?>
+<configuration>
+  <runtime>
+    <assemblyBinding xmlns="urn:schemas-microsoft-com:asm.v1">
+      <probing privatePath="bin;plugins\x64; "/>
+    </assemblyBinding>
+  </runtime>
+</configuration>"#;
+
+        assert_eq!(
+            Manifest::parse(text).unwrap().private_paths,
+            vec!["bin".to_owned(), r"plugins\x64".to_owned()]
+        );
+    }
+
+    #[test]
+    fn settings() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">
+    <security>
+      <requestedPrivileges>
+        <requestedExecutionLevel level="asInvoker" uiAccess="false"/>
+      </requestedPrivileges>
+    </security>
+  </trustInfo>
+  <compatibility xmlns="urn:schemas-microsoft-com:compatibility.v1">
+    <application>
+      <supportedOS Id="{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}"/>
+    </application>
+  </compatibility>
+  <application xmlns="urn:schemas-microsoft-com:asm.v3">
+    <windowsSettings>
+      <dpiAware xmlns="http://schemas.microsoft.com/SMI/2005/WindowsSettings">true/pm</dpiAware>
+      <longPathAware xmlns="http://schemas.microsoft.com/SMI/2016/WindowsSettings">true</longPathAware>
+    </windowsSettings>
+  </application>
+</assembly>"#;
+
+        let manifest = Manifest::parse(text).unwrap();
+        assert_eq!(manifest.execution_level.as_deref(), Some("asInvoker"));
+        assert_eq!(manifest.ui_access.as_deref(), Some("false"));
+        assert_eq!(manifest.supported_os, vec!["{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}"]);
+        assert_eq!(supported_os_name(&manifest.supported_os[0]), Some("Windows 10 and 11"));
+        assert_eq!(
+            manifest.windows_settings,
+            vec![
+                ("dpiAware".to_owned(), "true/pm".to_owned()),
+                ("longPathAware".to_owned(), "true".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn binding_redirects() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v3" manifestVersion="1.0">
+  <assemblyIdentity name="policy.6.0.Microsoft.Windows.Common-Controls" version="10.0.22621.1" processorArchitecture="amd64" publicKeyToken="6595b64144ccf1df" type="win32-policy"/>
+  <dependency>
+    <dependentAssembly>
+      <assemblyIdentity name="Microsoft.Windows.Common-Controls" processorArchitecture="amd64" publicKeyToken="6595b64144ccf1df" type="win32"/>
+      <bindingRedirect oldVersion="6.0.0.0-6.0.22621.1" newVersion="6.0.22621.1"/>
+    </dependentAssembly>
+  </dependency>
+</assembly>"#;
+
+        assert_eq!(
+            Manifest::parse(text).unwrap().binding_redirects,
+            vec![BindingRedirect {
+                old_version: "6.0.0.0-6.0.22621.1".to_owned(),
+                new_version: "6.0.22621.1".to_owned(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use nom::{error::context, number::complete::le_u32, sequence::tuple};
+
+use super::{bounded_count, parse_error, FileParseResult, PeError};
+
+// Layout used since Windows 10
+const API_SET_SCHEMA_VERSION: u32 = 6;
+const NAMESPACE_ENTRY_SIZE: usize = 24;
+const VALUE_ENTRY_SIZE: usize = 20;
+
+/// The api set map stored in the .apiset section of apisetschema.dll, from api set
+/// names to the dlls implementing them
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ApiSetSchema {
+    hosts: HashMap<String, String>,
+}
+
+impl ApiSetSchema {
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let (_, (version, _, _, count, entry_offset)) = context(
+            "api set schema",
+            tuple((le_u32, le_u32, le_u32, le_u32, le_u32)),
+        )(input)?;
+
+        if version != API_SET_SCHEMA_VERSION {
+            return Err(parse_error(PeError::InvalidData("api set schema version")));
+        }
+
+        let data = input
+            .get(entry_offset as usize..)
+            .ok_or_else(|| parse_error(PeError::Truncated("api set schema")))?;
+        let (_, entries) = context(
+            "api set schema",
+            bounded_count(
+                tuple((le_u32, le_u32, le_u32, le_u32, le_u32, le_u32)),
+                count as usize,
+                NAMESPACE_ENTRY_SIZE,
+            ),
+        )(data)?;
+
+        let mut hosts = HashMap::new();
+        for (_, name_offset, _, hashed_length, value_offset, value_count) in entries {
+            let name = read_utf16(input, name_offset, hashed_length)?;
+
+            let data = input
+                .get(value_offset as usize..)
+                .ok_or_else(|| parse_error(PeError::Truncated("api set schema")))?;
+            let (_, values) = context(
+                "api set schema",
+                bounded_count(
+                    tuple((le_u32, le_u32, le_u32, le_u32, le_u32)),
+                    value_count as usize,
+                    VALUE_ENTRY_SIZE,
+                ),
+            )(data)?;
+
+            // The default host has no importer name, the other values are exceptions
+            // for specific importers and are ignored since a dll is resolved once for
+            // every importer
+            let default_value = values
+                .iter()
+                .find(|(_, _, importer_length, _, _)| *importer_length == 0)
+                .or_else(|| values.first());
+
+            if let Some((_, _, _, host_offset, host_length)) = default_value {
+                let host = read_utf16(input, *host_offset, *host_length)?;
+                if !host.is_empty() {
+                    hosts.insert(name.to_lowercase(), host.to_lowercase());
+                }
+            }
+        }
+
+        Ok((input, ApiSetSchema { hosts }))
+    }
+
+    /// Host dll of an api set. The name is matched up to its last hyphen like the
+    /// loader does, so any revision of an api set resolves to the same host.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        let name = name.strip_suffix(".dll").unwrap_or(&name);
+        let hashed_name = &name[..name.rfind('-')?];
+
+        self.hosts.get(hashed_name).map(|host| host.as_str())
+    }
+
+    /// Api set names, without their revision, with their hosts
+    pub fn hosts(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.hosts.iter().map(|(name, host)| (name.as_str(), host.as_str()))
+    }
+}
+
+impl FromIterator<(String, String)> for ApiSetSchema {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(hosts: T) -> Self {
+        ApiSetSchema {
+            hosts: hosts
+                .into_iter()
+                .map(|(name, host)| (name.to_lowercase(), host.to_lowercase()))
+                .collect(),
+        }
+    }
+}
+
+fn read_utf16(input: &[u8], offset: u32, length: u32) -> Result<String, nom::Err<PeError>> {
+    let data = input
+        .get(offset as usize..)
+        .and_then(|data| data.get(..length as usize))
+        .ok_or_else(|| parse_error(PeError::Truncated("api set name")))?;
+
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+
+    Ok(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn api_set_schema() {
+        let utf16 = |text: &str| -> Vec<u8> {
+            text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+        };
+
+        let mut data = vec![0u8; 0x100];
+        let mut write = |offset: usize, values: &[u32]| {
+            for (index, value) in values.iter().enumerate() {
+                let offset = offset + index * 4;
+                data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            }
+        };
+
+        // Namespace header and its two entries at 0x1c
+        write(0x00, &[6, 0x100, 0, 2, 0x1c]);
+
+        // api-ms-win-core-file-l1-2-0 -> kernelbase.dll, with an exception
+        let name = utf16("api-ms-win-core-file-l1-2-0");
+        write(0x1c, &[0, 0x80, name.len() as u32, name.len() as u32 - 4, 0x50, 2]);
+        // ext-ms-win-unsupported-l1-1-0 without any host
+        write(0x34, &[0, 0xc0, 0x3a, 0x36, 0x50, 0]);
+
+        write(0x50, &[0, 0xb8, 2, 0xf0, 2]);
+        write(0x64, &[0, 0, 0, 0xe0, 0x1c]);
+
+        data[0x80..0x80 + name.len()].copy_from_slice(&name);
+        data[0xc0..0xc0 + 0x3a].copy_from_slice(&utf16("ext-ms-win-unsupported-l1-1-0"));
+        data[0xe0..0xe0 + 0x1c].copy_from_slice(&utf16("KernelBase.dll"));
+
+        let schema = ApiSetSchema::parse(&data).unwrap().1;
+        assert_eq!(
+            schema.resolve("api-ms-win-core-file-l1-2-0.dll"),
+            Some("kernelbase.dll")
+        );
+        assert_eq!(
+            schema.resolve("API-MS-WIN-CORE-FILE-L1-2-4.DLL"),
+            Some("kernelbase.dll")
+        );
+        assert_eq!(schema.resolve("api-ms-win-core-file-l1-3-0.dll"), None);
+        assert_eq!(schema.resolve("ext-ms-win-unsupported-l1-1-0.dll"), None);
+        assert_eq!(schema.resolve("kernel32"), None);
+
+        data[0] = 2;
+        assert_eq!(
+            ApiSetSchema::parse(&data),
+            Err(parse_error(PeError::InvalidData("api set schema version")))
+        );
+    }
+}
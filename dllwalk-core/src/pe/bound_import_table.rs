@@ -0,0 +1,114 @@
+use nom::{
+    bytes::complete::take_while1,
+    error::context,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::{parse_error, FileParseResult, PeError};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BoundForwarder {
+    pub name: String,
+    pub time_date_stamp: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BoundImport {
+    pub name: String,
+    pub time_date_stamp: u32,
+    pub forwarders: Vec<BoundForwarder>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BoundImportTable {
+    pub imports: Vec<BoundImport>,
+}
+
+impl BoundImportTable {
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let mut imports = Vec::new();
+        let mut remaining = input;
+
+        loop {
+            let (rest, (time_date_stamp, name_offset, number_of_forwarders)) =
+                context("bound import table", tuple((le_u32, le_u16, le_u16)))(remaining)?;
+            remaining = rest;
+
+            // Null entry, end of the table
+            if time_date_stamp == 0 && name_offset == 0 {
+                break;
+            }
+
+            let name = BoundImportTable::read_name(input, name_offset)?;
+
+            let mut forwarders = Vec::new();
+            for _ in 0..number_of_forwarders {
+                let (rest, (time_date_stamp, name_offset, _)) =
+                    context("bound import table", tuple((le_u32, le_u16, le_u16)))(remaining)?;
+                remaining = rest;
+
+                forwarders.push(BoundForwarder {
+                    name: BoundImportTable::read_name(input, name_offset)?,
+                    time_date_stamp,
+                });
+            }
+
+            imports.push(BoundImport {
+                name,
+                time_date_stamp,
+                forwarders,
+            });
+        }
+
+        Ok((remaining, BoundImportTable { imports }))
+    }
+
+    // Names are stored as offsets from the start of the bound import table
+    fn read_name(input: &[u8], offset: u16) -> Result<String, nom::Err<PeError>> {
+        let data = input
+            .get(offset as usize..)
+            .ok_or_else(|| parse_error(PeError::Truncated("bound import table")))?;
+        let (_, name) = context("bound import name", take_while1(|c| c != 0))(data)?;
+
+        Ok(String::from_utf8_lossy(name).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bound_import_table() {
+        let data = vec![
+            0x78, 0x56, 0x34, 0x12, 0x20, 0x00, 0x01, 0x00, 0x21, 0x43, 0x65, 0x87, 0x2a, 0x00,
+            0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x64, 0x6c, 0x6c, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x62, 0x2e, 0x64, 0x6c, 0x6c, 0x00,
+        ];
+
+        assert_eq!(
+            BoundImportTable::parse(&data).unwrap().1,
+            BoundImportTable {
+                imports: vec![
+                    BoundImport {
+                        name: "a.dll".to_owned(),
+                        time_date_stamp: 0x12345678,
+                        forwarders: vec![BoundForwarder {
+                            name: "b.dll".to_owned(),
+                            time_date_stamp: 0x87654321,
+                        }],
+                    },
+                    BoundImport {
+                        name: "b.dll".to_owned(),
+                        time_date_stamp: 0x11111111,
+                        forwarders: vec![],
+                    },
+                ],
+            }
+        );
+
+        assert_eq!(BoundImportTable::parse(&data[..6]).is_err(), true);
+    }
+}
@@ -0,0 +1,59 @@
+use super::msdos_header::MsDosHeader;
+
+// The checksum field sits at the same offset in PE32 and PE32+ optional headers
+const CHECKSUM_OFFSET: usize = 4 + 20 + 64;
+
+/// Recomputes the image checksum the way the loader and CheckSumMappedFile do: a
+/// 16-bit one's complement sum of the file, skipping the checksum field, plus the
+/// file size.
+pub fn compute_checksum(data: &[u8]) -> Option<u32> {
+    let (_, msdos_header) = MsDosHeader::parse(data).ok()?;
+    let checksum_offset = msdos_header.pe_offset as usize + CHECKSUM_OFFSET;
+    if checksum_offset + 4 > data.len() {
+        return None;
+    }
+
+    let mut sum: u64 = 0;
+    for (index, word) in data.chunks(2).enumerate() {
+        let offset = index * 2;
+        if offset == checksum_offset || offset == checksum_offset + 2 {
+            continue;
+        }
+
+        sum += u16::from_le_bytes([word[0], *word.get(1).unwrap_or(&0)]) as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum = (sum & 0xffff) + (sum >> 16);
+
+    // The size of files over 4 GB wraps around like in CheckSumMappedFile
+    Some((sum as u32).wrapping_add(data.len() as u32))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksum() {
+        let mut data = vec![0u8; 0x100];
+        data[0] = 0x4d;
+        data[1] = 0x5a;
+        data[0x3c] = 0x40;
+
+        // Ignored checksum field
+        data[0x40 + CHECKSUM_OFFSET] = 0xff;
+
+        // Carry folding
+        data[0x80] = 0xff;
+        data[0x81] = 0xff;
+        data[0x82] = 0x02;
+
+        // Odd trailing byte
+        data.push(0x01);
+
+        assert_eq!(compute_checksum(&data), Some(0x5a4d + 0x40 + 0x02 + 0x01 + 0x101));
+
+        assert_eq!(compute_checksum(&data[..0x80]), None);
+        assert_eq!(compute_checksum(&vec![0u8; 100]), None);
+    }
+}
@@ -0,0 +1,460 @@
+use nom::{
+    bytes::complete::{tag, take, take_while},
+    error::context,
+    number::complete::{le_u16, le_u32, le_u64, le_u8},
+    sequence::tuple,
+};
+
+use super::{parse_error, FileParseResult, PeError};
+
+// Metadata tables, see ECMA-335 II.22
+const MODULE: usize = 0x00;
+const TYPE_REF: usize = 0x01;
+const TYPE_DEF: usize = 0x02;
+const FIELD: usize = 0x04;
+const METHOD_DEF: usize = 0x06;
+const PARAM: usize = 0x08;
+const INTERFACE_IMPL: usize = 0x09;
+const MEMBER_REF: usize = 0x0a;
+const DECL_SECURITY: usize = 0x0e;
+const STAND_ALONE_SIG: usize = 0x11;
+const EVENT: usize = 0x14;
+const PROPERTY: usize = 0x17;
+const MODULE_REF: usize = 0x1a;
+const TYPE_SPEC: usize = 0x1b;
+const ASSEMBLY: usize = 0x20;
+const ASSEMBLY_REF: usize = 0x23;
+const FILE: usize = 0x26;
+const EXPORTED_TYPE: usize = 0x27;
+const MANIFEST_RESOURCE: usize = 0x28;
+const GENERIC_PARAM: usize = 0x2a;
+const METHOD_SPEC: usize = 0x2b;
+const GENERIC_PARAM_CONSTRAINT: usize = 0x2c;
+const NOT_USED: usize = usize::MAX;
+
+// Coded indexes, see ECMA-335 II.24.2.6
+const TYPE_DEF_OR_REF: &[usize] = &[TYPE_DEF, TYPE_REF, TYPE_SPEC];
+const HAS_CONSTANT: &[usize] = &[FIELD, PARAM, PROPERTY];
+const HAS_CUSTOM_ATTRIBUTE: &[usize] = &[
+    METHOD_DEF,
+    FIELD,
+    TYPE_REF,
+    TYPE_DEF,
+    PARAM,
+    INTERFACE_IMPL,
+    MEMBER_REF,
+    MODULE,
+    DECL_SECURITY,
+    PROPERTY,
+    EVENT,
+    STAND_ALONE_SIG,
+    MODULE_REF,
+    TYPE_SPEC,
+    ASSEMBLY,
+    ASSEMBLY_REF,
+    FILE,
+    EXPORTED_TYPE,
+    MANIFEST_RESOURCE,
+    GENERIC_PARAM,
+    GENERIC_PARAM_CONSTRAINT,
+    METHOD_SPEC,
+];
+const HAS_FIELD_MARSHAL: &[usize] = &[FIELD, PARAM];
+const HAS_DECL_SECURITY: &[usize] = &[TYPE_DEF, METHOD_DEF, ASSEMBLY];
+const MEMBER_REF_PARENT: &[usize] = &[TYPE_DEF, TYPE_REF, MODULE_REF, METHOD_DEF, TYPE_SPEC];
+const HAS_SEMANTICS: &[usize] = &[EVENT, PROPERTY];
+const METHOD_DEF_OR_REF: &[usize] = &[METHOD_DEF, MEMBER_REF];
+const MEMBER_FORWARDED: &[usize] = &[FIELD, METHOD_DEF];
+const CUSTOM_ATTRIBUTE_TYPE: &[usize] = &[NOT_USED, NOT_USED, METHOD_DEF, MEMBER_REF, NOT_USED];
+const RESOLUTION_SCOPE: &[usize] = &[MODULE, MODULE_REF, ASSEMBLY_REF, TYPE_REF];
+
+enum Column {
+    Fixed(usize),
+    String,
+    Guid,
+    Blob,
+    Table(usize),
+    Coded(&'static [usize]),
+}
+
+// Layout of the tables stored before AssemblyRef, needed to skip over them
+const TABLE_COLUMNS: [&[Column]; ASSEMBLY_REF] = [
+    // Module
+    &[Column::Fixed(2), Column::String, Column::Guid, Column::Guid, Column::Guid],
+    // TypeRef
+    &[Column::Coded(RESOLUTION_SCOPE), Column::String, Column::String],
+    // TypeDef
+    &[
+        Column::Fixed(4),
+        Column::String,
+        Column::String,
+        Column::Coded(TYPE_DEF_OR_REF),
+        Column::Table(FIELD),
+        Column::Table(METHOD_DEF),
+    ],
+    // FieldPtr
+    &[Column::Table(FIELD)],
+    // Field
+    &[Column::Fixed(2), Column::String, Column::Blob],
+    // MethodPtr
+    &[Column::Table(METHOD_DEF)],
+    // MethodDef
+    &[
+        Column::Fixed(4),
+        Column::Fixed(2),
+        Column::Fixed(2),
+        Column::String,
+        Column::Blob,
+        Column::Table(PARAM),
+    ],
+    // ParamPtr
+    &[Column::Table(PARAM)],
+    // Param
+    &[Column::Fixed(2), Column::Fixed(2), Column::String],
+    // InterfaceImpl
+    &[Column::Table(TYPE_DEF), Column::Coded(TYPE_DEF_OR_REF)],
+    // MemberRef
+    &[Column::Coded(MEMBER_REF_PARENT), Column::String, Column::Blob],
+    // Constant
+    &[Column::Fixed(2), Column::Coded(HAS_CONSTANT), Column::Blob],
+    // CustomAttribute
+    &[
+        Column::Coded(HAS_CUSTOM_ATTRIBUTE),
+        Column::Coded(CUSTOM_ATTRIBUTE_TYPE),
+        Column::Blob,
+    ],
+    // FieldMarshal
+    &[Column::Coded(HAS_FIELD_MARSHAL), Column::Blob],
+    // DeclSecurity
+    &[Column::Fixed(2), Column::Coded(HAS_DECL_SECURITY), Column::Blob],
+    // ClassLayout
+    &[Column::Fixed(2), Column::Fixed(4), Column::Table(TYPE_DEF)],
+    // FieldLayout
+    &[Column::Fixed(4), Column::Table(FIELD)],
+    // StandAloneSig
+    &[Column::Blob],
+    // EventMap
+    &[Column::Table(TYPE_DEF), Column::Table(EVENT)],
+    // EventPtr
+    &[Column::Table(EVENT)],
+    // Event
+    &[Column::Fixed(2), Column::String, Column::Coded(TYPE_DEF_OR_REF)],
+    // PropertyMap
+    &[Column::Table(TYPE_DEF), Column::Table(PROPERTY)],
+    // PropertyPtr
+    &[Column::Table(PROPERTY)],
+    // Property
+    &[Column::Fixed(2), Column::String, Column::Blob],
+    // MethodSemantics
+    &[
+        Column::Fixed(2),
+        Column::Table(METHOD_DEF),
+        Column::Coded(HAS_SEMANTICS),
+    ],
+    // MethodImpl
+    &[
+        Column::Table(TYPE_DEF),
+        Column::Coded(METHOD_DEF_OR_REF),
+        Column::Coded(METHOD_DEF_OR_REF),
+    ],
+    // ModuleRef
+    &[Column::String],
+    // TypeSpec
+    &[Column::Blob],
+    // ImplMap
+    &[
+        Column::Fixed(2),
+        Column::Coded(MEMBER_FORWARDED),
+        Column::String,
+        Column::Table(MODULE_REF),
+    ],
+    // FieldRVA
+    &[Column::Fixed(4), Column::Table(FIELD)],
+    // EncLog
+    &[Column::Fixed(4), Column::Fixed(4)],
+    // EncMap
+    &[Column::Fixed(4)],
+    // Assembly
+    &[
+        Column::Fixed(4),
+        Column::Fixed(2),
+        Column::Fixed(2),
+        Column::Fixed(2),
+        Column::Fixed(2),
+        Column::Fixed(4),
+        Column::Blob,
+        Column::String,
+        Column::String,
+    ],
+    // AssemblyProcessor
+    &[Column::Fixed(4)],
+    // AssemblyOS
+    &[Column::Fixed(4), Column::Fixed(4), Column::Fixed(4)],
+];
+
+struct TableSizes {
+    rows: Vec<u32>,
+    string_index_size: usize,
+    guid_index_size: usize,
+    blob_index_size: usize,
+}
+
+impl TableSizes {
+    fn row_count(&self, table: usize) -> u32 {
+        self.rows.get(table).copied().unwrap_or(0)
+    }
+
+    fn row_size(&self, columns: &[Column]) -> usize {
+        columns.iter().map(|column| self.column_size(column)).sum()
+    }
+
+    fn column_size(&self, column: &Column) -> usize {
+        match column {
+            Column::Fixed(size) => *size,
+            Column::String => self.string_index_size,
+            Column::Guid => self.guid_index_size,
+            Column::Blob => self.blob_index_size,
+            Column::Table(table) => {
+                if self.row_count(*table) < 0x10000 {
+                    2
+                } else {
+                    4
+                }
+            }
+            Column::Coded(tables) => {
+                // The low bits of a coded index select the table
+                let tag_bits = usize::BITS - (tables.len() - 1).leading_zeros();
+                let max_rows = tables
+                    .iter()
+                    .map(|&table| self.row_count(table))
+                    .max()
+                    .unwrap_or(0);
+
+                if (max_rows as u64) < (1u64 << (16 - tag_bits)) {
+                    2
+                } else {
+                    4
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssemblyReference {
+    pub name: String,
+    pub version: [u16; 4],
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClrHeader {
+    pub assembly_references: Vec<AssemblyReference>,
+}
+
+impl ClrHeader {
+    pub fn parse<'i>(
+        input: &'i [u8],
+        rva_to_file_slice: impl Fn(u32) -> Option<&'i [u8]>,
+    ) -> FileParseResult<'i, Self> {
+        let (remaining, (_, _, _, metadata_rva, metadata_size)) =
+            context("CLR header", tuple((le_u32, le_u16, le_u16, le_u32, le_u32)))(input)?;
+
+        let metadata = rva_to_file_slice(metadata_rva)
+            .ok_or_else(|| parse_error(PeError::RvaOutOfRange(metadata_rva)))?
+            .get(..metadata_size as usize)
+            .ok_or_else(|| parse_error(PeError::Truncated("CLR metadata")))?;
+
+        let (_, assembly_references) = ClrHeader::parse_metadata(metadata)?;
+
+        Ok((
+            remaining,
+            ClrHeader {
+                assembly_references,
+            },
+        ))
+    }
+
+    fn parse_metadata(root: &[u8]) -> FileParseResult<Vec<AssemblyReference>> {
+        let (input, _) = tag("BSJB".as_bytes())(root)
+            .map_err(|_: nom::Err<PeError>| parse_error(PeError::InvalidData("CLR metadata")))?;
+
+        let (input, (_, _, _, version_length)) =
+            context("CLR metadata", tuple((le_u16, le_u16, le_u32, le_u32)))(input)?;
+
+        let (mut input, (_, _, number_of_streams)) = context(
+            "CLR metadata",
+            tuple((take(version_length as usize), le_u16, le_u16)),
+        )(input)?;
+
+        let mut tables = None;
+        let mut strings = None;
+        for _ in 0..number_of_streams {
+            let (remaining, (offset, size, name)) = context(
+                "CLR stream header",
+                tuple((le_u32, le_u32, take_while(|c| c != 0))),
+            )(input)?;
+
+            // Names are null terminated and padded to a 4 bytes boundary
+            let (remaining, _) = context("CLR stream header", take(4 - name.len() % 4))(remaining)?;
+            input = remaining;
+
+            let stream = root
+                .get(offset as usize..)
+                .and_then(|data| data.get(..size as usize))
+                .ok_or_else(|| parse_error(PeError::Truncated("CLR stream")))?;
+
+            match name {
+                b"#~" | b"#-" => tables = Some(stream),
+                b"#Strings" => strings = Some(stream),
+                _ => {}
+            }
+        }
+
+        let tables = tables.ok_or_else(|| parse_error(PeError::InvalidData("CLR metadata")))?;
+        let strings = strings.ok_or_else(|| parse_error(PeError::InvalidData("CLR metadata")))?;
+
+        let (_, assembly_references) = ClrHeader::parse_tables(tables, strings)?;
+
+        Ok((input, assembly_references))
+    }
+
+    fn parse_tables<'i>(
+        input: &'i [u8],
+        strings: &'i [u8],
+    ) -> FileParseResult<'i, Vec<AssemblyReference>> {
+        let (mut input, (_, _, _, heap_sizes, _, valid, _)) = context(
+            "CLR tables stream",
+            tuple((le_u32, le_u8, le_u8, le_u8, le_u8, le_u64, le_u64)),
+        )(input)?;
+
+        // Row counts of the present tables
+        let mut rows = vec![0u32; 64];
+        for (table, count) in rows.iter_mut().enumerate() {
+            if valid & (1u64 << table) != 0 {
+                let (remaining, value) = context("CLR tables stream", le_u32)(input)?;
+                *count = value;
+                input = remaining;
+            }
+        }
+
+        let index_size = |flag: u8| if heap_sizes & flag != 0 { 4 } else { 2 };
+        let sizes = TableSizes {
+            rows,
+            string_index_size: index_size(0x01),
+            guid_index_size: index_size(0x02),
+            blob_index_size: index_size(0x04),
+        };
+
+        // Skip the tables stored before AssemblyRef
+        let offset: u64 = TABLE_COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(table, columns)| sizes.row_count(table) as u64 * sizes.row_size(columns) as u64)
+            .sum();
+        let (mut input, _) = context(
+            "CLR tables stream",
+            take(usize::try_from(offset).unwrap_or(usize::MAX)),
+        )(input)?;
+
+        let mut assembly_references = Vec::new();
+        for _ in 0..sizes.row_count(ASSEMBLY_REF) {
+            let (remaining, (major, minor, build, revision, _)) = context(
+                "AssemblyRef table",
+                tuple((le_u16, le_u16, le_u16, le_u16, le_u32)),
+            )(input)?;
+            let (remaining, _) = read_index(remaining, sizes.blob_index_size)?;
+            let (remaining, name) = read_index(remaining, sizes.string_index_size)?;
+            let (remaining, _) = read_index(remaining, sizes.string_index_size)?;
+            let (remaining, _) = read_index(remaining, sizes.blob_index_size)?;
+            input = remaining;
+
+            let name = strings
+                .get(name as usize..)
+                .ok_or_else(|| parse_error(PeError::Truncated("CLR strings heap")))?;
+            let length = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+
+            assembly_references.push(AssemblyReference {
+                name: String::from_utf8_lossy(&name[..length]).into_owned(),
+                version: [major, minor, build, revision],
+            });
+        }
+
+        Ok((input, assembly_references))
+    }
+}
+
+fn read_index(input: &[u8], size: usize) -> FileParseResult<u32> {
+    if size == 4 {
+        context("CLR table index", le_u32)(input)
+    } else {
+        let (input, index) = context("CLR table index", le_u16)(input)?;
+        Ok((input, index as u32))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metadata() {
+        let mut data = Vec::new();
+
+        // Metadata root
+        data.extend_from_slice(b"BSJB");
+        data.extend_from_slice(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&12u32.to_le_bytes());
+        data.extend_from_slice(b"v4.0.30319\0\0");
+        data.extend_from_slice(&[0x00, 0x00, 0x02, 0x00]);
+
+        // Stream headers
+        data.extend_from_slice(&64u32.to_le_bytes());
+        data.extend_from_slice(&48u32.to_le_bytes());
+        data.extend_from_slice(b"#~\0\0");
+        data.extend_from_slice(&112u32.to_le_bytes());
+        data.extend_from_slice(&16u32.to_le_bytes());
+        data.extend_from_slice(b"#Strings\0\0\0\0");
+        assert_eq!(data.len(), 64);
+
+        // Tables stream with a single AssemblyRef row
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01]);
+        data.extend_from_slice(&(1u64 << ASSEMBLY_REF).to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[
+            0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        assert_eq!(data.len(), 112);
+
+        // Strings heap
+        data.extend_from_slice(b"\0System.Runtime\0");
+
+        assert_eq!(
+            ClrHeader::parse_metadata(&data).unwrap().1,
+            vec![AssemblyReference {
+                name: "System.Runtime".to_owned(),
+                version: [1, 2, 3, 4],
+            }]
+        );
+
+        assert_eq!(ClrHeader::parse_metadata(&data[..60]).is_err(), true);
+    }
+
+    #[test]
+    fn coded_index_size() {
+        let mut sizes = TableSizes {
+            rows: vec![0u32; 64],
+            string_index_size: 2,
+            guid_index_size: 2,
+            blob_index_size: 2,
+        };
+
+        assert_eq!(sizes.column_size(&Column::Coded(TYPE_DEF_OR_REF)), 2);
+
+        sizes.rows[TYPE_SPEC] = 0x4000;
+        assert_eq!(sizes.column_size(&Column::Coded(TYPE_DEF_OR_REF)), 4);
+        assert_eq!(sizes.column_size(&Column::Coded(HAS_SEMANTICS)), 2);
+        assert_eq!(sizes.column_size(&Column::Table(TYPE_SPEC)), 2);
+    }
+}
@@ -0,0 +1,82 @@
+use nom::{
+    bytes::complete::{tag, take},
+    error::context,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::{parse_error, FileParseResult, PeError};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CoffHeader {
+    pub machine: u16,
+    pub number_of_sections: u16,
+    pub time_date_stamp: u32,
+    pub size_of_optional_header: u16,
+    pub characteristics: u16,
+}
+
+impl CoffHeader {
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let (input, _) = tag("PE\0\0".as_bytes())(input)
+            .map_err(|_: nom::Err<PeError>| parse_error(PeError::InvalidPeSignature))?;
+        let (
+            input,
+            (
+                machine,
+                number_of_sections,
+                time_date_stamp,
+                _,
+                size_of_optional_header,
+                characteristics,
+            ),
+        ) = context(
+            "COFF header",
+            tuple((le_u16, le_u16, le_u32, take(8_usize), le_u16, le_u16)),
+        )(input)?;
+
+        Ok((
+            input,
+            CoffHeader {
+                machine,
+                number_of_sections,
+                time_date_stamp,
+                size_of_optional_header,
+                characteristics,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn coff_header() {
+        let data = vec![
+            0x50, 0x45, 0x00, 0x00, 0x64, 0xaa, 0x02, 0x01, 0x80, 0x4e, 0x8d, 0x60, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x34, 0x12, 0x02, 0x21,
+        ];
+
+        assert_eq!(
+            CoffHeader::parse(&data).unwrap().1,
+            CoffHeader {
+                machine: 0xaa64,
+                number_of_sections: 0x0102,
+                time_date_stamp: 0x608d4e80,
+                size_of_optional_header: 0x1234,
+                characteristics: 0x2102,
+            }
+        );
+
+        assert_eq!(
+            CoffHeader::parse(&vec![0u8; 100]),
+            Err(parse_error(PeError::InvalidPeSignature))
+        );
+        assert_eq!(
+            CoffHeader::parse(&data[..10]),
+            Err(parse_error(PeError::Truncated("COFF header")))
+        );
+    }
+}
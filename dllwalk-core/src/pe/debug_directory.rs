@@ -0,0 +1,162 @@
+use nom::{
+    bytes::complete::{tag, take, take_while},
+    error::context,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::{bounded_count, slice_from, FileParseResult};
+
+const IMAGE_DEBUG_TYPE_CODEVIEW: u32 = 2;
+const IMAGE_DEBUG_TYPE_REPRO: u32 = 16;
+const DEBUG_DIRECTORY_SIZE: u32 = 28;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PdbInfo {
+    pub guid: [u8; 16],
+    pub age: u32,
+    pub path: String,
+}
+
+impl PdbInfo {
+    /// Identifier used by symbol servers: the GUID followed by the age, both in
+    /// uppercase hexadecimal
+    pub fn symbol_id(&self) -> String {
+        let data1 = u32::from_le_bytes([self.guid[0], self.guid[1], self.guid[2], self.guid[3]]);
+        let data2 = u16::from_le_bytes([self.guid[4], self.guid[5]]);
+        let data3 = u16::from_le_bytes([self.guid[6], self.guid[7]]);
+        let data4: String = self.guid[8..]
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+
+        format!("{:08X}{:04X}{:04X}{}{:X}", data1, data2, data3, data4, self.age)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DebugDirectory {
+    pub pdb_info: Option<PdbInfo>,
+    /// Linked with /Brepro, the COFF timestamp is then a hash of the image
+    pub reproducible: bool,
+}
+
+impl DebugDirectory {
+    /// Parses the debug directory entries, `data` is the whole file since entries
+    /// point to their record with a file offset.
+    pub fn parse<'i>(input: &'i [u8], size: u32, data: &'i [u8]) -> FileParseResult<'i, Self> {
+        let (input, entries) = context(
+            "debug directory",
+            bounded_count(
+                tuple((le_u32, le_u32, le_u16, le_u16, le_u32, le_u32, le_u32, le_u32)),
+                (size / DEBUG_DIRECTORY_SIZE) as usize,
+                DEBUG_DIRECTORY_SIZE as usize,
+            ),
+        )(input)?;
+
+        let mut pdb_info = None;
+        let mut reproducible = false;
+        for (_, _, _, _, debug_type, size_of_data, _, pointer_to_raw_data) in entries {
+            if debug_type == IMAGE_DEBUG_TYPE_REPRO {
+                reproducible = true;
+            }
+
+            if debug_type != IMAGE_DEBUG_TYPE_CODEVIEW {
+                continue;
+            }
+
+            let record = slice_from(data, pointer_to_raw_data.into())
+                .and_then(|record| record.get(..size_of_data as usize));
+            if let Some(record) = record {
+                pdb_info = DebugDirectory::parse_codeview(record).ok().map(|(_, info)| info);
+            }
+        }
+
+        Ok((
+            input,
+            DebugDirectory {
+                pdb_info,
+                reproducible,
+            },
+        ))
+    }
+
+    // Only the RSDS format written by PDB 7.0 toolchains is supported
+    fn parse_codeview(input: &[u8]) -> FileParseResult<PdbInfo> {
+        let (input, (_, guid, age, path)) = context(
+            "CodeView record",
+            tuple((
+                tag("RSDS".as_bytes()),
+                take(16_usize),
+                le_u32,
+                take_while(|c| c != 0),
+            )),
+        )(input)?;
+
+        let mut pdb_guid = [0u8; 16];
+        pdb_guid.copy_from_slice(guid);
+
+        Ok((
+            input,
+            PdbInfo {
+                guid: pdb_guid,
+                age,
+                path: String::from_utf8_lossy(path).into_owned(),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pe::{parse_error, PeError};
+
+    #[test]
+    fn debug_directory() {
+        let mut data = vec![0u8; 0x40];
+
+        // Codeview record at 0x40
+        data.extend_from_slice(b"RSDS");
+        data.extend_from_slice(&[
+            0x67, 0x45, 0x23, 0x01, 0xab, 0x89, 0xef, 0xcd, 0x01, 0x23, 0x45, 0x67, 0x89, 0xab,
+            0xcd, 0xef,
+        ]);
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(b"C:\\build\\test.pdb\0");
+
+        // Debug directory with a non codeview entry first
+        let mut directory = Vec::new();
+        for (debug_type, pointer_to_raw_data) in [(13u32, 0u32), (2u32, 0x40u32)] {
+            directory.extend_from_slice(&[0u8; 12]);
+            directory.extend_from_slice(&debug_type.to_le_bytes());
+            directory.extend_from_slice(&42u32.to_le_bytes());
+            directory.extend_from_slice(&0u32.to_le_bytes());
+            directory.extend_from_slice(&pointer_to_raw_data.to_le_bytes());
+        }
+
+        let debug_directory = DebugDirectory::parse(&directory, 56, &data).unwrap().1;
+        assert_eq!(debug_directory.reproducible, false);
+
+        let pdb_info = debug_directory.pdb_info.unwrap();
+
+        assert_eq!(pdb_info.path, "C:\\build\\test.pdb");
+        assert_eq!(pdb_info.age, 3);
+        assert_eq!(pdb_info.symbol_id(), "0123456789ABCDEF0123456789ABCDEF3");
+
+        // Reproducible build entry
+        directory[12..16].copy_from_slice(&16u32.to_le_bytes());
+        assert_eq!(
+            DebugDirectory::parse(&directory, 56, &data)
+                .unwrap()
+                .1
+                .reproducible,
+            true
+        );
+
+        assert_eq!(
+            DebugDirectory::parse(&directory, 84, &data),
+            Err(parse_error(PeError::Truncated("debug directory")))
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use nom::{error::context, multi::count, number::complete::le_u32};
+
+use super::{import_table::ImportTable, FileParseResult, ImportedDll, Magic, PeError};
+
+// The RVAs of the descriptors of old linkers are virtual addresses instead when this
+// attribute is not set
+const DLATTR_RVA: u32 = 0x1;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Descriptor {
+    attributes: u32,
+    name_rva: u32,
+    import_name_table_rva: u32,
+}
+
+/// Dlls loaded on the first call of one of their functions, through the delay load helper
+#[derive(Debug, PartialEq, Eq)]
+pub struct DelayImportTable {
+    pub imports: Vec<ImportedDll>,
+    pub warnings: Vec<PeError>,
+}
+
+impl DelayImportTable {
+    pub fn parse<'i>(
+        input: &'i [u8],
+        magic: Magic,
+        image_base: u64,
+        rva_to_file_slice: impl Fn(u32) -> Option<&'i [u8]>,
+    ) -> FileParseResult<'i, Self> {
+        let (remaining, descriptors) = DelayImportTable::parse_descriptors(input)?;
+
+        let mut imports = Vec::new();
+        let mut warnings = Vec::new();
+        for descriptor in &descriptors {
+            let rva = |address: u32| match descriptor.attributes & DLATTR_RVA {
+                0 => address.wrapping_sub(image_base as u32),
+                _ => address,
+            };
+
+            imports.extend(ImportTable::read_imported_dll(
+                rva(descriptor.name_rva),
+                rva(descriptor.import_name_table_rva),
+                magic,
+                &rva_to_file_slice,
+                &mut warnings,
+            ));
+        }
+
+        Ok((remaining, DelayImportTable { imports, warnings }))
+    }
+
+    // IMAGE_DELAYLOAD_DESCRIPTOR: attributes, dll name, module handle, address table, name
+    // table, bound address table, unload table and timestamp
+    fn parse_descriptors(mut input: &[u8]) -> FileParseResult<Vec<Descriptor>> {
+        let mut descriptors = Vec::new();
+        loop {
+            let (remaining, fields) =
+                context("delay import directory table", count(le_u32, 8))(input)?;
+            input = remaining;
+
+            // Null entry, end of the table
+            if fields[1] == 0 {
+                break;
+            }
+
+            descriptors.push(Descriptor {
+                attributes: fields[0],
+                name_rva: fields[1],
+                import_name_table_rva: fields[4],
+            });
+        }
+
+        Ok((input, descriptors))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pe::import_table::ImportedSymbol;
+
+    #[test]
+    fn delay_import_table() {
+        let mut data = vec![0u8; 0x100];
+
+        // A descriptor with RVAs, one with the virtual addresses of old linkers
+        let descriptors = [(DLATTR_RVA, 0x60u32, 0x80u32), (0, 0x40_0070, 0x40_0090)];
+        for (index, (attributes, name_rva, name_table_rva)) in descriptors.iter().enumerate() {
+            let offset = index * 32;
+            data[offset..offset + 4].copy_from_slice(&attributes.to_le_bytes());
+            data[offset + 4..offset + 8].copy_from_slice(&name_rva.to_le_bytes());
+            data[offset + 16..offset + 20].copy_from_slice(&name_table_rva.to_le_bytes());
+        }
+
+        data[0x60..0x6b].copy_from_slice(b"dbghelp.dll");
+        data[0x70..0x7a].copy_from_slice(b"ws2_32.dll");
+        data[0x80..0x84].copy_from_slice(&0xa0u32.to_le_bytes());
+        data[0x90..0x94].copy_from_slice(&0x8000_0073u32.to_le_bytes());
+        data[0xa2..0xb3].copy_from_slice(b"MiniDumpWriteDump");
+
+        let table = DelayImportTable::parse(&data, Magic::Pe32, 0x40_0000, |rva| {
+            data.get(rva as usize..)
+        })
+        .unwrap()
+        .1;
+
+        assert_eq!(
+            table,
+            DelayImportTable {
+                imports: vec![
+                    ImportedDll {
+                        name: "dbghelp.dll".to_owned(),
+                        symbols: vec![ImportedSymbol::Name("MiniDumpWriteDump".to_owned())],
+                    },
+                    ImportedDll {
+                        name: "ws2_32.dll".to_owned(),
+                        symbols: vec![ImportedSymbol::Ordinal(0x73)],
+                    },
+                ],
+                warnings: vec![],
+            }
+        );
+    }
+}
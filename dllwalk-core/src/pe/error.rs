@@ -0,0 +1,85 @@
+use nom::error::{ContextError, ErrorKind, ParseError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeError {
+    InvalidDosSignature,
+    InvalidPeSignature,
+    InvalidOptionalHeaderMagic(u16),
+    Truncated(&'static str),
+    RvaOutOfRange(u32),
+    OffsetOutOfRange(u64),
+    InvalidData(&'static str),
+    Nom(ErrorKind),
+}
+
+impl std::fmt::Display for PeError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PeError::InvalidDosSignature => write!(formatter, "missing MZ signature"),
+            PeError::InvalidPeSignature => write!(formatter, "missing PE signature"),
+            PeError::InvalidOptionalHeaderMagic(magic) => {
+                write!(formatter, "unknown optional header magic 0x{:04x}", magic)
+            }
+            PeError::Truncated(structure) => write!(formatter, "truncated {}", structure),
+            PeError::RvaOutOfRange(rva) => {
+                write!(formatter, "rva 0x{:08x} is not mapped by any section", rva)
+            }
+            PeError::OffsetOutOfRange(offset) => {
+                write!(formatter, "offset 0x{:08x} is past the end of the file", offset)
+            }
+            PeError::InvalidData(structure) => write!(formatter, "invalid {}", structure),
+            PeError::Nom(kind) => write!(formatter, "unexpected data ({:?})", kind),
+        }
+    }
+}
+
+impl std::error::Error for PeError {}
+
+/// A part of the file that could not be read, the rest of the file is still usable
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub structure: &'static str,
+    pub error: PeError,
+}
+
+impl ParseWarning {
+    pub fn new(structure: &'static str, error: PeError) -> Self {
+        Self { structure, error }
+    }
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{}: {}", self.structure, self.error)
+    }
+}
+
+impl From<nom::Err<PeError>> for PeError {
+    fn from(err: nom::Err<PeError>) -> Self {
+        match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err,
+            nom::Err::Incomplete(_) => PeError::Truncated("file"),
+        }
+    }
+}
+
+impl<I> ParseError<I> for PeError {
+    fn from_error_kind(_: I, kind: ErrorKind) -> Self {
+        PeError::Nom(kind)
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+// Names the structure being read when a combinator runs out of data, errors
+// raised by nested parsers are more precise and kept as is
+impl<I> ContextError<I> for PeError {
+    fn add_context(_: I, structure: &'static str, other: Self) -> Self {
+        match other {
+            PeError::Nom(_) => PeError::Truncated(structure),
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use nom::{
+    bytes::complete::take_while,
+    error::context,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::{
+    bounded_count, import_table::ImportedSymbol, optional_header::DataDirectory, parse_error,
+    FileParseResult, PeError,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Export {
+    pub name: Option<String>,
+    pub ordinal: u32,
+    pub forwarder: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExportTable {
+    pub name: String,
+    pub exports: Vec<Export>,
+}
+
+impl ExportTable {
+    pub fn parse<'i>(
+        input: &'i [u8],
+        directory: DataDirectory,
+        rva_to_file_slice: impl Fn(u32) -> Option<&'i [u8]>,
+    ) -> FileParseResult<'i, Self> {
+        let (
+            remaining,
+            (
+                _,
+                _,
+                _,
+                _,
+                name_rva,
+                ordinal_base,
+                number_of_functions,
+                number_of_names,
+                functions_rva,
+                names_rva,
+                name_ordinals_rva,
+            ),
+        ) = context(
+            "export directory",
+            tuple((
+                le_u32, le_u32, le_u16, le_u16, le_u32, le_u32, le_u32, le_u32, le_u32, le_u32,
+                le_u32,
+            )),
+        )(input)?;
+
+        let slice = |rva| {
+            rva_to_file_slice(rva).ok_or_else(|| parse_error(PeError::RvaOutOfRange(rva)))
+        };
+
+        let read_string = |rva| -> Result<String, nom::Err<PeError>> {
+            let data = slice(rva)?;
+            let (_, string) = take_while(|c| c != 0)(data)?;
+            Ok(String::from_utf8_lossy(string).into_owned())
+        };
+
+        let name = read_string(name_rva)?;
+
+        // Export address table
+        let data = slice(functions_rva)?;
+        let (_, functions) = context(
+            "export address table",
+            bounded_count(le_u32, number_of_functions as usize, 4),
+        )(data)?;
+
+        // Name pointer and ordinal tables
+        let mut names = Vec::new();
+        let mut name_ordinals = Vec::new();
+        if number_of_names != 0 {
+            let data = slice(names_rva)?;
+            names = context(
+                "export name table",
+                bounded_count(le_u32, number_of_names as usize, 4),
+            )(data)?
+            .1;
+
+            let data = slice(name_ordinals_rva)?;
+            name_ordinals = context(
+                "export ordinal table",
+                bounded_count(le_u16, number_of_names as usize, 2),
+            )(data)?
+            .1;
+        }
+
+        let mut exports = Vec::new();
+        for (index, &rva) in functions.iter().enumerate() {
+            // Unused slot in the export address table
+            if rva == 0 {
+                continue;
+            }
+
+            let name = match name_ordinals.iter().position(|&i| i as usize == index) {
+                Some(position) => Some(read_string(names[position])?),
+                None => None,
+            };
+
+            // Forwarders point back inside the export directory
+            let forwarder = if directory.rva <= rva && rva - directory.rva < directory.size {
+                Some(read_string(rva)?)
+            } else {
+                None
+            };
+
+            exports.push(Export {
+                name,
+                ordinal: ordinal_base.wrapping_add(index as u32),
+                forwarder,
+            });
+        }
+
+        Ok((remaining, ExportTable { name, exports }))
+    }
+
+    pub fn contains(&self, symbol: &ImportedSymbol) -> bool {
+        self.exports.iter().any(|export| match symbol {
+            ImportedSymbol::Name(name) => export.name.as_ref() == Some(name),
+            ImportedSymbol::Ordinal(ordinal) => export.ordinal == *ordinal as u32,
+        })
+    }
+
+    /// Export named `name`, names are case sensitive
+    pub fn find(&self, name: &str) -> Option<&Export> {
+        self.exports
+            .iter()
+            .find(|export| export.name.as_deref() == Some(name))
+    }
+
+    pub fn forwarded_dlls(&self) -> Vec<String> {
+        let mut dlls: Vec<String> = Vec::new();
+        for export in &self.exports {
+            if let Some(forwarder) = &export.forwarder {
+                if let Some((module, _)) = forwarder.rsplit_once('.') {
+                    let dll = format!("{}.dll", module.to_lowercase());
+                    if !dlls.contains(&dll) {
+                        dlls.push(dll);
+                    }
+                }
+            }
+        }
+
+        dlls
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn export_table() {
+        let mut data = vec![0u8; 0x100];
+
+        // Export directory at 0x00
+        data[0x0c..0x10].copy_from_slice(&0x60u32.to_le_bytes()); // name
+        data[0x10..0x14].copy_from_slice(&1u32.to_le_bytes()); // ordinal base
+        data[0x14..0x18].copy_from_slice(&3u32.to_le_bytes()); // number of functions
+        data[0x18..0x1c].copy_from_slice(&2u32.to_le_bytes()); // number of names
+        data[0x1c..0x20].copy_from_slice(&0x30u32.to_le_bytes()); // functions
+        data[0x20..0x24].copy_from_slice(&0x40u32.to_le_bytes()); // names
+        data[0x24..0x28].copy_from_slice(&0x48u32.to_le_bytes()); // name ordinals
+
+        // Functions: one local, one unused, one forwarded
+        data[0x30..0x34].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[0x38..0x3c].copy_from_slice(&0x70u32.to_le_bytes());
+
+        // Names
+        data[0x40..0x44].copy_from_slice(&0x80u32.to_le_bytes());
+        data[0x44..0x48].copy_from_slice(&0x90u32.to_le_bytes());
+        data[0x48..0x4a].copy_from_slice(&0u16.to_le_bytes());
+        data[0x4a..0x4c].copy_from_slice(&2u16.to_le_bytes());
+
+        data[0x60..0x67].copy_from_slice(b"foo.dll");
+        data[0x70..0x7f].copy_from_slice(b"NTDLL.RtlAction");
+        data[0x80..0x85].copy_from_slice(b"Local");
+        data[0x90..0x97].copy_from_slice(b"Forward");
+
+        let directory = DataDirectory {
+            rva: 0,
+            size: 0x100,
+        };
+        let export_table = ExportTable::parse(&data, directory, |rva| data.get(rva as usize..))
+            .unwrap()
+            .1;
+
+        assert_eq!(
+            export_table,
+            ExportTable {
+                name: "foo.dll".to_owned(),
+                exports: vec![
+                    Export {
+                        name: Some("Local".to_owned()),
+                        ordinal: 1,
+                        forwarder: None,
+                    },
+                    Export {
+                        name: Some("Forward".to_owned()),
+                        ordinal: 3,
+                        forwarder: Some("NTDLL.RtlAction".to_owned()),
+                    },
+                ],
+            }
+        );
+
+        assert_eq!(export_table.forwarded_dlls(), vec!["ntdll.dll".to_owned()]);
+
+        assert!(export_table.contains(&ImportedSymbol::Name("Local".to_owned())));
+        assert!(export_table.contains(&ImportedSymbol::Ordinal(3)));
+        assert!(!export_table.contains(&ImportedSymbol::Name("Missing".to_owned())));
+        assert!(!export_table.contains(&ImportedSymbol::Ordinal(2)));
+
+        assert_eq!(export_table.find("Forward").map(|export| export.ordinal), Some(3));
+        assert_eq!(export_table.find("forward"), None);
+
+        assert_eq!(
+            ExportTable::parse(&data, directory, |_| None),
+            Err(parse_error(PeError::RvaOutOfRange(0x60)))
+        );
+
+        // A huge function count is rejected before anything is allocated
+        data[0x14..0x18].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            ExportTable::parse(&data, directory, |rva| data.get(rva as usize..)),
+            Err(parse_error(PeError::Truncated("export address table")))
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use super::{
+    api_set_schema::ApiSetSchema,
+    bound_import_table::{BoundImport, BoundImportTable},
+    clr_header::{AssemblyReference, ClrHeader},
+    coff_header::CoffHeader,
+    debug_directory::{DebugDirectory, PdbInfo},
+    delay_import_table::DelayImportTable,
+    export_table::ExportTable,
+    import_table::{ImportTable, ImportedDll},
+    load_config::LoadConfig,
+    msdos_header::MsDosHeader,
+    optional_header::{DataDirectory, DataDirectoryIndex, OptionalHeader},
+    resource_directory::{Resource, ResourceDirectory, RT_MANIFEST, RT_VERSION},
+    rich_header::RichHeader,
+    section_table::SectionTable,
+    slice_from,
+    version_info::VersionInfo,
+    Architecture, Magic, ParseWarning, PeError, Subsystem,
+};
+
+const IMAGE_FILE_RELOCS_STRIPPED: u16 = 0x0001;
+const KERNEL_MODULES: [&str; 3] = ["ntoskrnl.exe", "ntkrnlpa.exe", "hal.dll"];
+const IMAGE_FILE_DLL: u16 = 0x2000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mitigation {
+    Aslr,
+    HighEntropyVa,
+    Dep,
+    Cfg,
+}
+
+impl Mitigation {
+    fn dll_characteristic(self) -> u16 {
+        match self {
+            Mitigation::Aslr => 0x0040,
+            Mitigation::HighEntropyVa => 0x0020,
+            Mitigation::Dep => 0x0100,
+            Mitigation::Cfg => 0x4000,
+        }
+    }
+}
+
+impl std::fmt::Display for Mitigation {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mitigation::Aslr => write!(formatter, "aslr"),
+            Mitigation::HighEntropyVa => write!(formatter, "high-entropy-va"),
+            Mitigation::Dep => write!(formatter, "dep"),
+            Mitigation::Cfg => write!(formatter, "cfg"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DependencyKind {
+    Import,
+    /// Loaded by the delay load helper on the first call of one of its functions
+    Delay,
+    Forwarder,
+    Assembly,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct File {
+    pub architecture: Option<Architecture>,
+    pub imports: Vec<ImportedDll>,
+    pub delay_imports: Vec<ImportedDll>,
+    pub exports: ExportTable,
+    pub bound_imports: Vec<BoundImport>,
+    pub assembly_references: Vec<AssemblyReference>,
+    pub version_info: Option<VersionInfo>,
+    pub manifest: Option<String>,
+    pub api_set_schema: Option<ApiSetSchema>,
+    pub rich_header: Option<RichHeader>,
+    pub checksum: u32,
+    pub pdb_info: Option<PdbInfo>,
+    pub timestamp: u32,
+    pub reproducible: bool,
+    pub dependent_load_flags: u16,
+    pub magic: Option<Magic>,
+    pub characteristics: u16,
+    pub subsystem: Option<Subsystem>,
+    /// Major and minor versions from the optional header
+    pub os_version: (u16, u16),
+    pub subsystem_version: (u16, u16),
+    /// Size of the image mapped in memory
+    pub size_of_image: u32,
+    pub dll_characteristics: u16,
+    pub image_base: u64,
+    pub entry_point: u32,
+    pub writable_executable_sections: Vec<String>,
+    pub has_code: bool,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl File {
+    pub fn new() -> Self {
+        Self {
+            architecture: None,
+            imports: Vec::new(),
+            delay_imports: Vec::new(),
+            exports: ExportTable::default(),
+            bound_imports: Vec::new(),
+            assembly_references: Vec::new(),
+            version_info: None,
+            manifest: None,
+            api_set_schema: None,
+            rich_header: None,
+            checksum: 0,
+            pdb_info: None,
+            timestamp: 0,
+            reproducible: false,
+            dependent_load_flags: 0,
+            magic: None,
+            characteristics: 0,
+            subsystem: None,
+            os_version: (0, 0),
+            subsystem_version: (0, 0),
+            size_of_image: 0,
+            dll_characteristics: 0,
+            image_base: 0,
+            entry_point: 0,
+            writable_executable_sections: Vec::new(),
+            has_code: false,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Parses an image held in any byte container: a buffer, a memory mapped file
+    /// or an image embedded in another file.
+    pub fn parse_from<D: AsRef<[u8]>>(data: D) -> Result<Self, PeError> {
+        File::parse(data.as_ref())
+    }
+
+    /// Parses the headers, which are required, then every data directory on a
+    /// best effort basis: a damaged directory is skipped and reported in `warnings`.
+    pub fn parse(data: &[u8]) -> Result<Self, PeError> {
+        // MSDOS header
+        let (_, msdos_header) = MsDosHeader::parse(data)?;
+
+        // Rich header, between the MSDOS stub and the PE header
+        let rich_header = data
+            .get(..msdos_header.pe_offset as usize)
+            .and_then(|stub| RichHeader::parse(stub).ok())
+            .map(|(_, rich_header)| rich_header);
+
+        // COFF header
+        let (input, coff_header) = CoffHeader::parse(
+            slice_from(data, msdos_header.pe_offset.into())
+                .ok_or(PeError::OffsetOutOfRange(msdos_header.pe_offset.into()))?,
+        )?;
+
+        // Optional header
+        let (input, optional_header) = OptionalHeader::parse(input)?;
+
+        // Section table
+        let (_, section_table) = SectionTable::parse(
+            input,
+            coff_header.number_of_sections,
+            optional_header.section_alignment(),
+            optional_header.size_of_headers(),
+        )?;
+
+        let rva_to_file_slice = |rva| {
+            let offset = section_table.rva_to_file_offset(rva)?;
+            slice_from(data, offset)
+        };
+
+        let directory_slice = |entry: DataDirectory| {
+            rva_to_file_slice(entry.rva).ok_or(PeError::RvaOutOfRange(entry.rva))
+        };
+
+        let mut warnings = Vec::new();
+
+        // Imports
+        let mut imports = Vec::new();
+        if let Some(import_table_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::Import)
+        {
+            if import_table_entry.rva != 0 {
+                let import_table = directory_slice(import_table_entry).and_then(|input| {
+                    Ok(ImportTable::parse(input, optional_header.magic(), rva_to_file_slice)?.1)
+                });
+
+                if let Some(import_table) = tolerate(&mut warnings, "import table", import_table) {
+                    imports = import_table.imports;
+                    warnings.extend(
+                        import_table
+                            .warnings
+                            .into_iter()
+                            .map(|error| ParseWarning::new("import table", error)),
+                    );
+                }
+            }
+        }
+
+        // Delay imports
+        let mut delay_imports = Vec::new();
+        if let Some(delay_import_table_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::DelayImport)
+        {
+            if delay_import_table_entry.rva != 0 {
+                let delay_import_table =
+                    directory_slice(delay_import_table_entry).and_then(|input| {
+                        Ok(DelayImportTable::parse(
+                            input,
+                            optional_header.magic(),
+                            optional_header.image_base(),
+                            rva_to_file_slice,
+                        )?
+                        .1)
+                    });
+
+                if let Some(delay_import_table) =
+                    tolerate(&mut warnings, "delay import table", delay_import_table)
+                {
+                    delay_imports = delay_import_table.imports;
+                    warnings.extend(
+                        delay_import_table
+                            .warnings
+                            .into_iter()
+                            .map(|error| ParseWarning::new("delay import table", error)),
+                    );
+                }
+            }
+        }
+
+        // Exports
+        let mut exports = ExportTable::default();
+        if let Some(export_table_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::Export)
+        {
+            if export_table_entry.rva != 0 {
+                let export_table = directory_slice(export_table_entry).and_then(|input| {
+                    Ok(ExportTable::parse(input, export_table_entry, rva_to_file_slice)?.1)
+                });
+
+                if let Some(export_table) = tolerate(&mut warnings, "export table", export_table) {
+                    exports = export_table;
+                }
+            }
+        }
+
+        // Bound imports
+        let mut bound_imports = Vec::new();
+        if let Some(bound_import_table_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::BoundImport)
+        {
+            if bound_import_table_entry.rva != 0 {
+                let bound_import_table = directory_slice(bound_import_table_entry)
+                    .and_then(|input| Ok(BoundImportTable::parse(input)?.1));
+
+                if let Some(bound_import_table) =
+                    tolerate(&mut warnings, "bound import table", bound_import_table)
+                {
+                    bound_imports = bound_import_table.imports;
+                }
+            }
+        }
+
+        // Managed assembly references
+        let mut assembly_references = Vec::new();
+        if let Some(clr_runtime_header_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::ClrRuntimeHeader)
+        {
+            if clr_runtime_header_entry.rva != 0 {
+                let clr_header = directory_slice(clr_runtime_header_entry)
+                    .and_then(|input| Ok(ClrHeader::parse(input, rva_to_file_slice)?.1));
+
+                if let Some(clr_header) = tolerate(&mut warnings, "CLR header", clr_header) {
+                    assembly_references = clr_header.assembly_references;
+                }
+            }
+        }
+
+        // Debug directory
+        let mut pdb_info = None;
+        let mut reproducible = false;
+        if let Some(debug_directory_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::Debug)
+        {
+            if debug_directory_entry.rva != 0 {
+                let debug_directory = directory_slice(debug_directory_entry).and_then(|input| {
+                    Ok(DebugDirectory::parse(input, debug_directory_entry.size, data)?.1)
+                });
+
+                if let Some(debug_directory) =
+                    tolerate(&mut warnings, "debug directory", debug_directory)
+                {
+                    pdb_info = debug_directory.pdb_info;
+                    reproducible = debug_directory.reproducible;
+                }
+            }
+        }
+
+        // Load config
+        let mut dependent_load_flags = 0;
+        if let Some(load_config_table_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::LoadConfig)
+        {
+            if load_config_table_entry.rva != 0 {
+                let load_config = directory_slice(load_config_table_entry)
+                    .and_then(|input| Ok(LoadConfig::parse(input, optional_header.magic())?.1));
+
+                if let Some(load_config) = tolerate(&mut warnings, "load config", load_config) {
+                    dependent_load_flags = load_config.dependent_load_flags;
+                }
+            }
+        }
+
+        // Resources
+        let mut version_info = None;
+        let mut manifest = None;
+        if let Some(resource_table_entry) =
+            optional_header.get_data_directory(DataDirectoryIndex::Resource)
+        {
+            if resource_table_entry.rva != 0 {
+                let resource_directory = directory_slice(resource_table_entry)
+                    .and_then(|input| Ok(ResourceDirectory::parse(input)?.1));
+
+                let read_resource = |resource: &Resource| {
+                    rva_to_file_slice(resource.rva)
+                        .ok_or(PeError::RvaOutOfRange(resource.rva))?
+                        .get(..resource.size as usize)
+                        .ok_or(PeError::Truncated("resource data"))
+                };
+
+                if let Some(resource_directory) =
+                    tolerate(&mut warnings, "resource directory", resource_directory)
+                {
+                    if let Some(resource) = resource_directory.find(RT_VERSION) {
+                        let version_resource = read_resource(resource)
+                            .and_then(|input| Ok(VersionInfo::parse(input)?.1));
+                        version_info =
+                            tolerate(&mut warnings, "version resource", version_resource);
+                    }
+
+                    if let Some(resource) = resource_directory.find(RT_MANIFEST) {
+                        let manifest_resource = read_resource(resource);
+                        if let Some(text) =
+                            tolerate(&mut warnings, "manifest resource", manifest_resource)
+                        {
+                            let text = String::from_utf8_lossy(text);
+                            manifest = Some(text.trim_start_matches('\u{feff}').to_owned());
+                        }
+                    }
+                }
+            }
+        }
+
+        // Api set map, only found in apisetschema.dll
+        let mut api_set_schema = None;
+        if let Some(section) = section_table
+            .sections()
+            .iter()
+            .find(|section| section.name() == ".apiset")
+        {
+            let schema = section
+                .raw_data(data)
+                .ok_or(PeError::Truncated(".apiset section"))
+                .and_then(|input| Ok(ApiSetSchema::parse(input)?.1));
+            api_set_schema = tolerate(&mut warnings, "api set schema", schema);
+        }
+
+        Ok(File {
+            architecture: Architecture::from_machine(coff_header.machine),
+            imports,
+            delay_imports,
+            exports,
+            bound_imports,
+            assembly_references,
+            version_info,
+            manifest,
+            api_set_schema,
+            rich_header,
+            checksum: optional_header.checksum(),
+            pdb_info,
+            timestamp: coff_header.time_date_stamp,
+            reproducible,
+            dependent_load_flags,
+            magic: Some(optional_header.magic()),
+            characteristics: coff_header.characteristics,
+            subsystem: Subsystem::from_value(optional_header.subsystem()),
+            os_version: optional_header.os_version(),
+            subsystem_version: optional_header.subsystem_version(),
+            size_of_image: optional_header.size_of_image(),
+            dll_characteristics: optional_header.dll_characteristics(),
+            image_base: optional_header.image_base(),
+            entry_point: optional_header.entry_point(),
+            writable_executable_sections: section_table
+                .sections()
+                .iter()
+                .filter(|section| section.is_writable_and_executable())
+                .map(|section| section.name().to_owned())
+                .collect(),
+            has_code: section_table
+                .sections()
+                .iter()
+                .any(|section| section.contains_code()),
+            warnings,
+        })
+    }
+
+    pub fn is_dll(&self) -> bool {
+        self.characteristics & IMAGE_FILE_DLL != 0
+    }
+
+    pub fn relocations_stripped(&self) -> bool {
+        self.characteristics & IMAGE_FILE_RELOCS_STRIPPED != 0
+    }
+
+    /// Whether the loader may map the image away from its preferred base, which
+    /// fails when relocations are stripped: dlls can collide with an already
+    /// loaded module and ASLR images are always rebased.
+    pub fn needs_relocations(&self) -> bool {
+        self.is_dll() || self.dll_characteristics & Mitigation::Aslr.dll_characteristic() != 0
+    }
+
+    /// Resource and MUI dlls: no code, no entry point and nothing imported, the
+    /// loader maps them as data and they never pull in other dlls.
+    pub fn is_resource_only(&self) -> bool {
+        self.is_dll()
+            && !self.has_code
+            && self.entry_point == 0
+            && self.imports.is_empty()
+            && self.delay_imports.is_empty()
+    }
+
+    /// Drivers and other kernel mode images: native subsystem and linked against
+    /// the kernel or the HAL.
+    pub fn is_kernel_image(&self) -> bool {
+        self.subsystem == Some(Subsystem::Native)
+            && self.imports.iter().any(|dll| {
+                KERNEL_MODULES
+                    .iter()
+                    .any(|module| dll.name.eq_ignore_ascii_case(module))
+            })
+    }
+
+    /// Number of functions imported from `name`, through its import lookup table or its
+    /// delay import name table
+    pub fn imported_symbol_count(&self, name: &str) -> Option<usize> {
+        self.imports
+            .iter()
+            .chain(&self.delay_imports)
+            .find(|dll| dll.name.eq_ignore_ascii_case(name))
+            .map(|dll| dll.symbols.len())
+    }
+
+    /// Mitigations that apply to this file, with whether they are enabled. High
+    /// entropy ASLR only exists for PE32+ images.
+    pub fn mitigations(&self) -> Vec<(Mitigation, bool)> {
+        let mut mitigations = vec![Mitigation::Aslr];
+        if self.magic == Some(Magic::Pe32Plus) {
+            mitigations.push(Mitigation::HighEntropyVa);
+        }
+        mitigations.push(Mitigation::Dep);
+        mitigations.push(Mitigation::Cfg);
+
+        mitigations
+            .into_iter()
+            .map(|mitigation| {
+                let flag = mitigation.dll_characteristic();
+                (mitigation, self.dll_characteristics & flag == flag)
+            })
+            .collect()
+    }
+
+    pub fn missing_mitigations(&self) -> Vec<Mitigation> {
+        self.mitigations()
+            .into_iter()
+            .filter(|(_, enabled)| !enabled)
+            .map(|(mitigation, _)| mitigation)
+            .collect()
+    }
+
+    /// Dlls the loader maps for this file: the imported dlls, the delay loaded ones, the
+    /// targets of forwarded exports and the referenced managed assemblies.
+    pub fn dependencies(&self) -> Vec<Dependency> {
+        let mut dependencies: Vec<Dependency> = Vec::new();
+
+        let mut add = |name: String, kind: DependencyKind| {
+            if !name.eq_ignore_ascii_case(&self.exports.name)
+                && !dependencies
+                    .iter()
+                    .any(|dependency| dependency.name.eq_ignore_ascii_case(&name))
+            {
+                dependencies.push(Dependency { name, kind });
+            }
+        };
+
+        for dll in &self.imports {
+            add(dll.name.clone(), DependencyKind::Import);
+        }
+
+        for dll in &self.delay_imports {
+            add(dll.name.clone(), DependencyKind::Delay);
+        }
+
+        for dll in self.exports.forwarded_dlls() {
+            add(dll, DependencyKind::Forwarder);
+        }
+
+        for assembly in &self.assembly_references {
+            add(format!("{}.dll", assembly.name), DependencyKind::Assembly);
+        }
+
+        dependencies
+    }
+}
+
+// Records a failed optional part of the file as a warning
+fn tolerate<T>(
+    warnings: &mut Vec<ParseWarning>,
+    structure: &'static str,
+    result: Result<T, PeError>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warnings.push(ParseWarning::new(structure, error));
+            None
+        }
+    }
+}
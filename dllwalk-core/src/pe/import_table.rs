@@ -0,0 +1,310 @@
+use nom::{
+    bytes::complete::take_while1,
+    error::context,
+    number::complete::{le_u16, le_u32, le_u64},
+    sequence::tuple,
+};
+
+use super::{parse_error, FileParseResult, Magic, PeError};
+
+#[derive(Debug, PartialEq, Eq)]
+struct DirectoryEntry {
+    import_lookup_table_rva: u32,
+    name_rva: u32,
+    import_address_table_rva: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportedSymbol {
+    Name(String),
+    Ordinal(u16),
+}
+
+impl std::fmt::Display for ImportedSymbol {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportedSymbol::Name(name) => write!(formatter, "{}", name),
+            ImportedSymbol::Ordinal(ordinal) => write!(formatter, "ordinal {}", ordinal),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportedDll {
+    pub name: String,
+    pub symbols: Vec<ImportedSymbol>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportTable {
+    pub imports: Vec<ImportedDll>,
+    pub warnings: Vec<PeError>,
+}
+
+impl ImportTable {
+    pub fn parse<'i>(
+        input: &'i [u8],
+        magic: Magic,
+        rva_to_file_slice: impl Fn(u32) -> Option<&'i [u8]>,
+    ) -> FileParseResult<'i, Self> {
+        let (remaining, directory_table) = ImportTable::parse_import_directory_table(input)?;
+
+        let mut imports = Vec::new();
+        let mut warnings = Vec::new();
+        for entry in &directory_table {
+            // Some linkers leave the lookup table empty and only fill the address table
+            let lookup_table_rva = if entry.import_lookup_table_rva != 0 {
+                entry.import_lookup_table_rva
+            } else {
+                entry.import_address_table_rva
+            };
+
+            imports.extend(ImportTable::read_imported_dll(
+                entry.name_rva,
+                lookup_table_rva,
+                magic,
+                &rva_to_file_slice,
+                &mut warnings,
+            ));
+        }
+
+        Ok((remaining, ImportTable { imports, warnings }))
+    }
+
+    /// Reads the name and the symbols of a dll from its name and its lookup table, which
+    /// the delay import table shares. A dll without a readable name cannot be resolved and
+    /// is skipped, damaged symbol data only loses symbols.
+    pub fn read_imported_dll<'i>(
+        name_rva: u32,
+        lookup_table_rva: u32,
+        magic: Magic,
+        rva_to_file_slice: &impl Fn(u32) -> Option<&'i [u8]>,
+        warnings: &mut Vec<PeError>,
+    ) -> Option<ImportedDll> {
+        let slice =
+            |rva| rva_to_file_slice(rva).ok_or_else(|| parse_error(PeError::RvaOutOfRange(rva)));
+
+        let name = match slice(name_rva).and_then(ImportTable::read_dll_name) {
+            Ok(name) => name,
+            Err(err) => {
+                warnings.push(err.into());
+                return None;
+            }
+        };
+
+        let lookup_table = slice(lookup_table_rva)
+            .and_then(|data| ImportTable::parse_import_lookup_table(data, magic));
+
+        let mut symbols = Vec::new();
+        match lookup_table {
+            Ok((_, lookup_table)) => {
+                for lookup_entry in lookup_table {
+                    match lookup_entry {
+                        LookupEntry::Ordinal(ordinal) => {
+                            symbols.push(ImportedSymbol::Ordinal(ordinal))
+                        }
+                        LookupEntry::HintName(rva) => {
+                            match slice(rva).and_then(ImportTable::read_symbol_name) {
+                                Ok(symbol) => symbols.push(ImportedSymbol::Name(symbol)),
+                                Err(err) => warnings.push(err.into()),
+                            }
+                        }
+                    }
+                }
+            }
+            Err(err) => warnings.push(err.into()),
+        }
+
+        Some(ImportedDll { name, symbols })
+    }
+
+    fn read_dll_name(input: &[u8]) -> Result<String, nom::Err<PeError>> {
+        let (_, name) = context("imported dll name", take_while1(|c| c != 0))(input)?;
+
+        Ok(std::str::from_utf8(name)
+            .map_err(|_| parse_error(PeError::InvalidData("imported dll name")))?
+            .to_owned())
+    }
+
+    // Hint/name entries start with the export name table index hint
+    fn read_symbol_name(input: &[u8]) -> Result<String, nom::Err<PeError>> {
+        let (_, (_hint, symbol)) = context(
+            "imported symbol name",
+            tuple((le_u16, take_while1(|c| c != 0))),
+        )(input)?;
+
+        Ok(String::from_utf8_lossy(symbol).into_owned())
+    }
+
+    fn parse_import_directory_table(mut input: &[u8]) -> FileParseResult<Vec<DirectoryEntry>> {
+        let mut entries = vec![];
+        loop {
+            let (remaining, entry) = context(
+                "import directory table",
+                tuple((le_u32, le_u32, le_u32, le_u32, le_u32)),
+            )(input)?;
+            input = remaining;
+
+            // Null entry, end of the table
+            if entry.0 == 0 && entry.4 == 0 {
+                break;
+            }
+
+            entries.push(DirectoryEntry {
+                import_lookup_table_rva: entry.0,
+                name_rva: entry.3,
+                import_address_table_rva: entry.4,
+            })
+        }
+
+        Ok((input, entries))
+    }
+
+    fn parse_import_lookup_table(
+        mut input: &[u8],
+        magic: Magic,
+    ) -> FileParseResult<Vec<LookupEntry>> {
+        let mut entries = vec![];
+        loop {
+            let (remaining, (ordinal_flag, value)) = match magic {
+                Magic::Pe32 => {
+                    let (remaining, entry) = context("import lookup table", le_u32)(input)?;
+                    (remaining, (entry & 0x8000_0000 != 0, entry))
+                }
+                Magic::Pe32Plus => {
+                    let (remaining, entry) = context("import lookup table", le_u64)(input)?;
+                    (remaining, (entry & 0x8000_0000_0000_0000 != 0, entry as u32))
+                }
+            };
+            input = remaining;
+
+            // Null entry, end of the table
+            if !ordinal_flag && value == 0 {
+                break;
+            }
+
+            entries.push(if ordinal_flag {
+                LookupEntry::Ordinal(value as u16)
+            } else {
+                LookupEntry::HintName(value & 0x7fff_ffff)
+            });
+        }
+
+        Ok((input, entries))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LookupEntry {
+    Ordinal(u16),
+    HintName(u32),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn import_directory_table() {
+        let data = vec![
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+            0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        assert_eq!(
+            ImportTable::parse_import_directory_table(&data).unwrap().1,
+            vec![
+                DirectoryEntry {
+                    import_lookup_table_rva: 0x03020100,
+                    name_rva: 0x0f0e0d0c,
+                    import_address_table_rva: 0x13121110,
+                },
+                DirectoryEntry {
+                    import_lookup_table_rva: 0x17161514,
+                    name_rva: 0x23222120,
+                    import_address_table_rva: 0x27262524,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn damaged_import_table() {
+        let mut data = vec![0u8; 0x100];
+
+        // Directory entries: unmapped name, unmapped lookup table, unmapped symbol name
+        let entries = [(0x80u32, 0x1000u32), (0x2000, 0x60), (0x80, 0x68)];
+        for (index, (lookup_table_rva, name_rva)) in entries.iter().enumerate() {
+            let offset = index * 20;
+            data[offset..offset + 4].copy_from_slice(&lookup_table_rva.to_le_bytes());
+            data[offset + 12..offset + 16].copy_from_slice(&name_rva.to_le_bytes());
+            data[offset + 16..offset + 20].copy_from_slice(&0x80u32.to_le_bytes());
+        }
+
+        data[0x60..0x65].copy_from_slice(b"b.dll");
+        data[0x68..0x6d].copy_from_slice(b"c.dll");
+        data[0x80..0x84].copy_from_slice(&0x3000u32.to_le_bytes());
+        data[0x84..0x88].copy_from_slice(&0x8000_0007u32.to_le_bytes());
+
+        let import_table = ImportTable::parse(&data, Magic::Pe32, |rva| data.get(rva as usize..))
+            .unwrap()
+            .1;
+
+        assert_eq!(
+            import_table,
+            ImportTable {
+                imports: vec![
+                    ImportedDll {
+                        name: "b.dll".to_owned(),
+                        symbols: vec![],
+                    },
+                    ImportedDll {
+                        name: "c.dll".to_owned(),
+                        symbols: vec![ImportedSymbol::Ordinal(7)],
+                    },
+                ],
+                warnings: vec![
+                    PeError::RvaOutOfRange(0x1000),
+                    PeError::RvaOutOfRange(0x2000),
+                    PeError::RvaOutOfRange(0x3000),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn import_lookup_table() {
+        // PE32
+        let data = vec![
+            0x10, 0x20, 0x00, 0x00, 0x05, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        assert_eq!(
+            ImportTable::parse_import_lookup_table(&data, Magic::Pe32)
+                .unwrap()
+                .1,
+            vec![LookupEntry::HintName(0x2010), LookupEntry::Ordinal(5)]
+        );
+
+        // PE32+
+        let data = vec![
+            0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        assert_eq!(
+            ImportTable::parse_import_lookup_table(&data, Magic::Pe32Plus)
+                .unwrap()
+                .1,
+            vec![LookupEntry::HintName(0x2010), LookupEntry::Ordinal(5)]
+        );
+
+        assert_eq!(
+            ImportTable::parse_import_lookup_table(&data[..12], Magic::Pe32Plus),
+            Err(parse_error(PeError::Truncated("import lookup table")))
+        );
+    }
+}
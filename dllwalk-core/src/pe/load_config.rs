@@ -0,0 +1,82 @@
+use nom::{
+    error::context,
+    number::complete::{le_u16, le_u32},
+};
+
+use super::{parse_error, FileParseResult, Magic, PeError};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LoadConfig {
+    pub dependent_load_flags: u16,
+}
+
+impl LoadConfig {
+    pub fn parse(input: &[u8], magic: Magic) -> FileParseResult<Self> {
+        let (_, size) = context("load config", le_u32)(input)?;
+
+        // The structure grew over time, fields past the declared size are absent
+        let dependent_load_flags_offset = match magic {
+            Magic::Pe32 => 0x36_usize,
+            Magic::Pe32Plus => 0x4e_usize,
+        };
+
+        let mut dependent_load_flags = 0;
+        if size as usize >= dependent_load_flags_offset + 2 {
+            let data = input
+                .get(dependent_load_flags_offset..)
+                .ok_or_else(|| parse_error(PeError::Truncated("load config")))?;
+            dependent_load_flags = context("load config", le_u16)(data)?.1;
+        }
+
+        Ok((
+            input,
+            LoadConfig {
+                dependent_load_flags,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn load_config() {
+        let mut data = vec![0u8; 0x50];
+        data[0x36] = 0x00;
+        data[0x37] = 0x08;
+        data[0x4e] = 0x00;
+        data[0x4f] = 0x10;
+
+        data[0] = 0x40;
+        assert_eq!(
+            LoadConfig::parse(&data, Magic::Pe32).unwrap().1,
+            LoadConfig {
+                dependent_load_flags: 0x0800
+            }
+        );
+
+        data[0] = 0x50;
+        assert_eq!(
+            LoadConfig::parse(&data, Magic::Pe32Plus).unwrap().1,
+            LoadConfig {
+                dependent_load_flags: 0x1000
+            }
+        );
+
+        // Older load config without the field
+        data[0] = 0x48;
+        assert_eq!(
+            LoadConfig::parse(&data, Magic::Pe32Plus).unwrap().1,
+            LoadConfig {
+                dependent_load_flags: 0
+            }
+        );
+
+        assert_eq!(
+            LoadConfig::parse(&[0x50, 0x00, 0x00, 0x00], Magic::Pe32),
+            Err(parse_error(PeError::Truncated("load config")))
+        );
+    }
+}
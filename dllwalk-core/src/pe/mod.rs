@@ -0,0 +1,232 @@
+//! Parser of the Portable Executable format of Windows images and dlls
+
+mod api_set_schema;
+mod bound_import_table;
+mod checksum;
+mod clr_header;
+mod coff_header;
+mod debug_directory;
+mod delay_import_table;
+mod error;
+mod export_table;
+mod file;
+mod import_table;
+mod load_config;
+mod msdos_header;
+mod optional_header;
+mod resource_directory;
+mod rich_header;
+mod section_table;
+mod version_info;
+
+pub use api_set_schema::ApiSetSchema;
+pub use bound_import_table::{BoundForwarder, BoundImport};
+pub use checksum::compute_checksum;
+pub use clr_header::AssemblyReference;
+pub use debug_directory::PdbInfo;
+pub use error::{ParseWarning, PeError};
+pub use export_table::{Export, ExportTable};
+pub use file::{Dependency, DependencyKind, File, Mitigation};
+pub use import_table::{ImportedDll, ImportedSymbol};
+pub use rich_header::{RichEntry, RichHeader};
+pub use version_info::{StringTable, VersionInfo};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Architecture {
+    X86,
+    X64,
+    Arm,
+    Arm64,
+    Arm64EC,
+}
+
+impl Architecture {
+    pub fn from_machine(machine: u16) -> Option<Self> {
+        match machine {
+            0x014c => Some(Architecture::X86),
+            0x8664 => Some(Architecture::X64),
+            0x01c0 | 0x01c2 | 0x01c4 => Some(Architecture::Arm),
+            0xaa64 | 0xa64e => Some(Architecture::Arm64),
+            0xa641 => Some(Architecture::Arm64EC),
+            _ => None,
+        }
+    }
+
+    /// Machine type of the images built for this architecture, the Thumb-2 one for Arm
+    pub fn machine(self) -> u16 {
+        match self {
+            Architecture::X86 => 0x014c,
+            Architecture::X64 => 0x8664,
+            Architecture::Arm => 0x01c4,
+            Architecture::Arm64 => 0xaa64,
+            Architecture::Arm64EC => 0xa641,
+        }
+    }
+
+    /// Architecture of an image, read from its headers without parsing the rest of it
+    pub fn from_image(data: &[u8]) -> Option<Self> {
+        let (_, msdos_header) = msdos_header::MsDosHeader::parse(data).ok()?;
+        let pe_header = slice_from(data, msdos_header.pe_offset.into())?;
+        let (_, coff_header) = coff_header::CoffHeader::parse(pe_header).ok()?;
+        Architecture::from_machine(coff_header.machine)
+    }
+
+    pub fn is_compatible_with(self, other: Architecture) -> bool {
+        // Arm64EC code shares the process with x64 code
+        self == other
+            || matches!(
+                (self, other),
+                (Architecture::X64, Architecture::Arm64EC) | (Architecture::Arm64EC, Architecture::X64)
+            )
+    }
+}
+
+impl std::fmt::Display for Architecture {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Architecture::X86 => write!(formatter, "x86"),
+            Architecture::X64 => write!(formatter, "x64"),
+            Architecture::Arm => write!(formatter, "arm"),
+            Architecture::Arm64 => write!(formatter, "arm64"),
+            Architecture::Arm64EC => write!(formatter, "arm64ec"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    Native,
+    WindowsGui,
+    WindowsCui,
+    PosixCui,
+    WindowsCeGui,
+    EfiApplication,
+    EfiBootServiceDriver,
+    EfiRuntimeDriver,
+    EfiRom,
+    Xbox,
+    WindowsBootApplication,
+}
+
+impl Subsystem {
+    pub fn from_value(subsystem: u16) -> Option<Self> {
+        match subsystem {
+            1 => Some(Subsystem::Native),
+            2 => Some(Subsystem::WindowsGui),
+            3 => Some(Subsystem::WindowsCui),
+            7 => Some(Subsystem::PosixCui),
+            9 => Some(Subsystem::WindowsCeGui),
+            10 => Some(Subsystem::EfiApplication),
+            11 => Some(Subsystem::EfiBootServiceDriver),
+            12 => Some(Subsystem::EfiRuntimeDriver),
+            13 => Some(Subsystem::EfiRom),
+            14 => Some(Subsystem::Xbox),
+            16 => Some(Subsystem::WindowsBootApplication),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Subsystem {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Subsystem::Native => write!(formatter, "native"),
+            Subsystem::WindowsGui => write!(formatter, "windows-gui"),
+            Subsystem::WindowsCui => write!(formatter, "windows-cui"),
+            Subsystem::PosixCui => write!(formatter, "posix-cui"),
+            Subsystem::WindowsCeGui => write!(formatter, "windows-ce-gui"),
+            Subsystem::EfiApplication => write!(formatter, "efi-application"),
+            Subsystem::EfiBootServiceDriver => write!(formatter, "efi-boot-service-driver"),
+            Subsystem::EfiRuntimeDriver => write!(formatter, "efi-runtime-driver"),
+            Subsystem::EfiRom => write!(formatter, "efi-rom"),
+            Subsystem::Xbox => write!(formatter, "xbox"),
+            Subsystem::WindowsBootApplication => write!(formatter, "windows-boot-application"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Magic {
+    Pe32,
+    Pe32Plus,
+}
+
+type FileParseResult<'i, T> = nom::IResult<&'i [u8], T, PeError>;
+
+fn parse_error(error: PeError) -> nom::Err<PeError> {
+    nom::Err::Error(error)
+}
+
+// `nom::multi::count` reserves room for every element before parsing, so a count
+// read from the file is checked against the remaining input first
+fn bounded_count<'i, O, F>(
+    mut parser: F,
+    count: usize,
+    element_size: usize,
+) -> impl FnMut(&'i [u8]) -> FileParseResult<'i, Vec<O>>
+where
+    F: nom::Parser<&'i [u8], O, PeError>,
+{
+    move |input: &'i [u8]| {
+        match count.checked_mul(element_size) {
+            Some(size) if size <= input.len() => {}
+            _ => return Err(parse_error(PeError::Nom(nom::error::ErrorKind::Count))),
+        }
+
+        nom::multi::count(|input| parser.parse(input), count)(input)
+    }
+}
+
+// File offsets are 64 bits wide, those that don't fit in memory are past the end
+// of the data anyway
+fn slice_from(data: &[u8], offset: u64) -> Option<&[u8]> {
+    data.get(usize::try_from(offset).ok()?..)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn architecture() {
+        assert_eq!(Architecture::from_machine(0x014c), Some(Architecture::X86));
+        assert_eq!(Architecture::from_machine(0xaa64), Some(Architecture::Arm64));
+        assert_eq!(Architecture::from_machine(0x0000), None);
+        let arm = Architecture::from_machine(Architecture::Arm.machine());
+        assert_eq!(arm, Some(Architecture::Arm));
+
+        let mut image = vec![0; 0x58];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c] = 0x40;
+        image[0x40..0x46].copy_from_slice(&[0x50, 0x45, 0x00, 0x00, 0x64, 0x86]);
+        assert_eq!(Architecture::from_image(&image), Some(Architecture::X64));
+        assert_eq!(Architecture::from_image(&image[..0x40]), None);
+
+        assert_eq!(Architecture::X64.is_compatible_with(Architecture::Arm64EC), true);
+        assert_eq!(Architecture::X64.is_compatible_with(Architecture::X86), false);
+        assert_eq!(Architecture::Arm64.is_compatible_with(Architecture::Arm64EC), false);
+    }
+
+    #[test]
+    fn subsystem() {
+        assert_eq!(Subsystem::from_value(2), Some(Subsystem::WindowsGui));
+        assert_eq!(Subsystem::from_value(1), Some(Subsystem::Native));
+        assert_eq!(Subsystem::from_value(0), None);
+    }
+
+    #[test]
+    fn bounded_count() {
+        let data = [0x01, 0x00, 0x02, 0x00];
+        let mut parser = super::bounded_count(nom::number::complete::le_u16, 2, 2);
+        assert_eq!(parser(&data[..]), Ok((&data[4..], vec![1, 2])));
+
+        let mut parser = super::bounded_count(nom::number::complete::le_u16, usize::MAX, 2);
+        assert_eq!(
+            parser(&data[..]),
+            Err(parse_error(PeError::Nom(nom::error::ErrorKind::Count)))
+        );
+
+        assert_eq!(slice_from(&data, 3), Some(&data[3..]));
+        assert_eq!(slice_from(&data, u64::MAX), None);
+    }
+}
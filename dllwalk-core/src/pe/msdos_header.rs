@@ -1,10 +1,11 @@
 use nom::{
     bytes::complete::{tag, take},
+    error::context,
     number::complete::le_u32,
     sequence::tuple,
 };
 
-use super::FileParseResult;
+use super::{parse_error, FileParseResult, PeError};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct MsDosHeader {
@@ -13,8 +14,10 @@ pub struct MsDosHeader {
 
 impl MsDosHeader {
     pub fn parse(input: &[u8]) -> FileParseResult<Self> {
-        let (input, (_, _, pe_offset)) =
-            tuple((tag("MZ".as_bytes()), take(0x3a_usize), le_u32))(input)?;
+        let (input, _) = tag("MZ".as_bytes())(input)
+            .map_err(|_: nom::Err<PeError>| parse_error(PeError::InvalidDosSignature))?;
+        let (input, (_, pe_offset)) =
+            context("MSDOS header", tuple((take(0x3a_usize), le_u32)))(input)?;
 
         Ok((input, MsDosHeader { pe_offset }))
     }
@@ -41,6 +44,13 @@ mod test {
             }
         );
 
-        assert_eq!(MsDosHeader::parse(&vec![0u8; 100]).is_err(), true);
+        assert_eq!(
+            MsDosHeader::parse(&vec![0u8; 100]),
+            Err(parse_error(PeError::InvalidDosSignature))
+        );
+        assert_eq!(
+            MsDosHeader::parse(&data[..0x20]),
+            Err(parse_error(PeError::Truncated("MSDOS header")))
+        );
     }
 }
@@ -0,0 +1,310 @@
+use nom::{
+    bytes::complete::take,
+    error::context,
+    number::complete::{le_u16, le_u32, le_u64},
+    sequence::tuple,
+};
+
+use super::{bounded_count, parse_error, FileParseResult, Magic, PeError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataDirectory {
+    pub rva: u32,
+    pub size: u32,
+}
+
+/// Position of each data directory in the optional header
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataDirectoryIndex {
+    Export = 0,
+    Import = 1,
+    Resource = 2,
+    Exception = 3,
+    Certificate = 4,
+    BaseRelocation = 5,
+    Debug = 6,
+    Architecture = 7,
+    GlobalPointer = 8,
+    Tls = 9,
+    LoadConfig = 10,
+    BoundImport = 11,
+    ImportAddressTable = 12,
+    DelayImport = 13,
+    ClrRuntimeHeader = 14,
+    Reserved = 15,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OptionalHeader {
+    magic: Magic,
+    entry_point: u32,
+    image_base: u64,
+    section_alignment: u32,
+    os_version: (u16, u16),
+    subsystem_version: (u16, u16),
+    size_of_image: u32,
+    size_of_headers: u32,
+    checksum: u32,
+    subsystem: u16,
+    dll_characteristics: u16,
+    data_directories: Vec<DataDirectory>,
+}
+
+impl OptionalHeader {
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let (remaining, magic) = context("optional header", le_u16)(input)?;
+
+        let magic = match magic {
+            0x010b => Magic::Pe32,
+            0x020b => Magic::Pe32Plus,
+            _ => return Err(parse_error(PeError::InvalidOptionalHeaderMagic(magic))),
+        };
+
+        let (remaining, (_, entry_point, _)) = context(
+            "optional header",
+            tuple((take(14_usize), le_u32, take(4_usize))),
+        )(remaining)?;
+
+        // PE32 stores BaseOfData where PE32+ starts its 64 bits image base
+        let (remaining, image_base) = match magic {
+            Magic::Pe32 => {
+                let (remaining, (_, image_base)) =
+                    context("optional header", tuple((take(4_usize), le_u32)))(remaining)?;
+                (remaining, image_base as u64)
+            }
+            Magic::Pe32Plus => context("optional header", le_u64)(remaining)?,
+        };
+
+        let (
+            input,
+            (
+                section_alignment,
+                (_, os_major, os_minor, _, subsystem_major, subsystem_minor, _, size_of_image),
+                size_of_headers,
+                checksum,
+                subsystem,
+                dll_characteristics,
+                _,
+                number_of_rva_and_sizes,
+            ),
+        ) = context(
+            "optional header",
+            tuple((
+                le_u32,
+                tuple((
+                    take(4_usize),
+                    le_u16,
+                    le_u16,
+                    take(4_usize),
+                    le_u16,
+                    le_u16,
+                    take(4_usize),
+                    le_u32,
+                )),
+                le_u32,
+                le_u32,
+                le_u16,
+                le_u16,
+                take(if magic == Magic::Pe32 {
+                    20_usize
+                } else {
+                    36_usize
+                }),
+                le_u32,
+            )),
+        )(remaining)?;
+
+        // Data directories
+        let (input, data_directories) = context(
+            "data directories",
+            bounded_count(tuple((le_u32, le_u32)), number_of_rva_and_sizes as usize, 8),
+        )(input)?;
+
+        let data_directories = data_directories
+            .iter()
+            .map(|pair| DataDirectory {
+                rva: pair.0,
+                size: pair.1,
+            })
+            .collect();
+
+        Ok((
+            input,
+            OptionalHeader {
+                magic,
+                entry_point,
+                image_base,
+                section_alignment,
+                os_version: (os_major, os_minor),
+                subsystem_version: (subsystem_major, subsystem_minor),
+                size_of_image,
+                size_of_headers,
+                checksum,
+                subsystem,
+                dll_characteristics,
+                data_directories,
+            },
+        ))
+    }
+
+    pub fn magic(&self) -> Magic {
+        self.magic
+    }
+
+    pub fn entry_point(&self) -> u32 {
+        self.entry_point
+    }
+
+    pub fn image_base(&self) -> u64 {
+        self.image_base
+    }
+
+    pub fn section_alignment(&self) -> u32 {
+        self.section_alignment
+    }
+
+    /// Major and minor version of the operating system the image was built for
+    pub fn os_version(&self) -> (u16, u16) {
+        self.os_version
+    }
+
+    /// Major and minor version of the subsystem, the loader refuses to start images
+    /// requiring a later version than the running Windows
+    pub fn subsystem_version(&self) -> (u16, u16) {
+        self.subsystem_version
+    }
+
+    /// Size of the image mapped in memory, headers and sections aligned on the section
+    /// alignment
+    pub fn size_of_image(&self) -> u32 {
+        self.size_of_image
+    }
+
+    pub fn size_of_headers(&self) -> u32 {
+        self.size_of_headers
+    }
+
+    pub fn checksum(&self) -> u32 {
+        self.checksum
+    }
+
+    pub fn subsystem(&self) -> u16 {
+        self.subsystem
+    }
+
+    pub fn dll_characteristics(&self) -> u16 {
+        self.dll_characteristics
+    }
+
+    pub fn get_data_directory(&self, index: DataDirectoryIndex) -> Option<DataDirectory> {
+        self.data_directories.get(index as usize).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn optional_header() {
+        // x86
+        let data = vec![
+            0x0b, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x40, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x30, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x78, 0x56, 0x34, 0x12, 0x02, 0x00,
+            0x40, 0x81, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01,
+            0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+        ];
+
+        assert_eq!(
+            OptionalHeader::parse(&data).unwrap().1,
+            OptionalHeader {
+                magic: Magic::Pe32,
+                entry_point: 0x2010,
+                image_base: 0x400000,
+                section_alignment: 0x1000,
+                os_version: (6, 1),
+                subsystem_version: (6, 0),
+                size_of_image: 0x3000,
+                size_of_headers: 0x400,
+                checksum: 0x12345678,
+                subsystem: 2,
+                dll_characteristics: 0x8140,
+                data_directories: vec![
+                    DataDirectory {
+                        rva: 0x03020100,
+                        size: 0x07060504
+                    },
+                    DataDirectory {
+                        rva: 0x0b0a0908,
+                        size: 0x0f0e0d0c
+                    },
+                ]
+            }
+        );
+
+        let optional_header = OptionalHeader::parse(&data).unwrap().1;
+        assert_eq!(
+            optional_header.get_data_directory(DataDirectoryIndex::Import),
+            Some(DataDirectory {
+                rva: 0x0b0a0908,
+                size: 0x0f0e0d0c
+            })
+        );
+        assert_eq!(optional_header.get_data_directory(DataDirectoryIndex::Resource), None);
+
+        // x64
+        let data = vec![
+            0x0b, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x01, 0x00, 0x00, 0x04, 0x00, 0x00, 0x78, 0x56, 0x34, 0x12, 0x03, 0x00,
+            0x60, 0x41, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+
+        assert_eq!(
+            OptionalHeader::parse(&data).unwrap().1,
+            OptionalHeader {
+                magic: Magic::Pe32Plus,
+                entry_point: 0x2010,
+                image_base: 0x180000000,
+                section_alignment: 0x1000,
+                os_version: (0, 0),
+                subsystem_version: (0, 0),
+                size_of_image: 0x10000,
+                size_of_headers: 0x400,
+                checksum: 0x12345678,
+                subsystem: 3,
+                dll_characteristics: 0x4160,
+                data_directories: vec![
+                    DataDirectory {
+                        rva: 0x03020100,
+                        size: 0x07060504
+                    },
+                    DataDirectory {
+                        rva: 0x0b0a0908,
+                        size: 0x0f0e0d0c
+                    },
+                ]
+            }
+        );
+
+        assert_eq!(
+            OptionalHeader::parse(&[0x07, 0x01, 0x00, 0x00]),
+            Err(parse_error(PeError::InvalidOptionalHeaderMagic(0x0107)))
+        );
+        assert_eq!(
+            OptionalHeader::parse(&data[..100]),
+            Err(parse_error(PeError::Truncated("optional header")))
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use nom::{
+    error::context,
+    multi::count,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::{parse_error, FileParseResult, PeError};
+
+pub const RT_VERSION: u32 = 16;
+pub const RT_MANIFEST: u32 = 24;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResourceId {
+    Id(u32),
+    Name(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub resource_type: ResourceId,
+    pub name: ResourceId,
+    pub language: ResourceId,
+    pub rva: u32,
+    pub size: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ResourceDirectory {
+    pub resources: Vec<Resource>,
+}
+
+type Entries = Vec<(ResourceId, u32)>;
+
+impl ResourceDirectory {
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let mut resources = Vec::new();
+
+        // The tree is always three levels deep: type, name and language
+        for (resource_type, offset) in ResourceDirectory::parse_table(input, 0)? {
+            let offset = match ResourceDirectory::subdirectory_offset(offset) {
+                Some(offset) => offset,
+                None => continue,
+            };
+
+            for (name, offset) in ResourceDirectory::parse_table(input, offset)? {
+                let offset = match ResourceDirectory::subdirectory_offset(offset) {
+                    Some(offset) => offset,
+                    None => continue,
+                };
+
+                for (language, offset) in ResourceDirectory::parse_table(input, offset)? {
+                    if ResourceDirectory::subdirectory_offset(offset).is_some() {
+                        continue;
+                    }
+
+                    let data = input
+                        .get(offset as usize..)
+                        .ok_or_else(|| parse_error(PeError::Truncated("resource directory")))?;
+                    let (_, (rva, size)) =
+                        context("resource data entry", tuple((le_u32, le_u32)))(data)?;
+
+                    resources.push(Resource {
+                        resource_type: resource_type.clone(),
+                        name: name.clone(),
+                        language,
+                        rva,
+                        size,
+                    });
+                }
+            }
+        }
+
+        Ok((input, ResourceDirectory { resources }))
+    }
+
+    pub fn find(&self, resource_type: u32) -> Option<&Resource> {
+        self.resources
+            .iter()
+            .find(|resource| resource.resource_type == ResourceId::Id(resource_type))
+    }
+
+    fn subdirectory_offset(offset: u32) -> Option<u32> {
+        if offset & 0x8000_0000 != 0 {
+            Some(offset & 0x7fff_ffff)
+        } else {
+            None
+        }
+    }
+
+    fn parse_table(input: &[u8], offset: u32) -> Result<Entries, nom::Err<PeError>> {
+        let data = input
+            .get(offset as usize..)
+            .ok_or_else(|| parse_error(PeError::Truncated("resource directory")))?;
+
+        let (mut data, (_, _, _, _, number_of_named_entries, number_of_id_entries)) = context(
+            "resource directory table",
+            tuple((le_u32, le_u32, le_u16, le_u16, le_u16, le_u16)),
+        )(data)?;
+
+        let mut entries = Vec::new();
+        for _ in 0..number_of_named_entries as u32 + number_of_id_entries as u32 {
+            let (remaining, (name, offset)) =
+                context("resource directory entry", tuple((le_u32, le_u32)))(data)?;
+            data = remaining;
+
+            let id = if name & 0x8000_0000 != 0 {
+                ResourceId::Name(ResourceDirectory::read_name(input, name & 0x7fff_ffff)?)
+            } else {
+                ResourceId::Id(name)
+            };
+
+            entries.push((id, offset));
+        }
+
+        Ok(entries)
+    }
+
+    // Names are length prefixed UTF-16 strings
+    fn read_name(input: &[u8], offset: u32) -> Result<String, nom::Err<PeError>> {
+        let data = input
+            .get(offset as usize..)
+            .ok_or_else(|| parse_error(PeError::Truncated("resource directory")))?;
+
+        let (data, length) = context("resource name", le_u16)(data)?;
+        let (_, name) = context("resource name", count(le_u16, length as usize))(data)?;
+
+        Ok(String::from_utf16_lossy(&name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resource_directory() {
+        let data = vec![
+            // Root: one id entry
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x10, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x80,
+            // Names: one named entry
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+            0x00, 0x00, 0x58, 0x00, 0x00, 0x80, 0x30, 0x00, 0x00, 0x80,
+            // Languages: one data entry
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x09, 0x04, 0x00, 0x00, 0x48, 0x00, 0x00, 0x00,
+            // Data entry
+            0x00, 0x20, 0x00, 0x00, 0x34, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+            // Name
+            0x02, 0x00, 0x41, 0x00, 0x42, 0x00,
+        ];
+
+        let directory = ResourceDirectory::parse(&data).unwrap().1;
+        assert_eq!(
+            directory,
+            ResourceDirectory {
+                resources: vec![Resource {
+                    resource_type: ResourceId::Id(RT_VERSION),
+                    name: ResourceId::Name("AB".to_owned()),
+                    language: ResourceId::Id(0x0409),
+                    rva: 0x2000,
+                    size: 0x134,
+                }],
+            }
+        );
+
+        assert_eq!(directory.find(RT_VERSION).is_some(), true);
+        assert_eq!(directory.find(RT_MANIFEST).is_none(), true);
+    }
+}
@@ -0,0 +1,129 @@
+use nom::number::complete::le_u32;
+
+use super::{parse_error, FileParseResult, PeError};
+
+const RICH_SIGNATURE: u32 = 0x68636952; // "Rich"
+const DANS_SIGNATURE: u32 = 0x536e6144; // "DanS"
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RichEntry {
+    pub product_id: u16,
+    pub build: u16,
+    pub count: u32,
+}
+
+impl RichEntry {
+    /// Name of the tool for the product ids of the Visual Studio 2015+ toolchain
+    pub fn product_name(&self) -> Option<&'static str> {
+        match self.product_id {
+            0x0001 => Some("Import0"),
+            0x00ff => Some("Cvtres1400"),
+            0x0100 => Some("Export1400"),
+            0x0101 => Some("Implib1400"),
+            0x0102 => Some("Linker1400"),
+            0x0103 => Some("Masm1400"),
+            0x0104 => Some("Utc1900_C"),
+            0x0105 => Some("Utc1900_CPP"),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RichEntry {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.product_name() {
+            Some(name) => write!(formatter, "{} build {}", name, self.build)?,
+            None => write!(formatter, "product 0x{:04x} build {}", self.product_id, self.build)?,
+        }
+        write!(formatter, " (x{})", self.count)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RichHeader {
+    pub entries: Vec<RichEntry>,
+}
+
+impl RichHeader {
+    /// Parses the Rich header from the data preceding the PE header. The header
+    /// is XOR-encoded with the key that follows the "Rich" marker and starts at
+    /// the "DanS" marker.
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let read_u32 = |offset: usize| -> Result<u32, nom::Err<PeError>> {
+            let data = input
+                .get(offset..)
+                .ok_or_else(|| parse_error(PeError::Truncated("Rich header")))?;
+            Ok(le_u32(data)?.1)
+        };
+
+        let rich_offset = (0..input.len().saturating_sub(7))
+            .step_by(4)
+            .rev()
+            .find(|offset| read_u32(*offset) == Ok(RICH_SIGNATURE))
+            .ok_or_else(|| parse_error(PeError::InvalidData("Rich header")))?;
+        let key = read_u32(rich_offset + 4)?;
+
+        let mut dans_offset = rich_offset;
+        loop {
+            dans_offset = dans_offset
+                .checked_sub(4)
+                .ok_or_else(|| parse_error(PeError::InvalidData("Rich header")))?;
+            if read_u32(dans_offset)? ^ key == DANS_SIGNATURE {
+                break;
+            }
+        }
+
+        // The marker is followed by three padding values
+        let mut entries = Vec::new();
+        let mut offset = dans_offset + 16;
+        while offset + 8 <= rich_offset {
+            let comp_id = read_u32(offset)? ^ key;
+            let count = read_u32(offset + 4)? ^ key;
+
+            entries.push(RichEntry {
+                product_id: (comp_id >> 16) as u16,
+                build: comp_id as u16,
+                count,
+            });
+            offset += 8;
+        }
+
+        Ok((&input[rich_offset + 8..], RichHeader { entries }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rich_header() {
+        let key = 0x12345678_u32;
+        let mut data = vec![0u8; 8];
+        for value in [DANS_SIGNATURE, 0, 0, 0, 0x0104_7809, 3, 0x0102_7809, 1] {
+            data.extend_from_slice(&(value ^ key).to_le_bytes());
+        }
+        data.extend_from_slice(&RICH_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&key.to_le_bytes());
+
+        assert_eq!(
+            RichHeader::parse(&data).unwrap().1,
+            RichHeader {
+                entries: vec![
+                    RichEntry {
+                        product_id: 0x0104,
+                        build: 30729,
+                        count: 3
+                    },
+                    RichEntry {
+                        product_id: 0x0102,
+                        build: 30729,
+                        count: 1
+                    },
+                ]
+            }
+        );
+
+        assert_eq!(RichHeader::parse(&vec![0u8; 100]).is_err(), true);
+    }
+}
@@ -0,0 +1,253 @@
+use nom::{
+    bytes::complete::take,
+    error::context,
+    multi::count,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::FileParseResult;
+
+const IMAGE_SCN_CNT_CODE: u32 = 0x00000020;
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x80000000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Section {
+    name: String,
+    virtual_size: u32,
+    virtual_address: u32,
+    raw_data_size: u32,
+    raw_data_address: u32,
+    characteristics: u32,
+}
+
+impl Section {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Data of the section stored in the file
+    pub fn raw_data<'d>(&self, data: &'d [u8]) -> Option<&'d [u8]> {
+        data.get(self.raw_data_address as usize..)?
+            .get(..self.raw_data_size as usize)
+    }
+
+    pub fn contains_code(&self) -> bool {
+        self.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) != 0
+    }
+
+    pub fn is_writable_and_executable(&self) -> bool {
+        let flags = IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_WRITE;
+        self.characteristics & flags == flags
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SectionTable {
+    sections: Vec<Section>,
+    section_alignment: u32,
+    size_of_headers: u32,
+}
+
+impl SectionTable {
+    pub fn parse(
+        input: &[u8],
+        number_of_sections: u16,
+        section_alignment: u32,
+        size_of_headers: u32,
+    ) -> FileParseResult<Self> {
+        let (input, data) = context(
+            "section table",
+            count(
+                tuple((
+                    take(8_usize),
+                    le_u32,
+                    le_u32,
+                    le_u32,
+                    le_u32,
+                    le_u32,
+                    le_u32,
+                    le_u16,
+                    le_u16,
+                    le_u32,
+                )),
+                number_of_sections as usize,
+            ),
+        )(input)?;
+
+        let sections = data
+            .iter()
+            .map(|data| Section {
+                name: String::from_utf8_lossy(data.0)
+                    .trim_end_matches(|c| c == '\0')
+                    .to_owned(),
+                virtual_size: data.1,
+                virtual_address: data.2,
+                raw_data_size: data.3,
+                raw_data_address: data.4,
+                characteristics: data.9,
+            })
+            .collect();
+
+        Ok((
+            input,
+            SectionTable {
+                sections,
+                section_alignment,
+                size_of_headers,
+            },
+        ))
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Maps an RVA to a file offset the way the loader lays the image out:
+    /// sections span their virtual size rounded up to the section alignment
+    /// (the raw size when the virtual size is zero) and anything below the size
+    /// of the headers is mapped as is.
+    pub fn rva_to_file_offset(&self, rva: u32) -> Option<u64> {
+        for section in &self.sections {
+            let virtual_size = match section.virtual_size {
+                0 => section.raw_data_size,
+                size => size,
+            };
+            let virtual_end = align_up(
+                section.virtual_address,
+                virtual_size,
+                self.section_alignment,
+            );
+
+            if section.virtual_address <= rva && (rva as u64) < virtual_end {
+                let offset = rva - section.virtual_address;
+                // The rest of the section is zero filled and not backed by the file
+                if offset >= section.raw_data_size {
+                    return None;
+                }
+
+                return Some(section.raw_data_address as u64 + offset as u64);
+            }
+        }
+
+        if rva < self.size_of_headers {
+            return Some(rva.into());
+        }
+
+        None
+    }
+}
+
+fn align_up(address: u32, size: u32, alignment: u32) -> u64 {
+    let end = address as u64 + size as u64;
+    match alignment as u64 {
+        0 => end,
+        alignment => (end + alignment - 1) / alignment * alignment,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn section_table() {
+        // x86
+        let data = vec![
+            0x2e, 0x69, 0x64, 0x61, 0x74, 0x61, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05,
+            0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0xe0, 0x61, 0x61,
+            0x61, 0x61, 0x61, 0x61, 0x61, 0x61, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+            0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x40,
+        ];
+
+        let section_table = SectionTable::parse(&data, 2, 0x1000, 0x400).unwrap().1;
+        assert_eq!(
+            section_table.sections()[0].is_writable_and_executable(),
+            true
+        );
+        assert_eq!(
+            section_table.sections()[1].is_writable_and_executable(),
+            false
+        );
+        assert_eq!(section_table.sections()[0].contains_code(), true);
+        assert_eq!(section_table.sections()[1].contains_code(), false);
+
+        assert_eq!(
+            section_table,
+            SectionTable {
+                sections: vec![
+                    Section {
+                        name: ".idata".to_owned(),
+                        virtual_size: 0x03020100,
+                        virtual_address: 0x07060504,
+                        raw_data_size: 0x0b0a0908,
+                        raw_data_address: 0x0f0e0d0c,
+                        characteristics: 0xe0000020,
+                    },
+                    Section {
+                        name: "aaaaaaaa".to_owned(),
+                        virtual_size: 0x13121110,
+                        virtual_address: 0x17161514,
+                        raw_data_size: 0x1b1a1918,
+                        raw_data_address: 0x1f1e1d1c,
+                        characteristics: 0x40000040,
+                    },
+                ],
+                section_alignment: 0x1000,
+                size_of_headers: 0x400,
+            }
+        );
+    }
+
+    #[test]
+    fn rva_to_raw() {
+        let section_table = SectionTable {
+            sections: vec![
+                Section {
+                    name: "".to_owned(),
+                    virtual_size: 0x100,
+                    virtual_address: 0x1000,
+                    raw_data_size: 0x100,
+                    raw_data_address: 0x500,
+                    characteristics: 0,
+                },
+                Section {
+                    name: "".to_owned(),
+                    virtual_size: 0x100,
+                    virtual_address: 0x2000,
+                    raw_data_size: 0x100,
+                    raw_data_address: 0x800,
+                    characteristics: 0,
+                },
+                Section {
+                    name: "".to_owned(),
+                    virtual_size: 0,
+                    virtual_address: 0x3000,
+                    raw_data_size: 0x80,
+                    raw_data_address: 0xa00,
+                    characteristics: 0,
+                },
+            ],
+            section_alignment: 0x1000,
+            size_of_headers: 0x400,
+        };
+
+        assert_eq!(section_table.rva_to_file_offset(0x1010), Some(0x510));
+        assert_eq!(section_table.rva_to_file_offset(0x2080), Some(0x880));
+        assert_eq!(section_table.rva_to_file_offset(0x0fff), None);
+        assert_eq!(section_table.rva_to_file_offset(0x1100), None);
+        assert_eq!(section_table.rva_to_file_offset(0x1fff), None);
+        assert_eq!(section_table.rva_to_file_offset(0x2100), None);
+
+        // Headers
+        assert_eq!(section_table.rva_to_file_offset(0x0010), Some(0x10));
+        assert_eq!(section_table.rva_to_file_offset(0x0400), None);
+
+        // Section without virtual size
+        assert_eq!(section_table.rva_to_file_offset(0x3010), Some(0xa10));
+        assert_eq!(section_table.rva_to_file_offset(0x3080), None);
+    }
+}
@@ -0,0 +1,342 @@
+use nom::{
+    error::context,
+    number::complete::{le_u16, le_u32},
+    sequence::tuple,
+};
+
+use super::{parse_error, FileParseResult, PeError};
+
+// dwFileFlags values
+const VS_FF_DEBUG: u32 = 0x01;
+const VS_FF_PRERELEASE: u32 = 0x02;
+const VS_FF_PATCHED: u32 = 0x04;
+const VS_FF_PRIVATEBUILD: u32 = 0x08;
+const VS_FF_INFOINFERRED: u32 = 0x10;
+const VS_FF_SPECIALBUILD: u32 = 0x20;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StringTable {
+    /// Language and code page in hexadecimal, like 040904b0
+    pub language: String,
+    pub strings: Vec<(String, String)>,
+}
+
+impl StringTable {
+    pub fn language_id(&self) -> Option<u16> {
+        u16::from_str_radix(self.language.get(..4)?, 16).ok()
+    }
+
+    pub fn code_page(&self) -> Option<u16> {
+        u16::from_str_radix(self.language.get(4..)?, 16).ok()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VersionInfo {
+    pub file_version: Option<[u16; 4]>,
+    pub product_version: Option<[u16; 4]>,
+    /// dwFileFlags, masked by dwFileFlagsMask
+    pub file_flags: u32,
+    pub file_os: u32,
+    pub file_type: u32,
+    pub string_tables: Vec<StringTable>,
+    /// Language and code page pairs of the Translation value of VarFileInfo
+    pub translations: Vec<(u16, u16)>,
+}
+
+struct Block<'i> {
+    key: String,
+    value_length: usize,
+    data: &'i [u8],
+}
+
+impl VersionInfo {
+    pub fn parse(input: &[u8]) -> FileParseResult<Self> {
+        let (remaining, root) = VersionInfo::parse_block(input)?;
+        if root.key != "VS_VERSION_INFO" {
+            return Err(parse_error(PeError::InvalidData("version resource")));
+        }
+
+        let mut version_info = VersionInfo::default();
+
+        // VS_FIXEDFILEINFO
+        if root.value_length >= 52 {
+            let (data, (signature, _, file_ms, file_ls, product_ms, product_ls)) = context(
+                "fixed file info",
+                tuple((le_u32, le_u32, le_u32, le_u32, le_u32, le_u32)),
+            )(root.data)?;
+            let (_, (flags_mask, flags, file_os, file_type)) =
+                context("fixed file info", tuple((le_u32, le_u32, le_u32, le_u32)))(data)?;
+
+            if signature == 0xfeef04bd {
+                version_info.file_version = Some(split_version(file_ms, file_ls));
+                version_info.product_version = Some(split_version(product_ms, product_ls));
+                version_info.file_flags = flags & flags_mask;
+                version_info.file_os = file_os;
+                version_info.file_type = file_type;
+            }
+        }
+
+        // StringFileInfo and VarFileInfo
+        let mut children = root.data.get(align(root.value_length)..).unwrap_or_default();
+        while !children.is_empty() {
+            let (remaining, child) = VersionInfo::parse_block(children)?;
+            children = remaining;
+
+            if child.key == "VarFileInfo" {
+                let mut vars = child.data;
+                while !vars.is_empty() {
+                    let (remaining, var) = VersionInfo::parse_block(vars)?;
+                    vars = remaining;
+
+                    if var.key == "Translation" {
+                        let value = var.data.get(..var.value_length).unwrap_or(var.data);
+                        version_info.translations.extend(value.chunks_exact(4).map(|pair| {
+                            (
+                                u16::from_le_bytes([pair[0], pair[1]]),
+                                u16::from_le_bytes([pair[2], pair[3]]),
+                            )
+                        }));
+                    }
+                }
+                continue;
+            }
+
+            if child.key != "StringFileInfo" {
+                continue;
+            }
+
+            let mut tables = child.data;
+            while !tables.is_empty() {
+                let (remaining, table) = VersionInfo::parse_block(tables)?;
+                tables = remaining;
+
+                let mut strings = Vec::new();
+                let mut entries = table.data;
+                while !entries.is_empty() {
+                    let (remaining, entry) = VersionInfo::parse_block(entries)?;
+                    entries = remaining;
+
+                    strings.push((entry.key, read_utf16(entry.data)));
+                }
+
+                version_info.string_tables.push(StringTable {
+                    language: table.key,
+                    strings,
+                });
+            }
+        }
+
+        Ok((remaining, version_info))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.string_tables
+            .iter()
+            .flat_map(|table| table.strings.iter())
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn file_version_string(&self) -> Option<String> {
+        match self.file_version {
+            Some(version) => Some(format_version(version)),
+            None => self.get("FileVersion").map(|version| version.to_owned()),
+        }
+    }
+
+    pub fn product_version_string(&self) -> Option<String> {
+        match self.product_version {
+            Some(version) => Some(format_version(version)),
+            None => self.get("ProductVersion").map(|version| version.to_owned()),
+        }
+    }
+
+    /// Names of the dwFileFlags set, like debug or prerelease
+    pub fn file_flag_names(&self) -> Vec<&'static str> {
+        [
+            (VS_FF_DEBUG, "debug"),
+            (VS_FF_PRERELEASE, "prerelease"),
+            (VS_FF_PATCHED, "patched"),
+            (VS_FF_PRIVATEBUILD, "private build"),
+            (VS_FF_INFOINFERRED, "info inferred"),
+            (VS_FF_SPECIALBUILD, "special build"),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| self.file_flags & flag != 0)
+        .map(|(_, name)| name)
+        .collect()
+    }
+
+    /// Name of dwFileType, None for unknown types
+    pub fn file_type_name(&self) -> Option<&'static str> {
+        match self.file_type {
+            0x1 => Some("application"),
+            0x2 => Some("dll"),
+            0x3 => Some("driver"),
+            0x4 => Some("font"),
+            0x5 => Some("virtual device"),
+            0x7 => Some("static library"),
+            _ => None,
+        }
+    }
+
+    // Every node of the version resource shares the same header, followed by
+    // a null terminated key, the value and the children, all 32 bits aligned
+    fn parse_block(input: &[u8]) -> FileParseResult<Block<'_>> {
+        let (_, (length, value_length, value_type)) =
+            context("version block", tuple((le_u16, le_u16, le_u16)))(input)?;
+
+        let length = length as usize;
+        let block = match input.get(..length) {
+            Some(block) if length >= 6 => block,
+            _ => return Err(parse_error(PeError::Truncated("version block"))),
+        };
+
+        let key: Vec<u16> = block[6..]
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+
+        let data = block
+            .get(align(6 + (key.len() + 1) * 2)..)
+            .unwrap_or_default();
+
+        // Text values are measured in words
+        let value_length = if value_type == 1 {
+            value_length as usize * 2
+        } else {
+            value_length as usize
+        };
+
+        Ok((
+            input.get(align(length)..).unwrap_or_default(),
+            Block {
+                key: String::from_utf16_lossy(&key),
+                value_length,
+                data,
+            },
+        ))
+    }
+}
+
+fn align(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn read_utf16(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+
+    String::from_utf16_lossy(&units)
+}
+
+fn split_version(most_significant: u32, least_significant: u32) -> [u16; 4] {
+    [
+        (most_significant >> 16) as u16,
+        most_significant as u16,
+        (least_significant >> 16) as u16,
+        least_significant as u16,
+    ]
+}
+
+pub fn format_version(version: [u16; 4]) -> String {
+    format!("{}.{}.{}.{}", version[0], version[1], version[2], version[3])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn utf16(string: &str) -> Vec<u8> {
+        string
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    fn block(
+        key: &str,
+        value_type: u16,
+        value_length: u16,
+        value: &[u8],
+        children: &[u8],
+    ) -> Vec<u8> {
+        let mut data = vec![0u8; 6];
+        data.extend(utf16(key));
+        data.resize(align(data.len()), 0);
+        data.extend_from_slice(value);
+        data.resize(align(data.len()), 0);
+        data.extend_from_slice(children);
+
+        let length = data.len() as u16;
+        data[0..2].copy_from_slice(&length.to_le_bytes());
+        data[2..4].copy_from_slice(&value_length.to_le_bytes());
+        data[4..6].copy_from_slice(&value_type.to_le_bytes());
+        data.resize(align(data.len()), 0);
+        data
+    }
+
+    #[test]
+    fn version_info() {
+        let mut fixed = vec![0u8; 52];
+        fixed[0..4].copy_from_slice(&0xfeef04bdu32.to_le_bytes());
+        fixed[8..12].copy_from_slice(&0x00010002u32.to_le_bytes());
+        fixed[12..16].copy_from_slice(&0x00030004u32.to_le_bytes());
+        fixed[16..20].copy_from_slice(&0x00050006u32.to_le_bytes());
+        fixed[20..24].copy_from_slice(&0x00070008u32.to_le_bytes());
+        fixed[24..28].copy_from_slice(&0x3fu32.to_le_bytes());
+        fixed[28..32].copy_from_slice(&0x41u32.to_le_bytes());
+        fixed[32..36].copy_from_slice(&0x40004u32.to_le_bytes());
+        fixed[36..40].copy_from_slice(&0x2u32.to_le_bytes());
+
+        let mut strings = block("CompanyName", 1, 5, &utf16("Acme"), &[]);
+        strings.extend(block("FileVersion", 1, 8, &utf16("1.2.3.4"), &[]));
+        let translation = block("Translation", 0, 4, &[0x09, 0x04, 0xb0, 0x04], &[]);
+
+        let mut children = block(
+            "StringFileInfo",
+            1,
+            0,
+            &[],
+            &block("040904b0", 1, 0, &[], &strings),
+        );
+        children.extend(block("VarFileInfo", 1, 0, &[], &translation));
+        let data = block("VS_VERSION_INFO", 0, 52, &fixed, &children);
+
+        let version_info = VersionInfo::parse(&data).unwrap().1;
+        assert_eq!(
+            version_info,
+            VersionInfo {
+                file_version: Some([1, 2, 3, 4]),
+                product_version: Some([5, 6, 7, 8]),
+                file_flags: 0x1,
+                file_os: 0x40004,
+                file_type: 0x2,
+                string_tables: vec![StringTable {
+                    language: "040904b0".to_owned(),
+                    strings: vec![
+                        ("CompanyName".to_owned(), "Acme".to_owned()),
+                        ("FileVersion".to_owned(), "1.2.3.4".to_owned()),
+                    ],
+                }],
+                translations: vec![(0x0409, 0x04b0)],
+            }
+        );
+
+        assert_eq!(version_info.file_flag_names(), ["debug"]);
+        assert_eq!(version_info.file_type_name(), Some("dll"));
+        let table = &version_info.string_tables[0];
+        assert_eq!((table.language_id(), table.code_page()), (Some(0x0409), Some(1200)));
+
+        assert_eq!(version_info.get("CompanyName"), Some("Acme"));
+        assert_eq!(version_info.product_version_string(), Some("5.6.7.8".to_owned()));
+
+        assert_eq!(VersionInfo::parse(&data[..4]).is_err(), true);
+    }
+}
@@ -1,13 +1,13 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path};
 
 use bindings::Windows::Win32::{
     Foundation::PWSTR,
     System::{
         Diagnostics::Debug::{ERROR_MORE_DATA, ERROR_NO_MORE_ITEMS, ERROR_SUCCESS},
         Registry::{
-            RegCloseKey, RegEnumValueW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_CLASSES_ROOT,
-            HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, HKEY_USERS, KEY_READ,
-            RRF_RT_REG_DWORD, RRF_RT_REG_SZ,
+            RegCloseKey, RegEnumValueW, RegGetValueW, RegLoadAppKeyW, RegOpenKeyExW, HKEY,
+            HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE,
+            HKEY_USERS, KEY_READ, RRF_RT, RRF_RT_REG_DWORD, RRF_RT_REG_MULTI_SZ, RRF_RT_REG_SZ,
         },
     },
 };
@@ -80,6 +80,30 @@ impl RegistryKey {
         }
     }
 
+    /// Opens a hive file, such as the SYSTEM hive of an offline Windows image. The hive
+    /// is unloaded when the key is dropped.
+    pub fn load_hive(path: &Path) -> Result<Self, RegistryError> {
+        let mut handle = HKEY::NULL;
+        let error_code = unsafe {
+            RegLoadAppKeyW(
+                path.to_string_lossy().as_ref(),
+                &mut handle,
+                KEY_READ.0,
+                0,
+                0,
+            )
+        };
+
+        if error_code.0 != ErrorCode::ERROR_SUCCESS {
+            return Err(RegistryError::new(format!(
+                "Failed to load hive: {}",
+                path.to_string_lossy()
+            )));
+        }
+
+        Ok(Self { handle })
+    }
+
     pub fn value_names(&self, subkey: &str) -> Result<HashSet<String>, RegistryError> {
         // Open the key
         let mut handle = HKEY::NULL;
@@ -138,13 +162,45 @@ impl RegistryKey {
     }
 
     pub fn read_string(&self, subkey: &str, value_name: &str) -> Result<String, RegistryError> {
+        let buffer = self.read_utf16(subkey, value_name, RRF_RT_REG_SZ)?;
+
+        let value = String::from_utf16_lossy(&buffer)
+            .trim_end_matches(|c| c == '\0')
+            .to_owned();
+
+        Ok(value)
+    }
+
+    pub fn read_multi_string(
+        &self,
+        subkey: &str,
+        value_name: &str,
+    ) -> Result<Vec<String>, RegistryError> {
+        let buffer = self.read_utf16(subkey, value_name, RRF_RT_REG_MULTI_SZ)?;
+
+        // Strings are separated by a null character, the list ends with an empty string
+        let values = String::from_utf16_lossy(&buffer)
+            .split('\0')
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        Ok(values)
+    }
+
+    fn read_utf16(
+        &self,
+        subkey: &str,
+        value_name: &str,
+        flags: RRF_RT,
+    ) -> Result<Vec<u16>, RegistryError> {
         let mut size = 0;
         let error_code = unsafe {
             RegGetValueW(
                 self.handle,
                 subkey,
                 value_name,
-                RRF_RT_REG_SZ,
+                flags,
                 std::ptr::null_mut(),
                 std::ptr::null_mut(),
                 &mut size,
@@ -165,7 +221,7 @@ impl RegistryKey {
                 self.handle,
                 subkey,
                 value_name,
-                RRF_RT_REG_SZ,
+                flags,
                 std::ptr::null_mut(),
                 buffer.as_mut_ptr() as _,
                 &mut size,
@@ -179,11 +235,7 @@ impl RegistryKey {
             )));
         }
 
-        let value = String::from_utf16_lossy(&buffer)
-            .trim_end_matches(|c| c == '\0')
-            .to_owned();
-
-        Ok(value)
+        Ok(buffer)
     }
 
     pub fn read_dword(&self, subkey: &str, value_name: &str) -> Result<u32, RegistryError> {
@@ -256,6 +308,21 @@ mod test {
         assert_eq!(value.is_err(), true);
     }
 
+    #[test]
+    fn read_multi_string() {
+        let value = RegistryKey::root(RootKey::LocalMachine)
+            .read_multi_string(r"SYSTEM\CurrentControlSet\Control\ServiceGroupOrder", "List");
+
+        assert_eq!(value.is_ok(), true);
+        assert_eq!(value.unwrap().iter().any(|group| group == "Boot Bus Extender"), true);
+
+        let value = RegistryKey::root(RootKey::LocalMachine).read_multi_string(
+            r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\",
+            "SystemRoot",
+        );
+        assert_eq!(value.is_err(), true);
+    }
+
     #[test]
     fn value_names() {
         let names = RegistryKey::root(RootKey::LocalMachine)
@@ -0,0 +1,2385 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{collections::HashSet, error::Error, path::Path};
+
+#[cfg(windows)]
+use bindings::Windows::Win32::Foundation::PWSTR;
+#[cfg(windows)]
+use bindings::Windows::Win32::System::SystemInformation::{
+    GetSystemDirectoryW, GetWindowsDirectoryW,
+};
+use log::{info, warn};
+use regex::Regex;
+
+#[cfg(windows)]
+use crate::error::WindowsError;
+use crate::directory_cache::DirectoryCache;
+use crate::file_provider::{FileProvider, LocalFiles};
+use crate::inventory::{Inventory, InventoryFile};
+use crate::long_path::strip_extended_length;
+use crate::manifest::{AssemblyIdentity, BindingRedirect, Manifest};
+use crate::pe::{ApiSetSchema, Architecture, File};
+#[cfg(windows)]
+use crate::registry::{RegistryKey, RootKey};
+use crate::DllType;
+
+// LoadLibraryEx search flags, also used as a module's DependentLoadFlags
+pub const LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR: u32 = 0x00000100;
+pub const LOAD_LIBRARY_SEARCH_APPLICATION_DIR: u32 = 0x00000200;
+pub const LOAD_LIBRARY_SEARCH_USER_DIRS: u32 = 0x00000400;
+pub const LOAD_LIBRARY_SEARCH_SYSTEM32: u32 = 0x00000800;
+pub const LOAD_LIBRARY_SEARCH_DEFAULT_DIRS: u32 = 0x00001000;
+
+const LOAD_LIBRARY_SEARCH_MASK: u32 = LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR
+    | LOAD_LIBRARY_SEARCH_APPLICATION_DIR
+    | LOAD_LIBRARY_SEARCH_USER_DIRS
+    | LOAD_LIBRARY_SEARCH_SYSTEM32
+    | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS;
+
+/// A directory of the search order and the files it contains, by lowercase name
+#[derive(Debug, Default)]
+struct SearchDirectory {
+    path: PathBuf,
+    files: HashMap<String, PathBuf>,
+}
+
+impl SearchDirectory {
+    fn read(
+        path: &Path,
+        files: &dyn FileProvider,
+        cache: &mut DirectoryCache,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(SearchDirectory {
+            path: path.to_owned(),
+            files: SearchPath::read_directory_files(path, files, cache)?,
+        })
+    }
+
+    // Directories of the system are listed by the inventory when there is one
+    fn read_system(
+        path: &Path,
+        inventory: Option<&Inventory>,
+        files: &dyn FileProvider,
+        cache: &mut DirectoryCache,
+    ) -> Self {
+        let inventory = match inventory {
+            Some(inventory) => inventory,
+            None => return SearchDirectory::read_or_empty(path, files, cache),
+        };
+
+        let files = inventory
+            .directory(path)
+            .into_iter()
+            .flatten()
+            .map(|(name, _)| (name.to_lowercase(), path.join(name)))
+            .collect();
+        SearchDirectory {
+            path: path.to_owned(),
+            files,
+        }
+    }
+
+    // A directory that does not exist yet is still searched once it is created
+    fn read_or_empty(path: &Path, files: &dyn FileProvider, cache: &mut DirectoryCache) -> Self {
+        SearchDirectory::read(path, files, cache).unwrap_or_else(|_| {
+            info!("Failed to read files in {:?}", path);
+            SearchDirectory {
+                path: path.to_owned(),
+                files: HashMap::new(),
+            }
+        })
+    }
+
+    // Directories of the global assembly cache are only listed when an assembly is searched,
+    // without the cache of the directories of the search order
+    fn list(path: &Path, files: &dyn FileProvider) -> Option<Self> {
+        let files = files
+            .list_dir(path)
+            .ok()?
+            .into_iter()
+            .map(|name| (name.to_lowercase(), path.join(name)))
+            .collect();
+        Some(SearchDirectory {
+            path: path.to_owned(),
+            files,
+        })
+    }
+
+    /// File named `name`, a lowercase long name or 8.3 name
+    fn get(&self, name: &str) -> Option<&PathBuf> {
+        self.files
+            .get(name)
+            .or_else(|| find_short_name(name, &self.files))
+    }
+}
+
+/// A directory consulted by the search, with the type of the dlls found there
+#[derive(Debug)]
+pub struct SearchLocation<'a> {
+    pub directory: &'a Path,
+    pub dll_type: DllType,
+    pub description: &'static str,
+}
+
+/// What a step of the search found, as traced by `explain`
+#[derive(Debug, PartialEq)]
+pub enum SearchOutcome {
+    /// The dll is loaded from this file
+    Found(PathBuf),
+    NotPresent,
+    /// A file built for another architecture, skipped by the loader
+    WrongArchitecture(PathBuf, Architecture),
+    /// A file in a directory of the standard search order that the search flags of the
+    /// importer leave out
+    NotSearched(PathBuf),
+}
+
+/// A rule or directory consulted to resolve a dll, the directory is None for the rules
+/// that do not search one
+#[derive(Debug)]
+pub struct SearchStep<'a> {
+    pub description: &'static str,
+    pub directory: Option<&'a Path>,
+    pub dll_type: DllType,
+    pub outcome: SearchOutcome,
+}
+
+/// A rule of the search order, as given to `SearchPathBuilder::order`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchRule {
+    /// Dlls redirected by the side-by-side assemblies of the application manifest
+    ActivationContext,
+    /// Known dlls and the dlls they import, loaded from the system directory
+    KnownDlls,
+    /// The application directory, then the private paths of the application
+    ApplicationDirectory,
+    /// Directories added with AddDllDirectory, only searched with the user dirs or default
+    /// dirs search flags
+    UserDirectories,
+    CurrentDirectory,
+    SystemDirectory,
+    WindowsDirectory,
+    /// Directories of the PATH
+    Path,
+    /// Another directory, whose dlls have the given type
+    Directory(PathBuf, DllType),
+    /// Umbrella dlls of api sets. A file of another architecture found by the rules before
+    /// is loaded instead.
+    ApiSets,
+}
+
+impl SearchRule {
+    /// The standard search order of the loader, safe dll search mode moves the current
+    /// directory after the system directories
+    pub fn standard(safe_search_enabled: bool) -> Vec<SearchRule> {
+        let mut rules = vec![
+            SearchRule::ActivationContext,
+            SearchRule::KnownDlls,
+            SearchRule::ApplicationDirectory,
+            SearchRule::UserDirectories,
+            SearchRule::SystemDirectory,
+            SearchRule::WindowsDirectory,
+            SearchRule::Path,
+            SearchRule::ApiSets,
+        ];
+        let index = if safe_search_enabled { 6 } else { 3 };
+        rules.insert(index, SearchRule::CurrentDirectory);
+        rules
+    }
+}
+
+// What a rule of the search order consults
+enum Lookup<'a> {
+    /// Files by lowercase name, found without searching
+    Files(&'a HashMap<String, PathBuf>),
+    Directory(&'a SearchDirectory),
+    ApiSets,
+}
+
+fn directories<'a>(
+    lookups: &[(Lookup<'a>, DllType, &'static str)],
+) -> Vec<(&'a SearchDirectory, DllType, &'static str)> {
+    lookups
+        .iter()
+        .filter_map(|(lookup, dll_type, description)| match lookup {
+            Lookup::Directory(directory) => Some((*directory, *dll_type, *description)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn explain_wrong_architecture(found: Option<(PathBuf, DllType)>) -> Option<SearchStep<'static>> {
+    let (path, dll_type) = found?;
+    Some(SearchStep {
+        description: "first file of another architecture",
+        directory: None,
+        dll_type,
+        outcome: SearchOutcome::Found(path),
+    })
+}
+
+// A directory can be searched twice, like the application directory when it is also the
+// current directory, and known dlls are also found in the system directory
+fn add_candidates<'a>(
+    candidates: &mut Vec<(PathBuf, DllType)>,
+    name: &str,
+    directories: impl IntoIterator<Item = (&'a SearchDirectory, DllType, &'static str)>,
+) {
+    for (directory, dll_type, _) in directories {
+        if let Some(path) = directory.get(name) {
+            if !candidates
+                .iter()
+                .any(|(candidate, _)| same_directory(candidate, path))
+            {
+                candidates.push((path.to_owned(), dll_type));
+            }
+        }
+    }
+}
+
+fn to_locations<'a>(
+    directories: impl IntoIterator<Item = (&'a SearchDirectory, DllType, &'static str)>,
+) -> Vec<SearchLocation<'a>> {
+    directories
+        .into_iter()
+        .map(|(directory, dll_type, description)| SearchLocation {
+            directory: &directory.path,
+            dll_type,
+            description,
+        })
+        .collect()
+}
+
+/// Search order of the loader for a process, from its application directory to the PATH
+#[derive(Debug)]
+pub struct SearchPath {
+    safe_search_enabled: bool,
+    base_directory: SearchDirectory,
+    known_dll_files: HashMap<String, PathBuf>,
+    known_dll_importers: HashMap<String, String>,
+    system_directory: SearchDirectory,
+    drivers_directory: SearchDirectory,
+    windows_directory: SearchDirectory,
+    path_directories: Vec<SearchDirectory>,
+    current_directory: SearchDirectory,
+    framework_directories: Vec<SearchDirectory>,
+    user_directories: Vec<SearchDirectory>,
+    private_directories: Vec<SearchDirectory>,
+    extra_directories: Vec<SearchDirectory>,
+    side_by_side_files: HashMap<String, PathBuf>,
+    default_load_flags: u32,
+    /// Rules replacing the standard search order
+    order: Option<Vec<SearchRule>>,
+    api_set_schema: Option<ApiSetSchema>,
+    architecture: Option<Architecture>,
+    umbrella_dll_regex: Regex,
+    system_root: Option<PathBuf>,
+    inventory: Option<Inventory>,
+    files: Box<dyn FileProvider>,
+    directory_cache: DirectoryCache,
+}
+
+/// Settings of a [`SearchPath`], created by [`SearchPath::builder`]. By default the search
+/// follows the loader of the analysed system: its known dlls, api set schema and standard
+/// search order. Each of them can be replaced to see how dlls would resolve otherwise.
+#[derive(Debug)]
+pub struct SearchPathBuilder<'a> {
+    base_directory: &'a Path,
+    current_directory: &'a Path,
+    architecture: Option<Architecture>,
+    app_paths_executable: Option<&'a str>,
+    system_root: Option<&'a Path>,
+    environment: &'a [(String, String)],
+    inventory: Option<Inventory>,
+    cache_directory: Option<&'a Path>,
+    known_dlls: Option<HashSet<String>>,
+    api_set_schema: Option<Option<ApiSetSchema>>,
+    order: Option<Vec<SearchRule>>,
+    files: Box<dyn FileProvider>,
+}
+
+impl<'a> SearchPathBuilder<'a> {
+    /// Current directory of the process, the application directory by default
+    pub fn current_directory(mut self, current_directory: &'a Path) -> Self {
+        self.current_directory = current_directory;
+        self
+    }
+
+    /// Architecture of the process, which selects its system directory and makes the
+    /// search skip the dlls of other architectures
+    pub fn architecture(mut self, architecture: Option<Architecture>) -> Self {
+        self.architecture = architecture;
+        self
+    }
+
+    /// Executable whose App Paths registry key adds directories to the PATH
+    pub fn app_paths_executable(mut self, executable: Option<&'a str>) -> Self {
+        self.app_paths_executable = executable;
+        self
+    }
+
+    /// Directory of a mounted Windows image or Wine prefix analysed instead of the running
+    /// system
+    pub fn system_root(mut self, system_root: Option<&'a Path>) -> Self {
+        self.system_root = system_root;
+        self
+    }
+
+    /// Variables of the process, whose PATH replaces the one of the system
+    pub fn environment(mut self, environment: &'a [(String, String)]) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Inventory of the machine analysed instead of the running system
+    pub fn inventory(mut self, inventory: Option<Inventory>) -> Self {
+        self.inventory = inventory;
+        self
+    }
+
+    /// Directory where the files of the directories searched are cached between runs
+    pub fn cache_directory(mut self, cache_directory: Option<&'a Path>) -> Self {
+        self.cache_directory = cache_directory;
+        self
+    }
+
+    /// Known dlls used instead of the ones listed in the registry or inventory. The dlls
+    /// they import are known as well, like on the system.
+    pub fn known_dlls(mut self, names: &[&str]) -> Self {
+        self.known_dlls = Some(names.iter().map(|name| name.to_lowercase()).collect());
+        self
+    }
+
+    /// Api set schema used instead of the one of the system, None to resolve no api set
+    /// to its host
+    pub fn api_set_schema(mut self, api_set_schema: Option<ApiSetSchema>) -> Self {
+        self.api_set_schema = Some(api_set_schema);
+        self
+    }
+
+    /// Rules followed instead of the standard search order, in order. Safe dll search
+    /// mode no longer moves the current directory, and the search flags of an importer
+    /// only keep the rules they list.
+    pub fn order(mut self, order: Vec<SearchRule>) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    /// Provider the directories and files are read from instead of the local file system,
+    /// like an archive of an image
+    pub fn file_provider(mut self, files: impl FileProvider + 'static) -> Self {
+        self.files = Box::new(files);
+        self
+    }
+
+    /// Reads the directories searched and the loader settings of the system
+    pub fn build(self) -> Result<SearchPath, Box<dyn Error>> {
+        let SearchPathBuilder {
+            base_directory,
+            current_directory,
+            architecture,
+            app_paths_executable,
+            system_root,
+            environment,
+            inventory,
+            cache_directory,
+            known_dlls: known_dll_names,
+            api_set_schema,
+            order,
+            files,
+        } = self;
+
+        let mut cache = DirectoryCache::load(cache_directory);
+
+        // An offline image or the inventory of another machine is analysed with its own
+        // directories and registry, never with the ones of the running system
+        let (windows_directory, native_system_directory) = match (&inventory, system_root) {
+            (Some(inventory), _) => (
+                inventory.windows_directory.clone(),
+                inventory.system_directory.clone(),
+            ),
+            (None, Some(system_root)) => {
+                let windows_directory = find_directory(&*files, system_root, "Windows");
+                let system_directory = find_directory(&*files, &windows_directory, "System32");
+                (windows_directory, system_directory)
+            }
+            (None, None) => SearchPath::get_live_directories()?,
+        };
+        info!("Windows directory: {}", windows_directory.to_string_lossy());
+
+        let environment = Environment {
+            variables: environment,
+            system_root,
+            files: &*files,
+        };
+
+        let (safe_search_enabled, known_dlls) = match &inventory {
+            Some(inventory) => (
+                inventory.safe_search_enabled,
+                inventory.known_dlls.iter().cloned().collect(),
+            ),
+            None => {
+                SearchPath::read_loader_settings(&native_system_directory, system_root.is_some())?
+            }
+        };
+        info!("Safe search enabled: {}", safe_search_enabled);
+
+        // Names given by the caller replace the ones of the registry or inventory
+        let known_dlls = known_dll_names.unwrap_or(known_dlls);
+
+        // WOW64 redirects the system directory of 32-bit processes on 64-bit hosts, 32-bit
+        // Arm binaries get their own system directory on Arm64 hosts
+        let system_directory = match (&inventory, architecture) {
+            (Some(inventory), _) => inventory.process_system_directory(architecture).to_owned(),
+            (None, Some(Architecture::X86))
+                if files.is_dir(&find_directory(&*files, &windows_directory, "SysWOW64")) =>
+            {
+                find_directory(&*files, &windows_directory, "SysWOW64")
+            }
+            (None, Some(Architecture::Arm))
+                if files.is_dir(&find_directory(&*files, &windows_directory, "SysArm32")) =>
+            {
+                find_directory(&*files, &windows_directory, "SysArm32")
+            }
+            _ => native_system_directory.clone(),
+        };
+        info!("System directory: {}", system_directory.to_string_lossy());
+        info!("Base directory: {}", base_directory.to_string_lossy());
+        info!("Current directory: {}", current_directory.to_string_lossy());
+
+        // Images and Wine prefixes may lack some of the system directories
+        let base_directory = SearchDirectory::read(base_directory, &*files, &mut cache)?;
+        let drivers_directory = match &inventory {
+            Some(inventory) => inventory.drivers_directory.clone(),
+            None => find_directory(&*files, &system_directory, "drivers"),
+        };
+        let drivers_directory = SearchDirectory::read_system(
+            &drivers_directory,
+            inventory.as_ref(),
+            &*files,
+            &mut cache,
+        );
+        let api_set_schema = match (api_set_schema, &inventory) {
+            (Some(api_set_schema), _) => api_set_schema,
+            (None, Some(inventory)) => inventory.api_set_schema(),
+            (None, None) => SearchPath::read_api_set_schema(&*files, &system_directory),
+        };
+
+        let system_directory = SearchDirectory::read_system(
+            &system_directory,
+            inventory.as_ref(),
+            &*files,
+            &mut cache,
+        );
+        let (known_dll_files, known_dll_importers) = SearchPath::get_known_dll_closure(
+            known_dlls,
+            &system_directory,
+            api_set_schema.as_ref(),
+            inventory.as_ref(),
+            &*files,
+        );
+
+        // A simulated PATH replaces the one of the running system, which says nothing
+        // about an image. Paths of an inventory are paths of its machine.
+        let simulated_path = environment.get("PATH").map(|path| {
+            path.split(';')
+                .filter(|directory| !directory.is_empty())
+                .map(|directory| match inventory {
+                    Some(_) => PathBuf::from(directory),
+                    None => environment.to_path(directory),
+                })
+                .collect::<Vec<_>>()
+        });
+        let path_directories = match (&inventory, system_root) {
+            (Some(inventory), _) => simulated_path.unwrap_or_else(|| inventory.path.clone()),
+            (None, Some(_)) => simulated_path.unwrap_or_default(),
+            (None, None) => SearchPath::get_path_directories(app_paths_executable, simulated_path),
+        };
+        let path_directories = path_directories
+            .iter()
+            .map(|directory| {
+                // The redirection applies to every path, including the system directory
+                // in PATH
+                let directory = if same_directory(directory, &native_system_directory) {
+                    &system_directory.path
+                } else {
+                    directory
+                };
+                SearchDirectory::read_system(directory, inventory.as_ref(), &*files, &mut cache)
+            })
+            .collect();
+
+        let current_directory = SearchDirectory::read(current_directory, &*files, &mut cache)?;
+
+        let framework_directories = match inventory {
+            Some(_) => Vec::new(),
+            None => SearchPath::get_framework_directories(&environment, architecture)
+                .iter()
+                .filter_map(|directory| SearchDirectory::read(directory, &*files, &mut cache).ok())
+                .collect(),
+        };
+
+        let extra_directories = order
+            .iter()
+            .flatten()
+            .filter_map(|rule| match rule {
+                SearchRule::Directory(directory, _) => {
+                    info!("Extra directory: {}", directory.to_string_lossy());
+                    Some(SearchDirectory::read(directory, &*files, &mut cache))
+                }
+                _ => None,
+            })
+            .collect::<Result<_, _>>()?;
+        if let Some(order) = &order {
+            info!("Search order: {:?}", order);
+        }
+
+        Ok(SearchPath {
+            safe_search_enabled,
+            base_directory,
+            known_dll_files,
+            known_dll_importers,
+            system_directory,
+            drivers_directory,
+            windows_directory: SearchDirectory::read_system(
+                &windows_directory,
+                inventory.as_ref(),
+                &*files,
+                &mut cache,
+            ),
+            path_directories,
+            current_directory,
+            framework_directories,
+            user_directories: Vec::new(),
+            private_directories: Vec::new(),
+            extra_directories,
+            side_by_side_files: HashMap::new(),
+            default_load_flags: 0,
+            order,
+            api_set_schema,
+            architecture,
+            umbrella_dll_regex: Regex::new(r"(api|ext)-.*-l\d+-\d+-\d+.dll").unwrap(),
+            system_root: system_root.map(Path::to_owned),
+            inventory,
+            files,
+            directory_cache: cache,
+        })
+    }
+}
+
+impl SearchPath {
+    /// Settings of the search for an application in `base_directory`
+    pub fn builder(base_directory: &Path) -> SearchPathBuilder {
+        SearchPathBuilder {
+            base_directory,
+            current_directory: base_directory,
+            architecture: None,
+            app_paths_executable: None,
+            system_root: None,
+            environment: &[],
+            inventory: None,
+            cache_directory: None,
+            known_dlls: None,
+            api_set_schema: None,
+            order: None,
+            files: Box::new(LocalFiles),
+        }
+    }
+
+    /// Saves the directories listed so far to the cache, see [`DirectoryCache::save`]
+    pub fn save_directory_cache(&mut self) {
+        self.directory_cache.save();
+    }
+
+    /// Searches for another application of the same system, in `base_directory`. The
+    /// private directories and side-by-side assemblies of the previous one are dropped.
+    pub fn set_base_directory(&mut self, base_directory: &Path) -> Result<(), Box<dyn Error>> {
+        info!("Base directory: {}", base_directory.to_string_lossy());
+        self.base_directory =
+            SearchDirectory::read(base_directory, &*self.files, &mut self.directory_cache)?;
+        self.private_directories.clear();
+        self.side_by_side_files.clear();
+        Ok(())
+    }
+
+    pub fn add_side_by_side_assemblies(&mut self, assemblies: &[AssemblyIdentity]) {
+        for assembly in assemblies {
+            match self.find_side_by_side_directory(assembly) {
+                Some(directory) => {
+                    info!(
+                        "Side-by-side assembly {}: {}",
+                        assembly.name,
+                        directory.to_string_lossy()
+                    );
+                    let cache = &mut self.directory_cache;
+                    let files = SearchPath::read_directory_files(&directory, &*self.files, cache);
+                    if let Ok(files) = files {
+                        self.side_by_side_files.extend(files);
+                    }
+                }
+                None => info!("Could not find side-by-side assembly {}", assembly.name),
+            }
+        }
+    }
+
+    /// Adds a private path of the application manifest or configuration file, a
+    /// subdirectory of the application directory
+    pub fn add_private_directory(&mut self, directory: &Path) -> Result<(), Box<dyn Error>> {
+        info!("Private directory: {}", directory.to_string_lossy());
+        let directory =
+            SearchDirectory::read(directory, &*self.files, &mut self.directory_cache)?;
+        self.private_directories.push(directory);
+        Ok(())
+    }
+
+    /// Forces the standard search order with or without safe dll search mode, whatever
+    /// the configuration of the system
+    pub fn set_safe_search_enabled(&mut self, enabled: bool) {
+        info!("Safe search forced: {}", enabled);
+        self.safe_search_enabled = enabled;
+    }
+
+    /// Emulates an AddDllDirectory call of the application. Like the loader, the
+    /// directory is only searched with the user dirs or default dirs load flags.
+    pub fn add_user_directory(&mut self, directory: &Path) -> Result<(), Box<dyn Error>> {
+        info!("User directory: {}", directory.to_string_lossy());
+        let directory =
+            SearchDirectory::read(directory, &*self.files, &mut self.directory_cache)?;
+        self.user_directories.push(directory);
+        Ok(())
+    }
+
+    /// Emulates a SetDefaultDllDirectories call of the application, the LOAD_LIBRARY_SEARCH_*
+    /// `load_flags` then apply to every importer without dependent load flags
+    pub fn set_default_load_flags(&mut self, load_flags: u32) {
+        self.default_load_flags = load_flags & LOAD_LIBRARY_SEARCH_MASK;
+    }
+
+    /// Resolves `name` the way the loader does for an importer whose dependent load
+    /// flags are `load_flags`, 0 meaning the process default search order.
+    pub fn search(&self, name: &str, load_flags: u32) -> Option<(PathBuf, DllType)> {
+        if is_path_qualified(name) {
+            return self.open_path_qualified(name);
+        }
+
+        // The loader skips the dlls built for another architecture and keeps searching,
+        // it only fails on one when no other is found
+        let name = name.to_lowercase();
+        let mut wrong_architecture = None;
+        for (lookup, dll_type, _) in self.lookups(load_flags) {
+            match lookup {
+                Lookup::Files(files) => {
+                    if let Some(path) = files.get(&name) {
+                        return Some((path.to_owned(), dll_type));
+                    }
+                }
+                Lookup::Directory(directory) => {
+                    if let Some(path) = directory.get(&name) {
+                        match self.foreign_architecture(path) {
+                            Some(architecture) => {
+                                let file = path.to_string_lossy();
+                                warn!("Skipping {} built for {}", file, architecture);
+                                wrong_architecture.get_or_insert((path.to_owned(), dll_type));
+                            }
+                            None => return Some((path.to_owned(), dll_type)),
+                        }
+                    }
+                }
+                Lookup::ApiSets => {
+                    if wrong_architecture.is_some() {
+                        return wrong_architecture;
+                    }
+                    if self.umbrella_dll_regex.is_match(&name) {
+                        return Some((PathBuf::new(), DllType::Umbrella));
+                    }
+                }
+            }
+        }
+
+        wrong_architecture
+    }
+
+    /// Every rule and directory `search` consults for `name` in order, up to the one that
+    /// resolves it, along with the files the search flags of the importer leave out
+    pub fn explain(&self, name: &str, load_flags: u32) -> Vec<SearchStep> {
+        let mut steps = Vec::new();
+        if is_path_qualified(name) {
+            let path = self.application_path(name);
+            let outcome = if self.files.is_file(&path) {
+                SearchOutcome::Found(path)
+            } else {
+                SearchOutcome::NotPresent
+            };
+            steps.push(SearchStep {
+                description: "path of the import",
+                directory: None,
+                dll_type: DllType::User,
+                outcome,
+            });
+            return steps;
+        }
+
+        let name = name.to_lowercase();
+        let lookups = self.lookups(load_flags);
+        let mut wrong_architecture = None;
+        let mut not_searched_explained = false;
+        for (lookup, dll_type, description) in &lookups {
+            let (directory, outcome) = match lookup {
+                Lookup::Files(files) => match files.get(&name) {
+                    Some(path) => (None, SearchOutcome::Found(path.to_owned())),
+                    None => (None, SearchOutcome::NotPresent),
+                },
+                Lookup::Directory(directory) => {
+                    let outcome = match directory.get(&name) {
+                        Some(path) => match self.foreign_architecture(path) {
+                            Some(architecture) => {
+                                wrong_architecture.get_or_insert((path.to_owned(), *dll_type));
+                                SearchOutcome::WrongArchitecture(path.to_owned(), architecture)
+                            }
+                            None => SearchOutcome::Found(path.to_owned()),
+                        },
+                        None => SearchOutcome::NotPresent,
+                    };
+                    (Some(directory.path.as_path()), outcome)
+                }
+                Lookup::ApiSets => {
+                    if !not_searched_explained {
+                        steps.extend(self.explain_not_searched(&name, &lookups, load_flags));
+                        not_searched_explained = true;
+                    }
+                    if let Some(step) = explain_wrong_architecture(wrong_architecture.take()) {
+                        steps.push(step);
+                        return steps;
+                    }
+
+                    let step = self.explain_umbrella(&name);
+                    let found = step.outcome != SearchOutcome::NotPresent;
+                    steps.push(step);
+                    if found {
+                        return steps;
+                    }
+                    continue;
+                }
+            };
+
+            let found = matches!(outcome, SearchOutcome::Found(_));
+            steps.push(SearchStep {
+                description,
+                directory,
+                dll_type: *dll_type,
+                outcome,
+            });
+            if found {
+                return steps;
+            }
+        }
+
+        if !not_searched_explained {
+            steps.extend(self.explain_not_searched(&name, &lookups, load_flags));
+        }
+        steps.extend(explain_wrong_architecture(wrong_architecture));
+        steps
+    }
+
+    // Files in the directories of the standard search order that the search flags of the
+    // importer leave out
+    fn explain_not_searched(
+        &self,
+        name: &str,
+        lookups: &[(Lookup, DllType, &'static str)],
+        load_flags: u32,
+    ) -> Vec<SearchStep> {
+        if self.effective_load_flags(load_flags) == 0 {
+            return Vec::new();
+        }
+
+        let searched = directories(lookups);
+        directories(&self.lookups_with(0))
+            .into_iter()
+            .filter(|(directory, _, _)| {
+                !searched
+                    .iter()
+                    .any(|(searched, _, _)| std::ptr::eq(*searched, *directory))
+            })
+            .filter_map(|(directory, dll_type, description)| {
+                Some(SearchStep {
+                    description,
+                    directory: Some(&directory.path),
+                    dll_type,
+                    outcome: SearchOutcome::NotSearched(directory.get(name)?.to_owned()),
+                })
+            })
+            .collect()
+    }
+
+    /// Every rule and directory `search_kernel` consults for `name` in order, up to the
+    /// one that resolves it
+    pub fn explain_kernel(&self, name: &str) -> Vec<SearchStep> {
+        let name = name.to_lowercase();
+        let mut steps = Vec::new();
+        for (directory, dll_type, description) in self.kernel_search_directories() {
+            let found = directory.get(&name);
+            steps.push(SearchStep {
+                description,
+                directory: Some(&directory.path),
+                dll_type,
+                outcome: match found {
+                    Some(path) => SearchOutcome::Found(path.to_owned()),
+                    None => SearchOutcome::NotPresent,
+                },
+            });
+            if found.is_some() {
+                return steps;
+            }
+        }
+
+        steps.push(self.explain_umbrella(&name));
+        steps
+    }
+
+    fn explain_umbrella(&self, name: &str) -> SearchStep {
+        SearchStep {
+            description: "api set",
+            directory: None,
+            dll_type: DllType::Umbrella,
+            outcome: if self.umbrella_dll_regex.is_match(name) {
+                SearchOutcome::Found(PathBuf::new())
+            } else {
+                SearchOutcome::NotPresent
+            },
+        }
+    }
+
+    // Architecture of a file that cannot be loaded in the process, None when it can be or
+    // is not an image
+    fn foreign_architecture(&self, path: &Path) -> Option<Architecture> {
+        let expected = self.architecture?;
+        let found = match self.inventory_file(path) {
+            Some(file) => Architecture::from_machine(file.machine?)?,
+            None => Architecture::from_image(&self.files.read(path).ok()?)?,
+        };
+        (!expected.is_compatible_with(found)).then_some(found)
+    }
+
+    /// Provider the directories and files of the system are read from
+    pub fn file_provider(&self) -> &dyn FileProvider {
+        &*self.files
+    }
+
+    /// File of the inventory the search runs against, for a path it found
+    pub fn inventory_file(&self, path: &Path) -> Option<&InventoryFile> {
+        self.inventory.as_ref()?.file(path)
+    }
+
+    /// Every file the search finds for `name` whatever its architecture, in search order.
+    /// The first one is loaded, the others are shadowed by it.
+    pub fn search_all(&self, name: &str, load_flags: u32) -> Vec<(PathBuf, DllType)> {
+        if is_path_qualified(name) {
+            return self.open_path_qualified(name).into_iter().collect();
+        }
+
+        let name = name.to_lowercase();
+        let mut candidates = Vec::new();
+        for (lookup, dll_type, description) in self.lookups(load_flags) {
+            match lookup {
+                Lookup::Files(files) => {
+                    if let Some(path) = files.get(&name) {
+                        candidates.push((path.to_owned(), dll_type));
+                    }
+                }
+                Lookup::Directory(directory) => {
+                    add_candidates(&mut candidates, &name, [(directory, dll_type, description)])
+                }
+                Lookup::ApiSets => {}
+            }
+        }
+        candidates
+    }
+
+    // The loader opens an import naming a path instead of searching it
+    fn open_path_qualified(&self, name: &str) -> Option<(PathBuf, DllType)> {
+        let path = self.application_path(name);
+        self.files.is_file(&path).then_some((path, DllType::User))
+    }
+
+    /// Path of a file or directory given as a Windows path, absolute or relative to the
+    /// application directory. On an image, drive paths are looked up in the system root.
+    pub fn application_path(&self, path: &str) -> PathBuf {
+        let environment = Environment {
+            variables: &[],
+            system_root: self.system_root.as_deref(),
+            files: &*self.files,
+        };
+
+        if is_absolute(path) {
+            environment.to_path(path)
+        } else {
+            path.split(['\\', '/'])
+                .filter(|component| !component.is_empty() && *component != ".")
+                .fold(self.base_directory.path.clone(), |path, component| {
+                    find_directory(&*self.files, &path, component)
+                })
+        }
+    }
+
+    /// Every file the kernel loader finds for `name`, in search order
+    pub fn search_all_kernel(&self, name: &str) -> Vec<(PathBuf, DllType)> {
+        let name = name.to_lowercase();
+        let mut candidates = Vec::new();
+        add_candidates(&mut candidates, &name, self.kernel_search_directories());
+        candidates
+    }
+
+    /// Directories searched for an importer whose dependent load flags are `load_flags`,
+    /// in order
+    pub fn search_order(&self, load_flags: u32) -> Vec<SearchLocation> {
+        to_locations(directories(&self.lookups(load_flags)))
+    }
+
+    /// Directories searched for the imports of a kernel image, in order
+    pub fn kernel_search_order(&self) -> Vec<SearchLocation> {
+        to_locations(self.kernel_search_directories())
+    }
+
+    /// LOAD_LIBRARY_SEARCH_* flags in effect for an importer whose dependent load flags
+    /// are `load_flags`, 0 for the standard search order
+    pub fn effective_load_flags(&self, load_flags: u32) -> u32 {
+        if load_flags & LOAD_LIBRARY_SEARCH_MASK != 0 {
+            load_flags & LOAD_LIBRARY_SEARCH_MASK
+        } else {
+            self.default_load_flags
+        }
+    }
+
+    pub fn is_safe_search_enabled(&self) -> bool {
+        self.safe_search_enabled
+    }
+
+    pub fn known_dll_count(&self) -> usize {
+        self.known_dll_files.len()
+    }
+
+    /// Known dlls sorted by name, with the known dll importing them when they are not
+    /// listed in the registry
+    pub fn known_dlls(&self) -> Vec<(&str, &Path, Option<&str>)> {
+        let mut known_dlls: Vec<_> = self
+            .known_dll_files
+            .iter()
+            .map(|(name, path)| {
+                let importer = self.known_dll_importers.get(name).map(String::as_str);
+                (name.as_str(), path.as_path(), importer)
+            })
+            .collect();
+        known_dlls.sort();
+        known_dlls
+    }
+
+    pub fn side_by_side_count(&self) -> usize {
+        self.side_by_side_files.len()
+    }
+
+    /// Rules followed for an importer whose dependent load flags are `load_flags`, in
+    /// order
+    pub fn rules(&self, load_flags: u32) -> Vec<SearchRule> {
+        self.rules_with(self.effective_load_flags(load_flags))
+    }
+
+    // Rules followed with the LOAD_LIBRARY_SEARCH_* flags `load_flags`, 0 for the standard
+    // search order. The flags replace the directories of the search order with the ones
+    // they list.
+    fn rules_with(&self, load_flags: u32) -> Vec<SearchRule> {
+        let mut rules = match &self.order {
+            Some(order) => order.clone(),
+            None => SearchRule::standard(self.safe_search_enabled),
+        };
+
+        let default_dirs = load_flags & LOAD_LIBRARY_SEARCH_DEFAULT_DIRS != 0;
+        let listed = |flag: u32| default_dirs || load_flags & flag != 0;
+        rules.retain(|rule| match rule {
+            SearchRule::ActivationContext | SearchRule::KnownDlls | SearchRule::ApiSets => true,
+            SearchRule::ApplicationDirectory => {
+                load_flags == 0 || listed(LOAD_LIBRARY_SEARCH_APPLICATION_DIR)
+            }
+            SearchRule::UserDirectories => listed(LOAD_LIBRARY_SEARCH_USER_DIRS),
+            SearchRule::SystemDirectory => load_flags == 0 || listed(LOAD_LIBRARY_SEARCH_SYSTEM32),
+            _ => load_flags == 0,
+        });
+        rules
+    }
+
+    fn lookups(&self, load_flags: u32) -> Vec<(Lookup, DllType, &'static str)> {
+        self.lookups_with(self.effective_load_flags(load_flags))
+    }
+
+    fn lookups_with(&self, load_flags: u32) -> Vec<(Lookup, DllType, &'static str)> {
+        let mut lookups = Vec::new();
+        for rule in self.rules_with(load_flags) {
+            match rule {
+                SearchRule::ActivationContext => lookups.push((
+                    Lookup::Files(&self.side_by_side_files),
+                    DllType::SxS,
+                    "activation context",
+                )),
+                SearchRule::KnownDlls => lookups.push((
+                    Lookup::Files(&self.known_dll_files),
+                    DllType::Known,
+                    "known dlls",
+                )),
+                // Private paths are probed right after the application directory
+                SearchRule::ApplicationDirectory => {
+                    lookups.push((
+                        Lookup::Directory(&self.base_directory),
+                        DllType::User,
+                        "application directory",
+                    ));
+                    lookups.extend(self.private_directories.iter().map(|directory| {
+                        (Lookup::Directory(directory), DllType::User, "private path")
+                    }));
+                }
+                SearchRule::UserDirectories => {
+                    lookups.extend(self.user_directories.iter().map(|directory| {
+                        (Lookup::Directory(directory), DllType::User, "user directory")
+                    }))
+                }
+                SearchRule::CurrentDirectory => lookups.push((
+                    Lookup::Directory(&self.current_directory),
+                    DllType::User,
+                    "current directory",
+                )),
+                SearchRule::SystemDirectory => lookups.push((
+                    Lookup::Directory(&self.system_directory),
+                    DllType::System,
+                    "system directory",
+                )),
+                SearchRule::WindowsDirectory => lookups.push((
+                    Lookup::Directory(&self.windows_directory),
+                    DllType::System,
+                    "Windows directory",
+                )),
+                SearchRule::Path => lookups.extend(
+                    self.path_directories
+                        .iter()
+                        .map(|directory| (Lookup::Directory(directory), DllType::Path, "PATH")),
+                ),
+                SearchRule::Directory(path, dll_type) => lookups.extend(
+                    self.extra_directories
+                        .iter()
+                        .find(|directory| directory.path == path)
+                        .map(|directory| (Lookup::Directory(directory), dll_type, "directory")),
+                ),
+                SearchRule::ApiSets => {
+                    lookups.push((Lookup::ApiSets, DllType::Umbrella, "api set"))
+                }
+            }
+        }
+        lookups
+    }
+
+    fn find_private_file(&self, name: &str) -> Option<&PathBuf> {
+        self.private_directories
+            .iter()
+            .find_map(|directory| directory.get(name))
+    }
+
+    pub fn windows_directory(&self) -> &Path {
+        &self.windows_directory.path
+    }
+
+    pub fn system_directory(&self) -> &Path {
+        &self.system_directory.path
+    }
+
+    pub fn drivers_directory(&self) -> &Path {
+        &self.drivers_directory.path
+    }
+
+    pub fn path_directories(&self) -> Vec<&Path> {
+        self.path_directories
+            .iter()
+            .map(|directory| directory.path.as_path())
+            .collect()
+    }
+
+    pub fn api_set_schema(&self) -> Option<&ApiSetSchema> {
+        self.api_set_schema.as_ref()
+    }
+
+    /// Host dll implementing an api set, as listed by the api set schema of the system
+    pub fn resolve_api_set(&self, name: &str) -> Option<&str> {
+        self.api_set_schema.as_ref()?.resolve(name)
+    }
+
+    /// Resolves an import of a kernel image. The kernel loader only looks in the
+    /// drivers directory then the system directory, the application directory is
+    /// only a fallback for the analysed driver and the files shipped with it.
+    pub fn search_kernel(&self, name: &str) -> Option<(PathBuf, DllType)> {
+        let name = name.to_lowercase();
+
+        for (directory, dll_type, _) in self.kernel_search_directories() {
+            if let Some(path) = directory.get(&name) {
+                return Some((path.to_owned(), dll_type));
+            }
+        }
+
+        if self.umbrella_dll_regex.is_match(&name) {
+            return Some((PathBuf::new(), DllType::Umbrella));
+        }
+
+        None
+    }
+
+    fn kernel_search_directories(&self) -> [(&SearchDirectory, DllType, &'static str); 3] {
+        [
+            (&self.drivers_directory, DllType::Driver, "drivers directory"),
+            (&self.system_directory, DllType::Kernel, "system directory"),
+            (&self.base_directory, DllType::User, "application directory"),
+        ]
+    }
+
+    pub fn search_assembly(&self, name: &str) -> Option<(PathBuf, DllType)> {
+        let file_name = name.to_lowercase();
+
+        // Application base directory
+        if let Some(path) = self.base_directory.get(&file_name) {
+            return Some((path.to_owned(), DllType::Managed));
+        }
+
+        if let Some(path) = self.find_private_file(&file_name) {
+            return Some((path.to_owned(), DllType::Managed));
+        }
+
+        // Shared .NET runtimes
+        for directory in &self.framework_directories {
+            if let Some(path) = directory.get(&file_name) {
+                return Some((path.to_owned(), DllType::Managed));
+            }
+        }
+
+        // Global assembly cache, one directory per version and public key token
+        let assembly_name = Path::new(name).file_stem()?;
+        let framework = find_directory(&*self.files, &self.windows_directory.path, "Microsoft.NET");
+        for cache in ["GAC_MSIL", "GAC_64", "GAC_32"] {
+            let directory = framework
+                .join("assembly")
+                .join(cache)
+                .join(assembly_name);
+
+            if let Ok(entries) = self.files.list_dir(&directory) {
+                for entry in entries {
+                    let path = SearchDirectory::list(&directory.join(entry), &*self.files)
+                        .and_then(|directory| directory.get(&file_name).cloned())
+                        .filter(|path| self.files.is_file(path));
+                    if let Some(path) = path {
+                        return Some((path, DllType::Managed));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn find_side_by_side_directory(&self, assembly: &AssemblyIdentity) -> Option<PathBuf> {
+        let architecture = match assembly.processor_architecture.as_deref() {
+            Some(architecture) if architecture != "*" => architecture.to_lowercase(),
+            _ => match self.architecture {
+                Some(Architecture::X86) => "x86",
+                Some(Architecture::Arm) => "arm",
+                Some(Architecture::Arm64) | Some(Architecture::Arm64EC) => "arm64",
+                _ => "amd64",
+            }
+            .to_owned(),
+        };
+        let language = match assembly.language.as_deref() {
+            Some(language) if language != "*" => language.to_lowercase(),
+            _ => "none".to_owned(),
+        };
+        let name = assembly.name.to_lowercase();
+        let public_key_token = assembly
+            .public_key_token
+            .as_deref()
+            .unwrap_or("none")
+            .to_lowercase();
+        let requested_version = assembly.version.as_deref().map(parse_version).map(|version| {
+            match self.apply_publisher_policy(&architecture, &name, &public_key_token, &version) {
+                Some(redirected) => {
+                    info!(
+                        "Publisher policy redirects {} {} to {}",
+                        assembly.name,
+                        format_version(&version),
+                        format_version(&redirected)
+                    );
+                    redirected
+                }
+                None => version,
+            }
+        });
+
+        // Directories are named arch_name_token_version_language_hash
+        let mut best: Option<(Vec<u32>, PathBuf)> = None;
+        let side_by_side = find_directory(&*self.files, &self.windows_directory.path, "WinSxS");
+        for entry in self.files.list_dir(&side_by_side).ok()? {
+            let directory_name = entry.to_lowercase();
+            let parts: Vec<&str> = directory_name.split('_').collect();
+            let count = parts.len();
+            if count < 6
+                || parts[0] != architecture
+                || parts[1..count - 4].join("_") != name
+                || parts[count - 4] != public_key_token
+                || parts[count - 2] != language
+            {
+                continue;
+            }
+
+            let version = parse_version(parts[count - 3]);
+            if let Some(requested_version) = &requested_version {
+                if version == *requested_version {
+                    return Some(side_by_side.join(entry));
+                }
+
+                // Servicing only ever moves to newer builds of the same major.minor version
+                if version.get(..2) != requested_version.get(..2) {
+                    continue;
+                }
+            }
+
+            if best.as_ref().map_or(true, |(best, _)| version > *best) {
+                best = Some((version, side_by_side.join(entry)));
+            }
+        }
+
+        best.map(|(_, path)| path)
+    }
+
+    // Publisher policies are manifests named arch_policy.major.minor.name_token_version_...
+    // that redirect a range of versions of an assembly to a serviced build, the newest
+    // policy for the requested major.minor version wins
+    fn apply_publisher_policy(
+        &self,
+        architecture: &str,
+        name: &str,
+        public_key_token: &str,
+        version: &[u32],
+    ) -> Option<Vec<u32>> {
+        let prefix = format!(
+            "{}_policy.{}.{}.{}_{}_",
+            architecture,
+            version.first()?,
+            version.get(1)?,
+            name,
+            public_key_token
+        );
+
+        let mut best: Option<(Vec<u32>, PathBuf)> = None;
+        let manifests = find_directory(&*self.files, &self.windows_directory.path, "WinSxS")
+            .join("Manifests");
+        for entry in self.files.list_dir(&manifests).ok()? {
+            let file_name = entry.to_lowercase();
+            if let Some(rest) = file_name.strip_prefix(&prefix) {
+                let policy_version = parse_version(rest.split('_').next().unwrap_or_default());
+                if best.as_ref().map_or(true, |(best, _)| policy_version > *best) {
+                    best = Some((policy_version, manifests.join(entry)));
+                }
+            }
+        }
+
+        // Recent systems store manifests compressed, those policies cannot be read and
+        // the newest build of the same major.minor version is used instead
+        let (_, path) = best?;
+        let manifest = self
+            .files
+            .read(&path)
+            .ok()
+            .and_then(|data| Manifest::parse(std::str::from_utf8(&data).ok()?).ok());
+        match manifest {
+            Some(manifest) => manifest
+                .binding_redirects
+                .iter()
+                .find_map(|redirect| redirect_version(redirect, version)),
+            None => {
+                info!("Could not read publisher policy {}", path.to_string_lossy());
+                None
+            }
+        }
+    }
+
+    fn read_directory_files(
+        path: &Path,
+        files: &dyn FileProvider,
+        cache: &mut DirectoryCache,
+    ) -> Result<HashMap<String, PathBuf>, Box<dyn Error>> {
+        Ok(cache
+            .file_names(files, path)?
+            .into_iter()
+            .map(|name| (name.to_lowercase(), path.join(name)))
+            .collect::<HashMap<_, _>>())
+    }
+
+    // The schema is stored in the .apiset section of apisetschema.dll, older systems
+    // without it leave umbrella dlls unresolved
+    fn read_api_set_schema(
+        files: &dyn FileProvider,
+        system_directory: &Path,
+    ) -> Option<ApiSetSchema> {
+        let path = system_directory.join("apisetschema.dll");
+        let schema = files
+            .read(&path)
+            .ok()
+            .and_then(|data| File::parse(&data).ok())
+            .and_then(|file| file.api_set_schema);
+
+        if schema.is_none() {
+            info!("No api set schema in {}", path.to_string_lossy());
+        }
+
+        schema
+    }
+
+    #[cfg(windows)]
+    fn get_live_directories() -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+        Ok((
+            SearchPath::get_windows_directory()?,
+            SearchPath::get_system_directory()?,
+        ))
+    }
+
+    // Without a running Windows system only the application and user directories are
+    // searched, unless the files of one are given as a system root
+    #[cfg(not(windows))]
+    fn get_live_directories() -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+        info!("No system root, system dlls are not resolved");
+        Ok((PathBuf::new(), PathBuf::new()))
+    }
+
+    #[cfg(windows)]
+    pub fn get_system_directory() -> Result<PathBuf, Box<dyn Error>> {
+        SearchPath::read_directory_name(|buffer, size| unsafe { GetSystemDirectoryW(buffer, size) })
+    }
+
+    #[cfg(windows)]
+    fn get_windows_directory() -> Result<PathBuf, Box<dyn Error>> {
+        SearchPath::read_directory_name(|buffer, size| unsafe {
+            GetWindowsDirectoryW(buffer, size)
+        })
+    }
+
+    // The ANSI functions mangle names outside of the code page. When the buffer is too
+    // small, the returned length is the size it needs.
+    #[cfg(windows)]
+    fn read_directory_name(read: impl Fn(PWSTR, u32) -> u32) -> Result<PathBuf, Box<dyn Error>> {
+        let mut buffer = vec![0u16; 260];
+        loop {
+            let length = read(PWSTR(buffer.as_mut_ptr()), buffer.len() as u32) as usize;
+            if length == 0 {
+                return Err(Box::new(WindowsError::last_error()));
+            }
+            if length < buffer.len() {
+                return Ok(PathBuf::from(String::from_utf16(&buffer[..length])?));
+            }
+            buffer.resize(length, 0);
+        }
+    }
+
+    // ShellExecute prepends the directories of the App Paths entry of the executable
+    // it starts to PATH, CreateProcess leaves PATH untouched
+    #[cfg(windows)]
+    fn get_path_directories(
+        app_paths_executable: Option<&str>,
+        simulated_path: Option<Vec<PathBuf>>,
+    ) -> Vec<PathBuf> {
+        let mut directories = app_paths_executable
+            .map(SearchPath::get_app_paths_directories)
+            .unwrap_or_default();
+        if !directories.is_empty() {
+            info!("App Paths directories: {:?}", directories);
+        }
+
+        let paths = simulated_path.unwrap_or_else(|| {
+            let mut paths: Vec<PathBuf> = match std::env::var_os("PATH") {
+                Some(paths) => std::env::split_paths(&paths).collect(),
+                None => vec![],
+            };
+
+            // Our own App Paths entry is in PATH when we were started from the shell
+            let own_directories = std::env::current_exe()
+                .ok()
+                .and_then(|path| Some(path.file_name()?.to_str()?.to_owned()))
+                .map(|name| SearchPath::get_app_paths_directories(&name))
+                .unwrap_or_default();
+            if !own_directories.is_empty() && paths.starts_with(&own_directories) {
+                paths.drain(..own_directories.len());
+            }
+            paths
+        });
+
+        directories.extend(paths);
+        directories
+    }
+
+    // The PATH of a host that is not Windows is not a dll search path, only a simulated
+    // one is searched
+    #[cfg(not(windows))]
+    fn get_path_directories(
+        _app_paths_executable: Option<&str>,
+        simulated_path: Option<Vec<PathBuf>>,
+    ) -> Vec<PathBuf> {
+        simulated_path.unwrap_or_default()
+    }
+
+    #[cfg(windows)]
+    fn get_app_paths_directories(executable: &str) -> Vec<PathBuf> {
+        let subkey = format!(
+            r"SOFTWARE\Microsoft\Windows\CurrentVersion\App Paths\{}",
+            executable
+        );
+
+        // The per user entry takes precedence over the machine wide one
+        [RootKey::CurrentUser, RootKey::LocalMachine]
+            .iter()
+            .find_map(|root| RegistryKey::root(*root).read_string(&subkey, "Path").ok())
+            .map(|value| {
+                std::env::split_paths(&value)
+                    .filter(|path| !path.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn get_framework_directories(
+        environment: &Environment,
+        architecture: Option<Architecture>,
+    ) -> Vec<PathBuf> {
+        let files = environment.files;
+        let variable = |name| match environment.get(name) {
+            Some(value) => Some(environment.to_path(&value)),
+            None if environment.system_root.is_none() => std::env::var_os(name).map(PathBuf::from),
+            None => None,
+        };
+
+        // 32-bit runtimes are installed in the x86 program files directory
+        let program_files = match architecture {
+            Some(Architecture::X86) => variable("ProgramFiles(x86)")
+                .or_else(|| variable("ProgramFiles"))
+                .or_else(|| {
+                    Some(find_directory(
+                        files,
+                        environment.system_root?,
+                        "Program Files (x86)",
+                    ))
+                }),
+            _ => variable("ProgramFiles")
+                .or_else(|| {
+                    Some(find_directory(files, environment.system_root?, "Program Files"))
+                }),
+        };
+
+        // Latest installed version of each shared framework
+        let program_files = match program_files {
+            Some(program_files) => program_files,
+            None => return vec![],
+        };
+
+        let shared = program_files.join("dotnet").join("shared");
+        let frameworks = match files.list_dir(&shared) {
+            Ok(frameworks) => frameworks,
+            Err(_) => return vec![],
+        };
+
+        frameworks
+            .into_iter()
+            .filter_map(|framework| {
+                let framework = shared.join(framework);
+                files
+                    .list_dir(&framework)
+                    .ok()?
+                    .into_iter()
+                    .map(|version| framework.join(version))
+                    .filter(|path| files.is_dir(path))
+                    .max_by_key(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .map(parse_version)
+                            .unwrap_or_default()
+                    })
+            })
+            .collect()
+    }
+
+    // Safe search mode and known dlls of the analysed system
+    #[cfg(windows)]
+    fn read_loader_settings(
+        system_directory: &Path,
+        offline: bool,
+    ) -> Result<(bool, HashSet<String>), Box<dyn Error>> {
+        let (registry, session_manager) =
+            match SearchPath::open_session_manager(system_directory, offline) {
+                Ok(session_manager) => session_manager,
+                Err(err) if offline => {
+                    info!("No registry in the image, using the loader defaults: {}", err);
+                    return Ok((true, HashSet::new()));
+                }
+                Err(err) => return Err(err),
+            };
+
+        Ok((
+            SearchPath::safe_search_enabled(&registry, &session_manager),
+            SearchPath::get_knwon_dll_files(&registry, &session_manager)?,
+        ))
+    }
+
+    // Registry hives can only be read through the Windows api
+    #[cfg(not(windows))]
+    fn read_loader_settings(
+        _system_directory: &Path,
+        _offline: bool,
+    ) -> Result<(bool, HashSet<String>), Box<dyn Error>> {
+        info!("No registry off Windows, using the loader defaults");
+        Ok((true, HashSet::new()))
+    }
+
+    // Session Manager key of the analysed system, read from the SYSTEM hive of the
+    // image when analysing offline
+    #[cfg(windows)]
+    fn open_session_manager(
+        system_directory: &Path,
+        offline: bool,
+    ) -> Result<(RegistryKey, String), Box<dyn Error>> {
+        if !offline {
+            return Ok((
+                RegistryKey::root(RootKey::LocalMachine),
+                r"SYSTEM\CurrentControlSet\Control\Session Manager".to_owned(),
+            ));
+        }
+
+        // CurrentControlSet only exists on a running system
+        let hive = RegistryKey::load_hive(&system_directory.join(r"config\SYSTEM"))?;
+        let control_set = hive.read_dword("Select", "Current")?;
+        Ok((hive, format!(r"ControlSet{:03}\Control\Session Manager", control_set)))
+    }
+
+    // The session manager maps the known dlls listed in the registry along with the dlls
+    // they statically import, recursively, when it creates the \KnownDlls sections. A dll
+    // missing from the system directory of the process, like a dll only in System32 for a
+    // SysWOW64 process, has no section and is searched like any other.
+    fn get_known_dll_closure(
+        known_dlls: HashSet<String>,
+        system_directory: &SearchDirectory,
+        api_set_schema: Option<&ApiSetSchema>,
+        inventory: Option<&Inventory>,
+        file_provider: &dyn FileProvider,
+    ) -> (HashMap<String, PathBuf>, HashMap<String, String>) {
+        let mut files = HashMap::new();
+        let mut importers = HashMap::new();
+
+        let mut queue: Vec<String> = known_dlls.iter().cloned().collect();
+        queue.sort_unstable_by(|first, second| second.cmp(first));
+
+        while let Some(name) = queue.pop() {
+            let path = match system_directory.get(&name) {
+                Some(path) => path.to_owned(),
+                None => continue,
+            };
+
+            let imports = match inventory.and_then(|inventory| inventory.file(&path)) {
+                Some(file) => file.dependencies.clone(),
+                None => file_provider
+                    .read(&path)
+                    .ok()
+                    .and_then(|data| File::parse_from(&data).ok())
+                    .map(|file| file.imports.into_iter().map(|dll| dll.name).collect())
+                    .unwrap_or_default(),
+            };
+            files.insert(name.clone(), path);
+
+            for import in imports {
+                let import = import.to_lowercase();
+                let import = match api_set_schema.and_then(|schema| schema.resolve(&import)) {
+                    Some(host) => host.to_lowercase(),
+                    None => import,
+                };
+
+                let known = known_dlls.contains(&import) || importers.contains_key(&import);
+                if !known && system_directory.get(&import).is_some() {
+                    importers.insert(import.clone(), name.clone());
+                    queue.push(import);
+                }
+            }
+        }
+
+        (files, importers)
+    }
+
+    #[cfg(windows)]
+    fn get_knwon_dll_files(
+        registry: &RegistryKey,
+        session_manager: &str,
+    ) -> Result<HashSet<String>, Box<dyn Error>> {
+        // Names listed in ExcludeFromKnownDlls are loaded through the search order
+        let excluded: HashSet<String> = registry
+            .read_multi_string(session_manager, "ExcludeFromKnownDlls")
+            .unwrap_or_default()
+            .iter()
+            .map(|name| name.to_lowercase())
+            .collect();
+        if !excluded.is_empty() {
+            info!("Excluded from known dlls: {:?}", excluded);
+        }
+
+        let known_dlls = format!(r"{}\KnownDLLs", session_manager);
+        let values = registry.value_names(&known_dlls)?;
+
+        // DllDirectory and DllDirectory32 hold the directories of the sections, not dlls
+        let files = values
+            .iter()
+            .filter(|value| !value.to_lowercase().starts_with("dlldirectory"))
+            .filter_map(|value| registry.read_string(&known_dlls, value).ok())
+            .map(|name| name.to_lowercase())
+            .filter(|name| !excluded.contains(name))
+            .collect();
+
+        Ok(files)
+    }
+
+    #[cfg(windows)]
+    fn safe_search_enabled(registry: &RegistryKey, session_manager: &str) -> bool {
+        let value = registry.read_dword(session_manager, "SafeDllSearchMode");
+
+        match value {
+            Ok(value) => value != 0,
+            Err(_) => true,
+        }
+    }
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+// Windows paths are case insensitive, images extracted on other systems and Wine
+// prefixes do not always use the usual casing
+pub fn find_directory(files: &dyn FileProvider, parent: &Path, name: &str) -> PathBuf {
+    let path = parent.join(name);
+    if files.is_dir(&path) {
+        return path;
+    }
+
+    files
+        .list_dir(parent)
+        .ok()
+        .and_then(|entries| entries.into_iter().find(|entry| entry.eq_ignore_ascii_case(name)))
+        .map(|entry| parent.join(entry))
+        .unwrap_or(path)
+}
+
+// 8.3 names are made of the first six valid characters of the long name, a ~N suffix
+// numbering the files that share them and the first three characters of the extension.
+// The numbers follow the creation order of the files, their sorted names approximate it.
+fn find_short_name<'a>(name: &str, files: &'a HashMap<String, PathBuf>) -> Option<&'a PathBuf> {
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    let (prefix, number) = stem.split_once('~')?;
+    let number: usize = number.parse().ok()?;
+    if prefix.is_empty() || prefix.len() > 6 || number == 0 {
+        return None;
+    }
+
+    let short_characters = |text: &str| -> String {
+        text.chars()
+            .filter(|character| *character != ' ' && *character != '.')
+            .map(|character| match character {
+                '+' | ',' | ';' | '=' | '[' | ']' => '_',
+                character if !character.is_ascii() => '_',
+                character => character,
+            })
+            .collect()
+    };
+
+    let mut candidates: Vec<&String> = files
+        .keys()
+        .filter(|long_name| {
+            let (long_stem, long_extension) =
+                long_name.rsplit_once('.').unwrap_or((long_name.as_str(), ""));
+            let basis = short_characters(long_stem);
+            let short_extension: String =
+                short_characters(long_extension).chars().take(3).collect();
+
+            // Names that are valid 8.3 names already are their own short name
+            let is_short = basis == long_stem
+                && long_stem.len() <= 8
+                && short_extension == long_extension;
+
+            !is_short && basis.starts_with(prefix) && short_extension == extension
+        })
+        .collect();
+    candidates.sort();
+
+    files.get(*candidates.get(number - 1)?)
+}
+
+/// Whether an import names a path, like plugins\engine.dll, rather than a file to search
+pub fn is_path_qualified(name: &str) -> bool {
+    name.contains(['\\', '/'])
+}
+
+// Drive and UNC paths, any other path is resolved from the application directory
+fn is_absolute(name: &str) -> bool {
+    let mut characters = name.chars();
+    let has_drive = matches!(
+        (characters.next(), characters.next()),
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic()
+    );
+    has_drive || name.starts_with(r"\\")
+}
+
+pub fn same_directory(first: &Path, second: &Path) -> bool {
+    let normalize = |path: &Path| {
+        strip_extended_length(path)
+            .to_string_lossy()
+            .trim_end_matches('\\')
+            .to_lowercase()
+    };
+
+    normalize(first) == normalize(second)
+}
+
+fn format_version(version: &[u32]) -> String {
+    version
+        .iter()
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn redirect_version(redirect: &BindingRedirect, version: &[u32]) -> Option<Vec<u32>> {
+    let (low, high) = redirect
+        .old_version
+        .split_once('-')
+        .unwrap_or((&redirect.old_version, &redirect.old_version));
+
+    if parse_version(low).as_slice() <= version && version <= parse_version(high).as_slice() {
+        Some(parse_version(&redirect.new_version))
+    } else {
+        None
+    }
+}
+
+/// Variables set for the analysed process, the last definition of a name wins
+struct Environment<'a> {
+    variables: &'a [(String, String)],
+    system_root: Option<&'a Path>,
+    files: &'a dyn FileProvider,
+}
+
+impl Environment<'_> {
+    /// Value of a variable set for the process, with the variables it references expanded
+    fn get(&self, name: &str) -> Option<String> {
+        self.variables
+            .iter()
+            .rev()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| self.expand(value))
+    }
+
+    // Variables that are not set are taken from the running system, except for an image
+    // where only the Windows directory is known. Like ExpandEnvironmentStrings, unknown
+    // variables are left as is.
+    fn expand(&self, value: &str) -> String {
+        let mut expanded = String::new();
+        let mut rest = value;
+        while let Some(start) = rest.find('%') {
+            let end = match rest[start + 1..].find('%') {
+                Some(end) => start + 1 + end,
+                None => break,
+            };
+            let name = &rest[start + 1..end];
+
+            let set_value = self
+                .variables
+                .iter()
+                .rev()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone());
+            let value = set_value.or_else(|| match self.system_root {
+                None => std::env::var(name).ok(),
+                Some(_)
+                    if ["SystemRoot", "windir"]
+                        .iter()
+                        .any(|windows| name.eq_ignore_ascii_case(windows)) =>
+                {
+                    Some(r"C:\Windows".to_owned())
+                }
+                Some(_) => None,
+            });
+
+            expanded.push_str(&rest[..start]);
+            match value {
+                Some(value) => {
+                    expanded.push_str(&value);
+                    rest = &rest[end + 1..];
+                }
+                // The closing percent sign may open the next variable
+                None => {
+                    expanded.push_str(&rest[start..end]);
+                    rest = &rest[end..];
+                }
+            }
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    /// Path of a directory of the analysed system, paths on the drive of an image are
+    /// looked up in its system root
+    fn to_path(&self, directory: &str) -> PathBuf {
+        let mut characters = directory.chars();
+        let has_drive = matches!(
+            (characters.next(), characters.next(), characters.next()),
+            (Some(drive), Some(':'), Some('\\' | '/')) if drive.is_ascii_alphabetic()
+        );
+
+        match self.system_root {
+            Some(system_root) if has_drive => directory[3..]
+                .split(['\\', '/'])
+                .filter(|component| !component.is_empty())
+                .fold(system_root.to_owned(), |path, component| {
+                    find_directory(self.files, &path, component)
+                }),
+            _ => PathBuf::from(directory.replace('\\', std::path::MAIN_SEPARATOR_STR)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::file_provider::MemoryFiles;
+
+    #[cfg(windows)]
+    #[test]
+    fn search() {
+        let cargo_dir = std::path::Path::new(env!("CARGO")).parent().unwrap();
+        let search_path = SearchPath::builder(cargo_dir)
+            .current_directory(&PathBuf::new())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            search_path.search("win32u.dll", 0),
+            Some((
+                PathBuf::from(r"C:\Windows\system32\win32u.dll"),
+                DllType::System
+            ))
+        );
+
+        assert_eq!(
+            search_path.search("WIN32U.DLL", 0),
+            Some((
+                PathBuf::from(r"C:\Windows\system32\win32u.dll"),
+                DllType::System
+            ))
+        );
+
+        assert_eq!(
+            search_path.search("cargo.exe", 0),
+            Some((PathBuf::from(&cargo_dir.join("cargo.exe")), DllType::User))
+        );
+
+        assert_eq!(search_path.search("hopefully_not_existing.dll", 0), None);
+
+        assert_eq!(search_path.search("cargo.exe", LOAD_LIBRARY_SEARCH_SYSTEM32), None);
+
+        assert_eq!(
+            search_path.search("win32u.dll", LOAD_LIBRARY_SEARCH_SYSTEM32),
+            Some((
+                PathBuf::from(r"C:\Windows\system32\win32u.dll"),
+                DllType::System
+            ))
+        );
+
+        assert_eq!(
+            search_path.search("api-ms-win-core-sysinfo-l1-2-3.dll", 0),
+            Some((PathBuf::new(), DllType::Umbrella))
+        );
+
+        assert_eq!(
+            search_path.search_kernel("ntoskrnl.exe"),
+            Some((
+                PathBuf::from(r"C:\Windows\system32\ntoskrnl.exe"),
+                DllType::Kernel
+            ))
+        );
+
+        assert_eq!(
+            search_path.search_kernel("ndis.sys"),
+            Some((
+                PathBuf::from(r"C:\Windows\system32\drivers\ndis.sys"),
+                DllType::Driver
+            ))
+        );
+
+        assert_eq!(
+            search_path.search("kernel32.dll", 0),
+            Some((PathBuf::from("C:\\Windows\\system32\\kernel32.dll"), DllType::Known))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn side_by_side() {
+        let cargo_dir = std::path::Path::new(env!("CARGO")).parent().unwrap();
+        let mut search_path = SearchPath::builder(cargo_dir)
+            .current_directory(&PathBuf::new())
+            .build()
+            .unwrap();
+        search_path.add_side_by_side_assemblies(&[AssemblyIdentity {
+            name: "Microsoft.Windows.Common-Controls".to_owned(),
+            version: Some("6.0.0.0".to_owned()),
+            processor_architecture: Some("*".to_owned()),
+            public_key_token: Some("6595b64144ccf1df".to_owned()),
+            language: Some("*".to_owned()),
+        }]);
+
+        let (path, dll_type) = search_path.search("comctl32.dll", 0).unwrap();
+        assert_eq!(dll_type, DllType::SxS);
+        assert!(path.starts_with(r"C:\Windows\WinSxS"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn wow64() {
+        let cargo_dir = std::path::Path::new(env!("CARGO")).parent().unwrap();
+        let architecture = Some(Architecture::X86);
+        let search_path = SearchPath::builder(cargo_dir)
+            .current_directory(&PathBuf::new())
+            .architecture(architecture)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            search_path.search("win32u.dll", 0),
+            Some((
+                PathBuf::from(r"C:\Windows\SysWOW64\win32u.dll"),
+                DllType::System
+            ))
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn user_directories() {
+        let cargo_dir = std::path::Path::new(env!("CARGO")).parent().unwrap();
+        let mut search_path = SearchPath::builder(&std::env::temp_dir())
+            .current_directory(&PathBuf::new())
+            .build()
+            .unwrap();
+        search_path.add_user_directory(cargo_dir).unwrap();
+
+        let cargo = Some((PathBuf::from(&cargo_dir.join("cargo.exe")), DllType::User));
+        assert_eq!(
+            search_path.search("cargo.exe", LOAD_LIBRARY_SEARCH_USER_DIRS),
+            cargo
+        );
+        assert_eq!(
+            search_path.search("cargo.exe", LOAD_LIBRARY_SEARCH_SYSTEM32),
+            None
+        );
+
+        search_path.set_default_load_flags(LOAD_LIBRARY_SEARCH_DEFAULT_DIRS);
+        assert_eq!(search_path.search("cargo.exe", 0), cargo);
+    }
+
+    #[test]
+    fn system_root() {
+        // Wine prefixes use lowercase directory names
+        let system_root = std::env::temp_dir().join("dllwalk-system-root");
+        let system_directory = system_root.join("windows").join("system32");
+        std::fs::create_dir_all(&system_directory).unwrap();
+        std::fs::write(system_directory.join("kernel32.dll"), []).unwrap();
+
+        let search_path = SearchPath::builder(&system_root)
+            .system_root(Some(&system_root))
+            .build()
+            .unwrap();
+        let (path, dll_type) = search_path.search("KERNEL32.DLL", 0).unwrap();
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        assert_eq!(dll_type, DllType::System);
+        assert!(same_directory(path.parent().unwrap(), &system_directory));
+    }
+
+    #[test]
+    fn search_all() {
+        let system_root = std::env::temp_dir().join("dllwalk-search-all");
+        let application_directory = system_root.join("app");
+        let system_directory = system_root.join("windows").join("system32");
+        for directory in [&application_directory, &system_directory] {
+            std::fs::create_dir_all(directory).unwrap();
+            std::fs::write(directory.join("zlib1.dll"), []).unwrap();
+        }
+
+        let search_path = SearchPath::builder(&application_directory)
+            .system_root(Some(&system_root))
+            .build()
+            .unwrap();
+        let candidates = search_path.search_all("ZLIB1.dll", 0);
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        let types: Vec<_> = candidates.iter().map(|(_, dll_type)| *dll_type).collect();
+        assert_eq!(types, vec![DllType::User, DllType::System]);
+        assert!(same_directory(candidates[1].0.parent().unwrap(), &system_directory));
+    }
+
+    #[test]
+    fn wrong_architecture() {
+        let system_root = std::env::temp_dir().join("dllwalk-wrong-architecture");
+        let application_directory = system_root.join("app");
+        let system_directory = system_root.join("windows").join("system32");
+
+        // Headers of an image, up to the machine type
+        let image = |machine: u16| {
+            let mut image = vec![0; 0x58];
+            image[..2].copy_from_slice(b"MZ");
+            image[0x3c] = 0x40;
+            image[0x40..0x44].copy_from_slice(b"PE\0\0");
+            image[0x44..0x46].copy_from_slice(&machine.to_le_bytes());
+            image
+        };
+        let machines = [(&application_directory, 0x014c), (&system_directory, 0x8664)];
+        for (directory, machine) in machines {
+            std::fs::create_dir_all(directory).unwrap();
+            std::fs::write(directory.join("zlib1.dll"), image(machine)).unwrap();
+        }
+
+        let search_path = SearchPath::builder(&application_directory)
+            .architecture(Some(Architecture::X64))
+            .system_root(Some(&system_root))
+            .build()
+            .unwrap();
+        let found = search_path.search("zlib1.dll", 0);
+        let found_x86 = search_path.search("zlib1.dll", LOAD_LIBRARY_SEARCH_APPLICATION_DIR);
+        let steps = search_path.explain("zlib1.dll", 0);
+        let user_steps = search_path.explain("zlib1.dll", LOAD_LIBRARY_SEARCH_USER_DIRS);
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        // A dll of the wrong architecture is still reported when nothing else is found
+        assert_eq!(found.unwrap().1, DllType::System);
+        assert_eq!(found_x86.unwrap().1, DllType::User);
+
+        let outcomes = |steps: Vec<SearchStep>| -> Vec<_> {
+            steps.into_iter().map(|step| (step.description, step.outcome)).collect()
+        };
+        assert!(matches!(
+            &outcomes(steps)[..],
+            [
+                ("activation context", SearchOutcome::NotPresent),
+                ("known dlls", SearchOutcome::NotPresent),
+                ("application directory", SearchOutcome::WrongArchitecture(_, Architecture::X86)),
+                ("system directory", SearchOutcome::Found(_)),
+            ]
+        ));
+        assert!(matches!(
+            &outcomes(user_steps)[2..],
+            [
+                ("application directory", SearchOutcome::NotSearched(_)),
+                ("system directory", SearchOutcome::NotSearched(_)),
+                ("current directory", SearchOutcome::NotSearched(_)),
+                ("api set", SearchOutcome::NotPresent),
+            ]
+        ));
+    }
+
+    #[test]
+    fn order() {
+        let system_root = std::env::temp_dir().join("dllwalk-order");
+        let application_directory = system_root.join("app");
+        let plugin_directory = system_root.join("plugins");
+        let system_directory = system_root.join("windows").join("system32");
+        for directory in [&application_directory, &plugin_directory, &system_directory] {
+            std::fs::create_dir_all(directory).unwrap();
+            std::fs::write(directory.join("zlib1.dll"), []).unwrap();
+        }
+        std::fs::write(plugin_directory.join("engine.dll"), []).unwrap();
+
+        // The system directory before the application directory, and no api sets
+        let order = vec![
+            SearchRule::KnownDlls,
+            SearchRule::SystemDirectory,
+            SearchRule::Directory(plugin_directory.clone(), DllType::Path),
+            SearchRule::ApplicationDirectory,
+        ];
+        let search_path = SearchPath::builder(&application_directory)
+            .system_root(Some(&system_root))
+            .order(order)
+            .build()
+            .unwrap();
+        let known_search_path = SearchPath::builder(&application_directory)
+            .system_root(Some(&system_root))
+            .known_dlls(&["ZLIB1.dll"])
+            .build()
+            .unwrap();
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        let dll_type = |search_path: &SearchPath, name, load_flags| {
+            search_path.search(name, load_flags).map(|(_, dll_type)| dll_type)
+        };
+        assert_eq!(dll_type(&search_path, "zlib1.dll", 0), Some(DllType::System));
+        assert_eq!(dll_type(&search_path, "engine.dll", 0), Some(DllType::Path));
+        assert_eq!(dll_type(&search_path, "api-ms-win-core-file-l1-1-0.dll", 0), None);
+        assert_eq!(
+            dll_type(&search_path, "zlib1.dll", LOAD_LIBRARY_SEARCH_APPLICATION_DIR),
+            Some(DllType::User)
+        );
+
+        let types: Vec<_> = search_path
+            .search_all("zlib1.dll", 0)
+            .into_iter()
+            .map(|(_, dll_type)| dll_type)
+            .collect();
+        assert_eq!(types, vec![DllType::System, DllType::Path, DllType::User]);
+        let descriptions: Vec<_> = search_path
+            .explain("engine.dll", 0)
+            .into_iter()
+            .map(|step| step.description)
+            .collect();
+        assert_eq!(descriptions, vec!["known dlls", "system directory", "directory"]);
+
+        assert_eq!(dll_type(&known_search_path, "zlib1.dll", 0), Some(DllType::Known));
+        assert_eq!(
+            known_search_path.rules(LOAD_LIBRARY_SEARCH_DEFAULT_DIRS),
+            vec![
+                SearchRule::ActivationContext,
+                SearchRule::KnownDlls,
+                SearchRule::ApplicationDirectory,
+                SearchRule::UserDirectories,
+                SearchRule::SystemDirectory,
+                SearchRule::ApiSets,
+            ]
+        );
+    }
+
+    #[test]
+    fn file_provider() {
+        let mut files = MemoryFiles::new();
+        files.insert("/image/Windows/System32/kernel32.dll", []);
+        files.insert("/image/App/zlib1.dll", []);
+        files.insert("/image/App/Plugins/engine.dll", []);
+        files.insert("/image/Tools/tool.dll", []);
+
+        let system_root = Path::new("/image");
+        let mut search_path = SearchPath::builder(&system_root.join("App"))
+            .system_root(Some(system_root))
+            .file_provider(files)
+            .build()
+            .unwrap();
+
+        assert_eq!(search_path.search("KERNEL32.dll", 0).unwrap().1, DllType::System);
+        assert_eq!(search_path.search("zlib1.dll", 0).unwrap().1, DllType::User);
+        assert!(search_path.search(r"plugins\engine.dll", 0).is_some());
+        assert!(search_path.search("missing.dll", 0).is_none());
+
+        search_path.set_base_directory(&system_root.join("Tools")).unwrap();
+        assert_eq!(search_path.search("tool.dll", 0).unwrap().1, DllType::User);
+        assert!(search_path.search(r"plugins\engine.dll", 0).is_none());
+    }
+
+    #[test]
+    fn known_dll_closure() {
+        let system_directory = PathBuf::from(r"C:\Windows\System32");
+        let file = |dependencies: &[&str]| InventoryFile {
+            machine: None,
+            dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+        };
+        let files = [
+            ("kernel32.dll", file(&["KERNELBASE.dll", "ntdll.dll"])),
+            ("kernelbase.dll", file(&["ntdll.dll", "missing.dll"])),
+            ("ntdll.dll", file(&[])),
+            ("user32.dll", file(&[])),
+        ];
+        let inventory = Inventory {
+            system_directory: system_directory.clone(),
+            known_dlls: vec![
+                "kernel32.dll".to_owned(),
+                "ntdll.dll".to_owned(),
+                "wow64only.dll".to_owned(),
+            ],
+            directories: [(
+                system_directory,
+                files
+                    .into_iter()
+                    .map(|(name, file)| (name.to_owned(), file))
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+            api_sets: [("api-ms-win-core-file-l1-2".to_owned(), "kernelbase.dll".to_owned())]
+                .into_iter()
+                .collect(),
+            ..Inventory::default()
+        };
+
+        let directory = std::env::temp_dir();
+        let search_path = SearchPath::builder(&directory)
+            .inventory(Some(inventory))
+            .build()
+            .unwrap();
+
+        let known_dlls: Vec<_> = search_path
+            .known_dlls()
+            .into_iter()
+            .map(|(name, _, importer)| (name, importer))
+            .collect();
+        assert_eq!(
+            known_dlls,
+            vec![
+                ("kernel32.dll", None),
+                ("kernelbase.dll", Some("kernel32.dll")),
+                ("ntdll.dll", None),
+            ]
+        );
+        assert_eq!(search_path.search("KernelBase.dll", 0).unwrap().1, DllType::Known);
+        // Listed in the registry but missing from the system directory
+        assert!(search_path.search("wow64only.dll", 0).is_none());
+        assert_eq!(
+            search_path.resolve_api_set("api-ms-win-core-file-l1-2-0.dll"),
+            Some("kernelbase.dll")
+        );
+    }
+
+    #[test]
+    fn path_qualified() {
+        let system_root = std::env::temp_dir().join("dllwalk-path-qualified");
+        let application_directory = system_root.join("app");
+        let plugin_directory = application_directory.join("plugins");
+        std::fs::create_dir_all(&plugin_directory).unwrap();
+        std::fs::write(plugin_directory.join("engine.dll"), []).unwrap();
+        std::fs::write(application_directory.join("engine.dll"), []).unwrap();
+
+        let search_path = SearchPath::builder(&application_directory)
+            .system_root(Some(&system_root))
+            .build()
+            .unwrap();
+        let relative = search_path.search(r"Plugins\Engine.dll", 0);
+        let absolute = search_path.search(r"C:\App\plugins\engine.dll", 0);
+        let missing = search_path.search(r"plugins\missing.dll", 0);
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        let (path, dll_type) = relative.unwrap();
+        assert_eq!(dll_type, DllType::User);
+        assert!(same_directory(path.parent().unwrap(), &plugin_directory));
+        assert!(same_directory(&absolute.unwrap().0, &path));
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn assembly_short_name() {
+        let system_root = std::env::temp_dir().join("dllwalk-assembly-short-name");
+        let application_directory = system_root.join("app");
+        let version_directory = system_root
+            .join("Windows")
+            .join("Microsoft.NET")
+            .join("assembly")
+            .join("GAC_MSIL")
+            .join("Contoso")
+            .join("v4.0_1.0.0.0__0123456789abcdef");
+        std::fs::create_dir_all(&application_directory).unwrap();
+        std::fs::create_dir_all(&version_directory).unwrap();
+        std::fs::write(application_directory.join("Newtonsoft.Json.dll"), []).unwrap();
+        std::fs::write(version_directory.join("Contoso.dll"), []).unwrap();
+
+        let search_path = SearchPath::builder(&application_directory)
+            .system_root(Some(&system_root))
+            .build()
+            .unwrap();
+        let short_name = search_path.search_assembly("NEWTON~1.DLL");
+        let global = search_path.search_assembly("Contoso.dll");
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        let (path, dll_type) = short_name.unwrap();
+        assert_eq!(dll_type, DllType::Managed);
+        assert_eq!(path.file_name().unwrap(), "Newtonsoft.Json.dll");
+        assert_eq!(global.unwrap().0.file_name().unwrap(), "Contoso.dll");
+    }
+
+    #[test]
+    fn simulated_path() {
+        let system_root = std::env::temp_dir().join("dllwalk-simulated-path");
+        let application_directory = system_root.join("program files").join("app").join("bin");
+        std::fs::create_dir_all(&application_directory).unwrap();
+        std::fs::write(application_directory.join("app.dll"), []).unwrap();
+
+        let environment = [
+            ("ProgramFiles".to_owned(), r"D:\Program Files".to_owned()),
+            ("ProgramFiles".to_owned(), r"C:\Program Files".to_owned()),
+            (
+                "Path".to_owned(),
+                r"%SystemRoot%\System32;%PROGRAMFILES%\App\Bin".to_owned(),
+            ),
+        ];
+        let search_path = SearchPath::builder(&std::env::temp_dir())
+            .current_directory(&system_root)
+            .system_root(Some(&system_root))
+            .environment(&environment)
+            .build()
+            .unwrap();
+        let (path, dll_type) = search_path.search("app.dll", 0).unwrap();
+
+        let environment = Environment {
+            variables: &environment,
+            system_root: Some(&system_root),
+            files: &LocalFiles,
+        };
+        let expanded = environment.expand("%SystemRoot%%Unknown%%windir%%");
+        std::fs::remove_dir_all(&system_root).unwrap();
+
+        assert_eq!(dll_type, DllType::Path);
+        assert!(same_directory(
+            path.parent().unwrap(),
+            &application_directory
+        ));
+        assert_eq!(expanded, r"C:\Windows%Unknown%C:\Windows%");
+    }
+
+    #[test]
+    fn short_names() {
+        let files: HashMap<String, PathBuf> = [
+            "msvcrt.dll",
+            "microsoft.vc90.crt.dll",
+            "microsoft windows.dll",
+            "microsoft.data.sqlite.dll",
+            "mscorlib.dll",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), PathBuf::from(name)))
+        .collect();
+
+        let find = |name| find_short_name(name, &files).and_then(|path| path.to_str());
+        assert_eq!(find("microd~1.dll"), None);
+        assert_eq!(find("micros~1.dll"), Some("microsoft windows.dll"));
+        assert_eq!(find("micros~2.dll"), Some("microsoft.data.sqlite.dll"));
+        assert_eq!(find("micros~3.dll"), Some("microsoft.vc90.crt.dll"));
+        assert_eq!(find("micros~4.dll"), None);
+        assert_eq!(find("msvcrt~1.dll"), None);
+        assert_eq!(find("mscorlib.dll"), None);
+    }
+
+    #[test]
+    fn publisher_policy() {
+        let redirect = BindingRedirect {
+            old_version: "6.0.0.0-6.0.22621.1".to_owned(),
+            new_version: "6.0.22621.1".to_owned(),
+        };
+
+        assert_eq!(
+            redirect_version(&redirect, &[6, 0, 0, 0]),
+            Some(vec![6, 0, 22621, 1])
+        );
+        assert_eq!(
+            redirect_version(&redirect, &[6, 0, 10586, 0]),
+            Some(vec![6, 0, 22621, 1])
+        );
+        assert_eq!(redirect_version(&redirect, &[6, 0, 22621, 2]), None);
+        assert_eq!(redirect_version(&redirect, &[5, 82, 0, 0]), None);
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(windows)]
+mod wintrust;
+
+// WinVerifyTrust errors of signatures that are present but not trusted
+const TRUST_E_BAD_DIGEST: i32 = 0x80096010_u32 as i32;
+const TRUST_E_EXPLICIT_DISTRUST: i32 = 0x800b0111_u32 as i32;
+const CERT_E_EXPIRED: i32 = 0x800b0101_u32 as i32;
+const CERT_E_UNTRUSTEDROOT: i32 = 0x800b0109_u32 as i32;
+const CERT_E_CHAINING: i32 = 0x800b010a_u32 as i32;
+const CERT_E_REVOKED: i32 = 0x800b010c_u32 as i32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Signed,
+    CatalogSigned,
+    Unsigned,
+    Invalid(i32),
+}
+
+impl std::fmt::Display for SignatureStatus {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureStatus::Signed => write!(formatter, "signed"),
+            SignatureStatus::CatalogSigned => write!(formatter, "catalog-signed"),
+            SignatureStatus::Unsigned => write!(formatter, "unsigned"),
+            SignatureStatus::Invalid(code) => {
+                write!(formatter, "invalid signature (0x{:08x})", code)
+            }
+        }
+    }
+}
+
+impl SignatureStatus {
+    pub fn is_trusted(&self) -> bool {
+        matches!(self, SignatureStatus::Signed | SignatureStatus::CatalogSigned)
+    }
+
+    /// Why an invalid signature is not trusted, for the common errors
+    pub fn reason(&self) -> Option<&'static str> {
+        match self {
+            SignatureStatus::Invalid(code) => match *code {
+                TRUST_E_BAD_DIGEST => Some("file modified after signing"),
+                TRUST_E_EXPLICIT_DISTRUST => Some("certificate explicitly distrusted"),
+                CERT_E_EXPIRED => Some("certificate expired"),
+                CERT_E_UNTRUSTEDROOT => Some("untrusted root certificate"),
+                CERT_E_CHAINING => Some("certificate chain not built to a trusted root"),
+                CERT_E_REVOKED => Some("certificate revoked"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Signature of a file and the name of its signer, known even when the signature is not
+/// trusted
+#[cfg(windows)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub status: SignatureStatus,
+    pub signer: Option<String>,
+}
+
+/// Verifies the Authenticode signature of a file, or its catalog signature
+#[cfg(windows)]
+pub fn verify(path: &std::path::Path) -> Signature {
+    wintrust::verify(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reason() {
+        let untrusted = SignatureStatus::Invalid(0x800b0109_u32 as i32);
+        assert_eq!(untrusted.reason(), Some("untrusted root certificate"));
+        assert_eq!(SignatureStatus::Invalid(1).reason(), None);
+        assert_eq!(SignatureStatus::Signed.reason(), None);
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    ffi::c_void,
+    os::windows::{ffi::OsStrExt, io::AsRawHandle},
+    path::Path,
+    ptr,
+};
+
+use bindings::Windows::Win32::{
+    Foundation::{BOOL, HANDLE, HWND, PWSTR},
+    Security::{
+        CryptCATAdminAcquireContext2, CryptCATAdminCalcHashFromFileHandle2,
+        CryptCATAdminEnumCatalogFromHash, CryptCATAdminReleaseCatalogContext,
+        CryptCATAdminReleaseContext, CryptCATCatalogInfoFromContext,
+        Cryptography::Core::{CertGetNameStringW, CERT_CONTEXT, CERT_NAME_SIMPLE_DISPLAY_TYPE},
+        WTHelperGetProvCertFromChain, WTHelperGetProvSignerFromChain,
+        WTHelperProvDataFromStateData, WinVerifyTrust, CATALOG_INFO, WINTRUST_CATALOG_INFO,
+        WINTRUST_DATA, WINTRUST_DATA_0, WINTRUST_DATA_UNION_CHOICE, WINTRUST_FILE_INFO,
+        WTD_CHOICE_CATALOG, WTD_CHOICE_FILE, WTD_REVOKE_NONE, WTD_STATEACTION_CLOSE,
+        WTD_STATEACTION_VERIFY, WTD_UICONTEXT_EXECUTE, WTD_UI_NONE,
+    },
+};
+use windows::Guid;
+
+use super::{Signature, SignatureStatus};
+use crate::long_path::to_extended_length;
+
+// Actions of the trust providers, from softpub.h and mscat.h
+const WINTRUST_ACTION_GENERIC_VERIFY_V2: Guid = Guid::from_values(
+    0x00aac56b,
+    0xcd44,
+    0x11d0,
+    [0x8c, 0xc2, 0x00, 0xc0, 0x4f, 0xc2, 0x95, 0xee],
+);
+
+const DRIVER_ACTION_VERIFY: Guid = Guid::from_values(
+    0xf750e6c3,
+    0x38ee,
+    0x11d1,
+    [0x85, 0xe5, 0x00, 0xc0, 0x4f, 0xc2, 0x95, 0xee],
+);
+
+// Provider flag of wintrust.h, not in the metadata
+const WTD_CACHE_ONLY_URL_RETRIEVAL: u32 = 0x00001000;
+
+const TRUST_E_NOSIGNATURE: i32 = 0x800b0100_u32 as i32;
+const TRUST_E_SUBJECT_FORM_UNKNOWN: i32 = 0x800b0003_u32 as i32;
+
+pub fn verify(path: &Path) -> Signature {
+    let mut path_wide = to_wide(to_extended_length(path).as_os_str());
+
+    // Embedded Authenticode signature
+    let mut file_info = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: PWSTR(path_wide.as_mut_ptr()),
+        ..Default::default()
+    };
+
+    let (result, signer) = win_verify_trust(
+        WTD_CHOICE_FILE,
+        WINTRUST_DATA_0 {
+            pFile: &mut file_info,
+        },
+    );
+    let status = match result {
+        0 => SignatureStatus::Signed,
+        TRUST_E_NOSIGNATURE | TRUST_E_SUBJECT_FORM_UNKNOWN => {
+            return verify_catalog(path, &mut path_wide)
+        }
+        code => SignatureStatus::Invalid(code),
+    };
+    Signature { status, signer }
+}
+
+// Most system files are not signed themselves but listed in a signed catalog. Newer
+// catalogs list the SHA256 hash of their members, older ones, still used by many drivers,
+// the SHA1 hash.
+fn verify_catalog(path: &Path, path_wide: &mut [u16]) -> Signature {
+    ["SHA256", "SHA1"]
+        .iter()
+        .find_map(|algorithm| verify_catalog_hash(path, path_wide, algorithm))
+        .unwrap_or(Signature {
+            status: SignatureStatus::Unsigned,
+            signer: None,
+        })
+}
+
+// None when no catalog lists the hash of the file
+fn verify_catalog_hash(path: &Path, path_wide: &mut [u16], algorithm: &str) -> Option<Signature> {
+    let file = std::fs::File::open(path).ok()?;
+
+    let mut algorithm = to_wide(algorithm.as_ref());
+    let mut cat_admin = 0;
+    let acquired = unsafe {
+        CryptCATAdminAcquireContext2(
+            &mut cat_admin,
+            &DRIVER_ACTION_VERIFY,
+            PWSTR(algorithm.as_mut_ptr()),
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if !acquired.as_bool() {
+        return None;
+    }
+
+    let mut signature = None;
+    let mut hash = vec![0u8; 64];
+    let mut hash_size = hash.len() as u32;
+    let hashed = unsafe {
+        CryptCATAdminCalcHashFromFileHandle2(
+            cat_admin,
+            HANDLE(file.as_raw_handle() as isize),
+            &mut hash_size,
+            hash.as_mut_ptr(),
+            0,
+        )
+    };
+
+    if hashed.as_bool() {
+        hash.truncate(hash_size as usize);
+
+        let cat_info = unsafe {
+            CryptCATAdminEnumCatalogFromHash(
+                cat_admin,
+                hash.as_mut_ptr(),
+                hash_size,
+                0,
+                ptr::null_mut(),
+            )
+        };
+
+        if cat_info != 0 {
+            let mut catalog = CATALOG_INFO {
+                cbStruct: std::mem::size_of::<CATALOG_INFO>() as u32,
+                ..Default::default()
+            };
+
+            if unsafe { CryptCATCatalogInfoFromContext(cat_info, &mut catalog, 0) }.as_bool() {
+                let member_tag: String = hash.iter().map(|byte| format!("{:02X}", byte)).collect();
+                let mut member_tag = to_wide(member_tag.as_ref());
+
+                let mut catalog_info = WINTRUST_CATALOG_INFO {
+                    cbStruct: std::mem::size_of::<WINTRUST_CATALOG_INFO>() as u32,
+                    pcwszCatalogFilePath: PWSTR(catalog.wszCatalogFile.as_mut_ptr()),
+                    pcwszMemberTag: PWSTR(member_tag.as_mut_ptr()),
+                    pcwszMemberFilePath: PWSTR(path_wide.as_mut_ptr()),
+                    pbCalculatedFileHash: hash.as_mut_ptr(),
+                    cbCalculatedFileHash: hash_size,
+                    hCatAdmin: cat_admin,
+                    ..Default::default()
+                };
+
+                let (result, signer) = win_verify_trust(
+                    WTD_CHOICE_CATALOG,
+                    WINTRUST_DATA_0 {
+                        pCatalog: &mut catalog_info,
+                    },
+                );
+                signature = Some(Signature {
+                    status: match result {
+                        0 => SignatureStatus::CatalogSigned,
+                        code => SignatureStatus::Invalid(code),
+                    },
+                    signer,
+                });
+            }
+
+            unsafe { CryptCATAdminReleaseCatalogContext(cat_admin, cat_info, 0) };
+        }
+    }
+
+    unsafe { CryptCATAdminReleaseContext(cat_admin, 0) };
+
+    signature
+}
+
+// Returns the result of the verification and the signer, read from the state of the
+// verification before it is released
+fn win_verify_trust(
+    union_choice: WINTRUST_DATA_UNION_CHOICE,
+    subject: WINTRUST_DATA_0,
+) -> (i32, Option<String>) {
+    // The union has no default, every field is set
+    let mut data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        pPolicyCallbackData: ptr::null_mut(),
+        pSIPClientData: ptr::null_mut(),
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: union_choice,
+        Anonymous: subject,
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        hWVTStateData: HANDLE::default(),
+        pwszURLReference: PWSTR::default(),
+        dwProvFlags: WTD_CACHE_ONLY_URL_RETRIEVAL,
+        dwUIContext: WTD_UICONTEXT_EXECUTE,
+        pSignatureSettings: ptr::null_mut(),
+    };
+
+    let mut action = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+    let data_pointer = &mut data as *mut WINTRUST_DATA as *mut c_void;
+    let result = unsafe { WinVerifyTrust(HWND::default(), &mut action, data_pointer) };
+
+    let signer = signer_name(data.hWVTStateData);
+
+    // Release the state allocated by the verification
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    let data_pointer = &mut data as *mut WINTRUST_DATA as *mut c_void;
+    unsafe { WinVerifyTrust(HWND::default(), &mut action, data_pointer) };
+
+    (result, signer)
+}
+
+// Display name of the certificate of the first signer
+fn signer_name(state_data: HANDLE) -> Option<String> {
+    if state_data.0 == 0 {
+        return None;
+    }
+
+    let cert: *const CERT_CONTEXT = unsafe {
+        let provider_data = WTHelperProvDataFromStateData(state_data);
+        if provider_data.is_null() {
+            return None;
+        }
+        let signer = WTHelperGetProvSignerFromChain(provider_data, 0, BOOL(0), 0);
+        if signer.is_null() {
+            return None;
+        }
+        let cert = WTHelperGetProvCertFromChain(signer, 0);
+        if cert.is_null() || (*cert).pCert.is_null() {
+            return None;
+        }
+        (*cert).pCert
+    };
+
+    let mut name = [0u16; 256];
+    let length = unsafe {
+        CertGetNameStringW(
+            cert,
+            CERT_NAME_SIMPLE_DISPLAY_TYPE,
+            0,
+            ptr::null_mut(),
+            PWSTR(name.as_mut_ptr()),
+            name.len() as u32,
+        )
+    };
+
+    // The length includes the null terminator, 1 means an empty name
+    if length <= 1 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&name[..length as usize - 1]))
+}
+
+fn to_wide(string: &std::ffi::OsStr) -> Vec<u16> {
+    string.encode_wide().chain(std::iter::once(0)).collect()
+}
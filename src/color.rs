@@ -0,0 +1,81 @@
+use std::io::IsTerminal;
+
+use dllwalk_core::DllType;
+
+#[cfg(windows)]
+const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+#[cfg(windows)]
+const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetStdHandle(std_handle: u32) -> isize;
+
+    fn GetConsoleMode(console: isize, mode: *mut u32) -> i32;
+
+    fn SetConsoleMode(console: isize, mode: u32) -> i32;
+}
+
+/// Whether the standard output is a terminal showing colors, unless NO_COLOR is set
+pub fn is_supported() -> bool {
+    std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal() && enable()
+}
+
+/// Lets the console interpret the color escape sequences, Windows consoles only do once
+/// asked to
+#[cfg(windows)]
+pub fn enable() -> bool {
+    unsafe {
+        let console = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        GetConsoleMode(console, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+#[cfg(not(windows))]
+pub fn enable() -> bool {
+    true
+}
+
+/// Index in the 16 color palette of the terminal of the dlls of `dll_type`, red for
+/// missing dlls and grey for the ones that are ignored or could not be parsed
+pub fn palette_index(dll_type: Option<DllType>, missing: bool) -> u8 {
+    match dll_type {
+        _ if missing => 1,
+        Some(DllType::User) => 2,
+        Some(DllType::Path) => 3,
+        Some(DllType::System) => 4,
+        Some(DllType::Known) => 6,
+        Some(DllType::Umbrella) => 5,
+        Some(DllType::Managed) => 10,
+        Some(DllType::SxS) => 14,
+        Some(DllType::Kernel) => 12,
+        Some(DllType::Driver) => 13,
+        None => 8,
+    }
+}
+
+/// `text` in the color of the dlls of `dll_type`
+pub fn paint(text: &str, dll_type: Option<DllType>, missing: bool) -> String {
+    // The bright colors have their own codes
+    let index = palette_index(dll_type, missing);
+    let code = if index < 8 { 30 + index } else { 90 + index - 8 };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paint() {
+        let painted = super::paint("zlib1.dll", Some(DllType::User), false);
+        assert_eq!(painted, "\x1b[32mzlib1.dll\x1b[0m");
+        assert_eq!(super::paint("zlib1.dll", None, true), "\x1b[31mzlib1.dll\x1b[0m");
+        let painted = super::paint("System.dll", Some(DllType::Managed), false);
+        assert_eq!(painted, "\x1b[92mSystem.dll\x1b[0m");
+    }
+}
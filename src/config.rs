@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::policy::Policy;
+
+pub const CONFIG_FILE_NAME: &str = ".dllwalk.toml";
+pub const DEFAULTS_FILE_NAME: &str = "config.toml";
+
+/// Deployment assumptions of an application, read from the .dllwalk.toml file of its
+/// directory
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Dlls that are not expected to be found, like plugins loaded at runtime
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
+    /// Directory each dll must be loaded from, absolute or relative to the application
+    /// directory
+    #[serde(default)]
+    pub locations: HashMap<String, String>,
+
+    /// Dlls the application may load, enforced by check
+    #[serde(default)]
+    pub policy: Policy,
+
+    /// Defaults of the commands run from this directory
+    #[serde(default)]
+    pub defaults: Defaults,
+}
+
+/// Options used when they are not given on the command line, read from the config.toml
+/// file of the user and the [defaults] table of the .dllwalk.toml file of the current
+/// directory. Values are the ones of the command line options.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Defaults {
+    pub format: Option<String>,
+    pub color: Option<String>,
+    pub exclude_system: Option<bool>,
+    pub only_type: Option<Vec<String>>,
+    pub filter: Option<String>,
+    pub add_dir: Option<Vec<PathBuf>>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// Config of the application in `directory`, None when it has none
+    pub fn load(directory: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        let text = match std::fs::read_to_string(directory.join(CONFIG_FILE_NAME)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(Config::parse(&text)?))
+    }
+}
+
+impl Defaults {
+    /// Defaults of the user, overridden by the ones of the project in `directory`
+    pub fn load(directory: &Path) -> Result<Self, Box<dyn Error>> {
+        let user = match user_file().map(std::fs::read_to_string) {
+            Some(Ok(text)) => toml::from_str(&text)?,
+            Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            _ => Defaults::default(),
+        };
+
+        let project = Config::load(directory)?.map(|config| config.defaults);
+        Ok(project.unwrap_or_default().or(user))
+    }
+
+    // Values of `self`, or of `other` when `self` has none
+    fn or(self, other: Self) -> Self {
+        Defaults {
+            format: self.format.or(other.format),
+            color: self.color.or(other.color),
+            exclude_system: self.exclude_system.or(other.exclude_system),
+            only_type: self.only_type.or(other.only_type),
+            filter: self.filter.or(other.filter),
+            add_dir: self.add_dir.or(other.add_dir),
+        }
+    }
+}
+
+/// %APPDATA%\dllwalk\config.toml on Windows, in the XDG config directory elsewhere
+fn user_file() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))
+    };
+
+    Some(base?.join("dllwalk").join(DEFAULTS_FILE_NAME))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+            ignore = ["engine_plugin.dll"]
+
+            [locations]
+            "msvcp140.dll" = "."
+            "d3dcompiler_47.dll" = 'C:\Windows\System32'
+
+            [policy]
+            deny = ["*d.dll"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.ignore, vec!["engine_plugin.dll"]);
+        assert_eq!(config.locations["msvcp140.dll"], ".");
+        assert_eq!(config.locations["d3dcompiler_47.dll"], r"C:\Windows\System32");
+        assert_eq!(config.policy.deny, vec!["*d.dll"]);
+
+        assert!(Config::parse("").unwrap().ignore.is_empty());
+        assert!(Config::parse("ignored = []").is_err());
+    }
+
+    #[test]
+    fn defaults() {
+        let config = Config::parse(
+            r#"
+            [defaults]
+            format = "json"
+            only-type = ["user", "path"]
+            "#,
+        )
+        .unwrap();
+        let user: Defaults = toml::from_str("format = 'table'\ncolor = 'never'").unwrap();
+
+        let defaults = config.defaults.or(user);
+        assert_eq!(defaults.format.as_deref(), Some("json"));
+        assert_eq!(defaults.color.as_deref(), Some("never"));
+        assert_eq!(defaults.only_type, Some(vec!["user".to_owned(), "path".to_owned()]));
+        assert_eq!(defaults.filter, None);
+        assert!(toml::from_str::<Defaults>("colour = 'never'").is_err());
+    }
+}
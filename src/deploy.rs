@@ -0,0 +1,106 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use clap::ArgEnum;
+use dllwalk_core::{search_path::same_directory, DllDatabase, DllType};
+
+/// What to do with the dlls already in the destination directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum Overwrite {
+    Never,
+    Newer,
+    Always,
+}
+
+/// A dll to copy, skipped when its copy in the destination is kept
+#[derive(Debug, PartialEq, Eq)]
+pub struct Deployment {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub skipped: bool,
+}
+
+/// Dlls of the walk to copy to `directory` so that the application finds them there,
+/// sorted by name. Only the dlls found in user and PATH directories are copied, the
+/// system and known dlls come with Windows.
+pub fn plan(
+    database: &DllDatabase,
+    root: &str,
+    directory: &Path,
+    overwrite: Overwrite,
+) -> Vec<Deployment> {
+    let mut deployments = Vec::new();
+    for name in database.get_all_dlls() {
+        let info = match database.get_dll_info(&name) {
+            Some(info) if name != root && !info.from_inventory => info,
+            _ => continue,
+        };
+        if !matches!(info.dll_type, DllType::User | DllType::Path) {
+            continue;
+        }
+
+        let file_name = match info.path.file_name() {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        if info.path.parent().map_or(false, |parent| same_directory(parent, directory)) {
+            continue;
+        }
+
+        let destination = directory.join(file_name);
+        let modified =
+            |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified());
+        let copy = should_copy(
+            modified(&info.path).ok(),
+            destination
+                .exists()
+                .then(|| modified(&destination).unwrap_or(SystemTime::UNIX_EPOCH)),
+            overwrite,
+        );
+        deployments.push(Deployment {
+            source: info.path.clone(),
+            destination,
+            skipped: !copy,
+        });
+    }
+
+    deployments
+}
+
+// `destination` is the modification time of the copy already there, if any
+fn should_copy(
+    source: Option<SystemTime>,
+    destination: Option<SystemTime>,
+    overwrite: Overwrite,
+) -> bool {
+    match (destination, overwrite) {
+        (None, _) => true,
+        (Some(_), Overwrite::Always) => true,
+        (Some(_), Overwrite::Never) => false,
+        (Some(destination), Overwrite::Newer) => {
+            source.map_or(false, |source| source > destination)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn should_copy() {
+        let old = Some(SystemTime::UNIX_EPOCH);
+        let new = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+
+        assert!(super::should_copy(old, None, Overwrite::Never));
+        assert!(!super::should_copy(new, old, Overwrite::Never));
+        assert!(super::should_copy(old, new, Overwrite::Always));
+        assert!(super::should_copy(new, old, Overwrite::Newer));
+        assert!(!super::should_copy(old, new, Overwrite::Newer));
+        assert!(!super::should_copy(None, old, Overwrite::Newer));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::report::{Node, Report};
+
+/// Difference between the dependencies of two files, found by `compare`
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change<'a> {
+    Added(&'a Node),
+    Removed(&'a Node),
+    /// Same name, resolved to another file or another version
+    Changed(&'a Node, &'a Node),
+}
+
+/// Dependencies added, removed or resolved differently in `new`, sorted by name. The
+/// roots are not compared, the files usually have the same name. The dlls of the application
+/// directories are compared by path relative to them, so that two builds of an application
+/// installed in different directories only differ by what they ship.
+pub fn compare<'a>(old: &'a Report, new: &'a Report) -> Vec<Change<'a>> {
+    let (old_directory, new_directory) = (application_directory(old), application_directory(new));
+
+    let find = |report: &'a Report, name: &str| {
+        report
+            .nodes
+            .iter()
+            .find(|node| node.name.eq_ignore_ascii_case(name) && !is_root(report, node))
+    };
+
+    let mut changes = Vec::new();
+    for node in old.nodes.iter().filter(|node| !is_root(old, node)) {
+        match find(new, &node.name) {
+            Some(new_node)
+                if relative_location(node, old_directory)
+                    != relative_location(new_node, new_directory) =>
+            {
+                changes.push(Change::Changed(node, new_node));
+            }
+            Some(new_node) if node.version != new_node.version => {
+                changes.push(Change::Changed(node, new_node));
+            }
+            Some(_) => {}
+            None => changes.push(Change::Removed(node)),
+        }
+    }
+    for node in new.nodes.iter().filter(|node| !is_root(new, node)) {
+        if find(old, &node.name).is_none() {
+            changes.push(Change::Added(node));
+        }
+    }
+
+    changes.sort_by_key(|change| match change {
+        Change::Added(node) | Change::Removed(node) | Change::Changed(node, _) => {
+            node.name.to_lowercase()
+        }
+    });
+    changes
+}
+
+/// Where the dll is loaded from, or why it is not
+pub fn location(node: &Node) -> String {
+    match (&node.path, &node.api_set_host) {
+        (Some(path), _) => path.clone(),
+        (None, Some(host)) => format!("api set implemented by {}", host),
+        (None, None) if node.not_found => "NOT FOUND".to_owned(),
+        (None, None) if node.ignored => "ignored".to_owned(),
+        (None, None) => "unreadable".to_owned(),
+    }
+}
+
+// Directory of the root of `report`, None when it was not found
+fn application_directory(report: &Report) -> Option<&str> {
+    let root = report.nodes.iter().find(|node| is_root(report, node))?;
+    let (directory, _) = root.path.as_deref()?.rsplit_once(['\\', '/'])?;
+    Some(directory)
+}
+
+// Location of `node`, relative to `directory` and lowercase when the dll is in it or in one of
+// its subdirectories like the private paths of the application
+fn relative_location(node: &Node, directory: Option<&str>) -> String {
+    let location = location(node);
+    let relative = directory.and_then(|directory| {
+        let prefix = location.get(..directory.len())?;
+        let relative = location[directory.len()..].strip_prefix(['\\', '/'])?;
+        prefix
+            .eq_ignore_ascii_case(directory)
+            .then(|| relative.to_lowercase())
+    });
+    relative.unwrap_or(location)
+}
+
+fn is_root(report: &Report, node: &Node) -> bool {
+    node.name == report.root
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compare() {
+        let node = |name: &str, path: Option<&str>, version: Option<&str>| Node {
+            path: path.map(str::to_owned),
+            version: version.map(str::to_owned),
+            not_found: path.is_none(),
+            ..Node::named(name)
+        };
+
+        let old = Report::of(
+            "old.exe",
+            vec![
+                node("old.exe", Some(r"C:\App\old.exe"), None),
+                node("foo.dll", Some(r"C:\App\foo.dll"), Some("1.0")),
+                node("bar.dll", Some(r"C:\App\bar.dll"), None),
+                node("zlib1.dll", None, None),
+            ],
+            Vec::new(),
+        );
+        let new = Report::of(
+            "new.exe",
+            vec![
+                node("new.exe", Some(r"C:\App\new.exe"), None),
+                node("BAR.dll", Some(r"C:\App\bar.dll"), None),
+                node("foo.dll", Some(r"C:\App\foo.dll"), Some("1.1")),
+                node("zlib1.dll", Some(r"C:\App\zlib1.dll"), None),
+                node("qux.dll", Some(r"C:\App\qux.dll"), None),
+            ],
+            Vec::new(),
+        );
+
+        let changes = super::compare(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                Change::Changed(&old.nodes[1], &new.nodes[2]),
+                Change::Added(&new.nodes[4]),
+                Change::Changed(&old.nodes[3], &new.nodes[3]),
+            ]
+        );
+        assert_eq!(location(&old.nodes[3]), "NOT FOUND");
+    }
+
+    #[test]
+    fn compare_application_directories() {
+        let node = |name: &str, path: &str| Node {
+            path: Some(path.to_owned()),
+            ..Node::named(name)
+        };
+        let report = |nodes| Report::of("app.exe", nodes, Vec::new());
+
+        let old = report(vec![
+            node("app.exe", r"C:\App 1.0\app.exe"),
+            node("foo.dll", r"C:\App 1.0\foo.dll"),
+            node("bar.dll", r"C:\App 1.0\plugins\bar.dll"),
+            node("zlib1.dll", r"C:\Windows\System32\zlib1.dll"),
+        ]);
+        let new = report(vec![
+            node("app.exe", r"D:\App 1.1\app.exe"),
+            node("foo.dll", r"D:\App 1.1\FOO.dll"),
+            node("bar.dll", r"D:\App 1.1\bar.dll"),
+            node("zlib1.dll", r"D:\App 1.1\zlib1.dll"),
+        ]);
+
+        let changes = super::compare(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                Change::Changed(&old.nodes[2], &new.nodes[2]),
+                Change::Changed(&old.nodes[3], &new.nodes[3]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use dllwalk_core::{DllDatabase, DllType};
+use regex::Regex;
+
+use crate::report::Edge;
+
+/// Dlls shown by tree and list, the others are still resolved and walked
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub exclude_system: bool,
+    pub only_types: Vec<DllType>,
+    pub name: Option<Regex>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        !self.exclude_system && self.only_types.is_empty() && self.name.is_none()
+    }
+
+    /// Missing dlls have no type and are only filtered by name, they are the ones to fix
+    pub fn matches(&self, database: &DllDatabase, name: &str) -> bool {
+        if let Some(regex) = &self.name {
+            if !regex.is_match(name) {
+                return false;
+            }
+        }
+
+        match database.get_dll_info(name) {
+            Some(info) => self.matches_type(info.dll_type),
+            None => true,
+        }
+    }
+
+    fn matches_type(&self, dll_type: DllType) -> bool {
+        if self.exclude_system && is_system(dll_type) {
+            return false;
+        }
+        self.only_types.is_empty() || self.only_types.contains(&dll_type)
+    }
+
+    /// Lowercase names of the dlls matching the filter and of the dlls leading to them, so
+    /// that a tree keeps the import chains of the dlls it shows
+    pub fn visible(&self, database: &DllDatabase) -> HashSet<String> {
+        let mut edges: Vec<(String, String)> = Vec::new();
+        for name in database.get_all_dlls() {
+            for edge in Edge::from_dependencies(database, &name) {
+                edges.push((edge.importer.to_lowercase(), edge.imported.to_lowercase()));
+            }
+            // The tree shows the dependencies of the host under an umbrella dll
+            let host = database.get_dll_info(&name).and_then(|info| info.api_set_host.as_ref());
+            if let Some(host) = host {
+                edges.push((name.to_lowercase(), host.to_lowercase()));
+            }
+        }
+
+        let mut visible: HashSet<String> = database
+            .get_all_dlls()
+            .into_iter()
+            .filter(|name| self.matches(database, name))
+            .map(|name| name.to_lowercase())
+            .collect();
+        add_importers(&mut visible, &edges);
+        visible
+    }
+}
+
+// Adds the dlls importing the `names`, directly or not
+fn add_importers(names: &mut HashSet<String>, edges: &[(String, String)]) {
+    let mut queue: Vec<String> = names.iter().cloned().collect();
+    while let Some(name) = queue.pop() {
+        for (importer, _) in edges.iter().filter(|(_, imported)| *imported == name) {
+            if names.insert(importer.clone()) {
+                queue.push(importer.clone());
+            }
+        }
+    }
+}
+
+/// Dlls that come with Windows
+pub fn is_system(dll_type: DllType) -> bool {
+    matches!(
+        dll_type,
+        DllType::System | DllType::Known | DllType::Umbrella | DllType::Kernel
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_type() {
+        assert!(Filter::default().is_empty());
+
+        let filter = Filter {
+            exclude_system: true,
+            ..Filter::default()
+        };
+        assert!(filter.matches_type(DllType::User));
+        assert!(!filter.matches_type(DllType::Known));
+
+        let filter = Filter {
+            exclude_system: true,
+            only_types: vec![DllType::Path, DllType::System],
+            name: None,
+        };
+        assert!(filter.matches_type(DllType::Path));
+        assert!(!filter.matches_type(DllType::User));
+        assert!(!filter.matches_type(DllType::System));
+    }
+
+    #[test]
+    fn add_importers() {
+        let edge = |importer: &str, imported: &str| (importer.to_owned(), imported.to_owned());
+        let edges = [
+            edge("app.exe", "qt5core.dll"),
+            edge("app.exe", "kernel32.dll"),
+            edge("qt5gui.dll", "qt5core.dll"),
+            edge("app.exe", "qt5gui.dll"),
+            edge("qt5core.dll", "zlib1.dll"),
+            edge("zlib1.dll", "qt5core.dll"),
+        ];
+
+        let mut names = HashSet::from(["zlib1.dll".to_owned()]);
+        super::add_importers(&mut names, &edges);
+        let mut names: Vec<_> = names.into_iter().collect();
+        names.sort();
+        assert_eq!(names, ["app.exe", "qt5core.dll", "qt5gui.dll", "zlib1.dll"]);
+    }
+}
@@ -1,62 +1,238 @@
-use std::path::PathBuf;
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
 
-use crate::dll_database::DllDatabase;
+use log::{error, info, warn};
+use regex::{Regex, RegexBuilder};
 
-mod dll_database;
-mod error;
-mod pe;
-mod registry;
-mod search_path;
+use dllwalk_core::{
+    directory_cache::{self, CacheStats, DirectoryCache},
+    file_provider,
+    hijack::UnknownPermissions,
+    inventory::Inventory,
+    long_path,
+    manifest::{self, Manifest},
+    pe::{self, Architecture, DependencyKind},
+    search_path::{self, SearchOutcome, SearchPath},
+    signature::SignatureStatus,
+    DllDatabase, DllType, WalkEvent,
+};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum DllType {
-    User,
-    Path,
-    System,
-    Known,
-    Umbrella,
+use crate::{
+    config::{Config, Defaults},
+    diff::Change,
+    deploy::Overwrite,
+    filter::Filter,
+    policy::Policy,
+    process::{Module, Origin, Process},
+    stats::Stats,
+    windows_version::WindowsVersion,
+    report::{Node, Record, Report},
+    runtime::Requirement,
+};
+
+mod color;
+mod config;
+mod deploy;
+mod diff;
+mod filter;
+mod policy;
+mod process;
+mod rdeps;
+mod report;
+mod runtime;
+mod sarif;
+mod stats;
+mod top;
+mod tui;
+mod watch;
+mod windows_version;
+
+use clap::{ArgEnum, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueSource};
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about, long_about = None)]
+struct Arguments {
+    #[clap(subcommand)]
+    command: Commands,
+
+    /// Search the directories of the App Paths registry entry of the executable, like
+    /// when it is started from the shell
+    #[clap(long, global = true)]
+    app_paths: bool,
+
+    /// Directories searched after a SetDefaultDllDirectories call of the application,
+    /// instead of the standard search order
+    #[clap(long, global = true, arg_enum, use_delimiter = true)]
+    search_flags: Vec<SearchFlag>,
+
+    /// Directory added by an AddDllDirectory call of the application, implies
+    /// `--search-flags default-dirs` when no search flags are given
+    #[clap(long, global = true, multiple_occurrences = true)]
+    add_dir: Vec<PathBuf>,
+
+    /// Resolve dlls on a mounted Windows image, the directory containing its Windows
+    /// directory, instead of the running system
+    #[clap(long, global = true)]
+    system_root: Option<PathBuf>,
+
+    /// Search with or without safe dll search mode, auto uses the system configuration
+    #[clap(long, global = true, arg_enum, default_value = "auto")]
+    safe_search: SafeSearch,
+
+    /// PATH of the application, searched instead of the PATH of the running system.
+    /// Directories are separated by semicolons and may reference %VARIABLES%.
+    #[clap(long, global = true)]
+    path: Option<String>,
+
+    /// Environment variable of the application, referenced by the PATH or used to find
+    /// the program files directory
+    #[clap(long, global = true, multiple_occurrences = true, parse(try_from_str = parse_variable))]
+    env: Vec<(String, String)>,
+
+    /// Current directory the application is started from, searched instead of the
+    /// current directory of dllwalk
+    #[clap(long, global = true)]
+    cwd: Option<PathBuf>,
+
+    /// Read every directory instead of using the listings cached by previous runs
+    #[clap(long, global = true)]
+    no_cache: bool,
+
+    /// Resolve dlls on the machine captured by the snapshot command, instead of the
+    /// running system
+    #[clap(long, global = true, conflicts_with = "system-root")]
+    inventory: Option<PathBuf>,
+
+    /// Color the dlls by type, auto colors the output of terminals unless NO_COLOR is set
+    #[clap(long, global = true, arg_enum, default_value = "auto")]
+    color: ColorChoice,
+
+    /// Write the output to a file instead of the standard output
+    #[clap(short, long, global = true)]
+    output: Option<PathBuf>,
+
+    /// Only print the output of the command, without status messages, and log errors only
+    #[clap(short, long, global = true)]
+    quiet: bool,
+}
+
+fn parse_variable(variable: &str) -> Result<(String, String), String> {
+    match variable.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("expected KEY=VALUE, got {}", variable)),
+    }
+}
+
+fn parse_filter(filter: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(filter).case_insensitive(true).build()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum SafeSearch {
+    On,
+    Off,
+    Auto,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum SearchFlag {
+    ApplicationDir,
+    UserDirs,
+    System32,
+    DefaultDirs,
 }
 
-impl std::fmt::Display for DllType {
-    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl SearchFlag {
+    fn load_flag(self) -> u32 {
         match self {
-            DllType::User => write!(formatter, "user-dll"),
-            DllType::Path => write!(formatter, "path-dll"),
-            DllType::System => write!(formatter, "system-dll"),
-            DllType::Known => write!(formatter, "known-dll"),
-            DllType::Umbrella => write!(formatter, "umbrella-dll"),
+            SearchFlag::ApplicationDir => search_path::LOAD_LIBRARY_SEARCH_APPLICATION_DIR,
+            SearchFlag::UserDirs => search_path::LOAD_LIBRARY_SEARCH_USER_DIRS,
+            SearchFlag::System32 => search_path::LOAD_LIBRARY_SEARCH_SYSTEM32,
+            SearchFlag::DefaultDirs => search_path::LOAD_LIBRARY_SEARCH_DEFAULT_DIRS,
         }
     }
 }
 
-fn walk_dlls(database: &mut DllDatabase, name: &str) {
-    let mut visited = std::collections::HashSet::new();
-    let mut queue = Vec::new();
-    queue.push(name.to_owned());
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum Format {
+    Text,
+    Json,
+    Dot,
+    Graphml,
+    Spdx,
+    Sarif,
+    Chains,
+    Ndjson,
+    Xml,
+    Table,
+    Markdown,
+}
 
-    while !queue.is_empty() {
-        let name = queue.pop().unwrap();
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum Problem {
+    Missing,
+    Location,
+    Architecture,
+    Policy,
+}
 
-        if let Some(info) =  database.search_dll(&name) {
-            for dll in &info.file.imports {
-                if !visited.contains(&dll.name) {
-                    queue.push(dll.name.clone());
-                }
-            }
-        }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum HashAlgorithm {
+    Sha256,
+}
 
-        visited.insert(name);
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum SizeOrder {
+    File,
+    Image,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+enum CacheAction {
+    Stats,
+    Clear,
+    Path,
+}
 
-use clap::{Parser, Subcommand};
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum Sort {
+    Name,
+    Type,
+    Size,
+    Depth,
+    Path,
+    Import,
+}
 
-#[derive(Debug, Parser)]
-#[clap(author, version, about, long_about = None)]
-struct Arguments {
-    #[clap(subcommand)]
-    command: Commands,
+type SortKey = (Option<DllType>, Option<u64>, String, String);
+
+// Missing and unreadable dlls come first, then the dlls closest to the application, the
+// smallest or the closest to the root. Ties are sorted by name.
+fn sort_key(database: &DllDatabase, name: &str, sort: Sort) -> SortKey {
+    let info = database.get_dll_info(name);
+    let depth = database.get_depth(name).map(u64::from);
+    let name = name.to_lowercase();
+    match sort {
+        Sort::Type => (info.map(|info| info.dll_type), None, String::new(), name),
+        Sort::Size => (None, info.and_then(|info| info.size), String::new(), name),
+        Sort::Depth => (None, depth, String::new(), name),
+        Sort::Path => {
+            let path = info.map(|info| info.path.to_string_lossy().to_lowercase());
+            (None, None, path.unwrap_or_default(), name)
+        }
+        Sort::Name | Sort::Import => (None, None, String::new(), name),
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -73,6 +249,72 @@ enum Commands {
         // Maximum depth
         #[clap(short, long)]
         depth: Option<u32>,
+
+        /// Print the tree of one of the dlls loaded by the file instead, like ucrtbase.dll
+        #[clap(long, value_name = "DLL")]
+        root: Option<String>,
+
+        /// Show the file version, product version and company name
+        #[clap(long)]
+        show_version: bool,
+
+        /// Mark unsigned and invalidly signed dlls
+        #[clap(long)]
+        verify_signatures: bool,
+
+        /// Mark dlls whose checksum does not match the optional header
+        #[clap(long)]
+        verify_checksums: bool,
+
+        /// Show the hash of each file
+        #[clap(long, arg_enum)]
+        hash: Option<HashAlgorithm>,
+
+        /// Show the other files named like each dll in its search order, which it shadows
+        #[clap(long)]
+        shadowed: bool,
+
+        /// Mark dlls built without ASLR, DEP or CFG, or with writable and executable sections
+        #[clap(long)]
+        mitigations: bool,
+
+        /// Show how many functions each dll imports from its dependencies
+        #[clap(long)]
+        symbol_counts: bool,
+
+        /// Tag each dll with its type, like [known] or [user], and the dlls loaded through a
+        /// forwarded export or an assembly with [forwarded] or [assembly]
+        #[clap(long)]
+        show_type: bool,
+
+        /// Print the system, known, api set and kernel dlls without their imports, with the
+        /// number of dlls they load
+        #[clap(long)]
+        collapse_system: bool,
+
+        /// Print the tree as text, a Markdown list or a table of its dlls, the dependency graph
+        /// as JSON, newline-delimited JSON, XML, Graphviz DOT or GraphML, the dlls loaded as an
+        /// SPDX document, the problems found as a SARIF log, or the shortest import chain leading
+        /// to each dll
+        #[clap(long, arg_enum, default_value = "text")]
+        format: Format,
+
+        /// Order the imports of each dll by name, by type, size, depth or path then name, or as
+        /// imported
+        #[clap(long, arg_enum, default_value = "name")]
+        sort: Sort,
+
+        /// Hide the system, known, api set and kernel dlls
+        #[clap(long)]
+        exclude_system: bool,
+
+        /// Only show the dlls of these types
+        #[clap(long, arg_enum, use_delimiter = true)]
+        only_type: Vec<DllType>,
+
+        /// Only show the dlls whose name matches a regular expression, ignoring case
+        #[clap(long, value_name = "REGEX", parse(try_from_str = parse_filter))]
+        filter: Option<Regex>,
     },
 
     /// List the imported dlls
@@ -83,107 +325,2778 @@ enum Commands {
         /// Show the files absolute path
         #[clap(short, long)]
         absolute_path: bool,
+
+        /// Show the file version, product version and company name
+        #[clap(long)]
+        show_version: bool,
+
+        /// Mark unsigned and invalidly signed dlls
+        #[clap(long)]
+        verify_signatures: bool,
+
+        /// Mark dlls whose checksum does not match the optional header
+        #[clap(long)]
+        verify_checksums: bool,
+
+        /// Show the hash of each file
+        #[clap(long, arg_enum)]
+        hash: Option<HashAlgorithm>,
+
+        /// Show the other files named like each dll in its search order, which it shadows
+        #[clap(long)]
+        shadowed: bool,
+
+        /// Only list the dlls imported through at most this many dlls, 1 for the direct
+        /// imports of the file
+        #[clap(short, long)]
+        depth: Option<u32>,
+
+        /// Show the pdb path and symbol server identifier
+        #[clap(long)]
+        debug_info: bool,
+
+        /// Show the link time, or the image hash of reproducible builds
+        #[clap(long)]
+        timestamps: bool,
+
+        /// Show how many functions are imported from each dll
+        #[clap(long)]
+        symbol_counts: bool,
+
+        /// Print the dlls in a section per type, the missing ones first
+        #[clap(long)]
+        group_by_type: bool,
+
+        /// Print the list as text, a table or a Markdown table, the dependency graph as JSON,
+        /// newline-delimited JSON, XML, Graphviz DOT or GraphML, the dlls loaded as an SPDX
+        /// document, the problems found as a SARIF log, or the shortest import chain leading to
+        /// each dll
+        #[clap(long, arg_enum, default_value = "text")]
+        format: Format,
+
+        /// Order the dlls by name, by type, size, depth or path then name, or as the walk finds
+        /// them
+        #[clap(long, arg_enum, default_value = "name")]
+        sort: Sort,
+
+        /// Hide the system, known, api set and kernel dlls
+        #[clap(long)]
+        exclude_system: bool,
+
+        /// Only show the dlls of these types
+        #[clap(long, arg_enum, use_delimiter = true)]
+        only_type: Vec<DllType>,
+
+        /// Only show the dlls whose name matches a regular expression, ignoring case
+        #[clap(long, value_name = "REGEX", parse(try_from_str = parse_filter))]
+        filter: Option<Regex>,
+    },
+
+    /// Print the problems of the dependencies of a file and exit with status 1 when one of
+    /// them is fatal, to gate packaging pipelines
+    Check {
+        /// File to parse
+        file: PathBuf,
+
+        /// Problems failing the check: missing dlls, dlls loaded from another directory than
+        /// the one .dllwalk.toml expects, dlls built for another architecture, and dlls
+        /// violating the policy
+        #[clap(
+            long,
+            arg_enum,
+            use_delimiter = true,
+            default_value = "missing,location,architecture,policy"
+        )]
+        fail_on: Vec<Problem>,
+
+        /// TOML file of dlls to deny, allow or require, added to the [policy] table of
+        /// .dllwalk.toml
+        #[clap(long, value_name = "FILE")]
+        policy: Option<PathBuf>,
+
+        /// Check again whenever the files of the directory of the file change
+        #[clap(long)]
+        watch: bool,
+    },
+
+    /// Check that every imported symbol is exported by the resolved dll
+    CheckSymbols {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Verify the Authenticode signatures of a file and of every dll it loads, print their
+    /// signers and list the unsigned and untrusted ones. Fails if any is not trusted.
+    SignCheck {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the headers, security mitigations, bound imports and toolchain of each dll
+    Info {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the directories writable by standard users where a planted dll would be
+    /// loaded instead of a dependency. Outside of Windows, the default permissions of a
+    /// Windows installation are assumed, which needs --system-root.
+    Hijack {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the locations consulted to resolve the imports of a file, in order
+    SearchOrder {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Write a single HTML page to browse the dependencies of a file, to attach to bug
+    /// reports
+    Report {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the version resource of a file, its fixed information and the strings of every
+    /// language, or of every dll it loads
+    VersionInfo {
+        /// File to parse
+        file: PathBuf,
+
+        /// Print the version resources of the dependencies too
+        #[clap(long)]
+        all: bool,
+    },
+
+    /// Print the manifest embedded in a file: the requested execution level, dependent
+    /// assemblies, supported Windows versions and settings like dpiAware
+    Manifest {
+        /// File to parse
+        file: PathBuf,
+
+        /// Print the XML of the manifest as it is embedded
+        #[clap(long)]
+        raw: bool,
+    },
+
+    /// Print the rules and directories consulted to resolve a dll imported by a file, in
+    /// order, and why each candidate was skipped
+    Explain {
+        /// File to parse
+        file: PathBuf,
+
+        /// Name of the dll to resolve
+        dll: String,
+    },
+
+    /// Print the import chains from a file to one of its dependencies, shortest first, to
+    /// find out which dlls load it
+    Why {
+        /// File to parse
+        file: PathBuf,
+
+        /// Name of the dependency
+        dll: String,
+
+        /// Print at most this many chains
+        #[clap(long, default_value = "10")]
+        max_chains: usize,
+    },
+
+    /// Print the dlls loaded by a file that export a function, and where forwarded exports
+    /// lead
+    FindSymbol {
+        /// File to parse
+        file: PathBuf,
+
+        /// Name of the function, case sensitive
+        symbol: String,
+    },
+
+    /// Print the dependencies added, removed or resolved to another file or version
+    /// between two files, like two versions of an application
+    Diff {
+        /// File to compare
+        old: PathBuf,
+
+        /// File to compare it with
+        new: PathBuf,
+    },
+
+    /// Print the modules loaded in a running process, marking the ones loaded at runtime and
+    /// the ones loaded from another path than the one the walk of its executable resolves
+    Process {
+        /// Id or executable name of the process
+        target: String,
+    },
+
+    /// Print the executables and dlls under a directory that load a dll, directly or through
+    /// other dlls, with the shortest import chain leading to it
+    Rdeps {
+        /// Directory to scan, with its subdirectories
+        directory: PathBuf,
+
+        /// Dll to look for, like msvcr120.dll
+        #[clap(long)]
+        dll: String,
+    },
+
+    /// Copy the dlls found in user and PATH directories next to a file, or to another
+    /// directory, to deploy the application with its dependencies
+    CopyDeps {
+        /// File to parse
+        file: PathBuf,
+
+        /// Directory to copy the dlls to instead of the directory of the file
+        #[clap(long)]
+        destination: Option<PathBuf>,
+
+        /// Print the copies without making them
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Replace the dlls already in the destination never, when older or always
+        #[clap(long, arg_enum, default_value = "never")]
+        overwrite: Overwrite,
+    },
+
+    /// Print the redistributable runtimes the dependencies of a file need, like the Visual C++
+    /// Redistributable, with the dlls needing each, to document how to deploy it
+    RuntimeRequirements {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the minimum Windows version the dlls of a file can run on, from their headers
+    /// and the api sets and system dlls they import, and the dlls requiring it
+    MinWindowsVersion {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the number of dlls of each type, the missing dlls, the maximum depth, the
+    /// longest import chain and the total size of the dependencies of a file
+    Stats {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the largest dependencies of a file, with their size on disk and in memory, and
+    /// the totals of the dlls shipped with it and of the system dlls
+    Top {
+        /// File to parse
+        file: PathBuf,
+
+        /// Rank the dlls by the size of their file or of their image mapped in memory
+        #[clap(long, arg_enum, default_value = "file")]
+        by: SizeOrder,
+
+        /// Number of dlls to print, 0 prints them all
+        #[clap(long, default_value = "10")]
+        count: usize,
+    },
+
+    /// Browse the dependency tree of a file in the terminal, with the details, imports and
+    /// exports of each dll
+    Tui {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print the known dlls of the processes running a file, the ones listed in the
+    /// registry and the dlls they import
+    KnownDlls {
+        /// File to parse
+        file: PathBuf,
+    },
+
+    /// Print what the cache of directory listings holds, delete it, or print where it is
+    /// stored, to reset it when it gets stale
+    Cache {
+        #[clap(arg_enum)]
+        action: CacheAction,
+    },
+
+    /// Save the system directories, PATH and loader settings of this machine, to
+    /// resolve dlls against them elsewhere with --inventory
+    Snapshot {
+        /// JSON file to create, instead of the --output file
+        #[clap(value_name = "OUTPUT")]
+        file: Option<PathBuf>,
     },
 }
 
 
-pub struct TreePrinter {
-    max_depth: Option<u32>,
+fn dll_label(
+    database: &DllDatabase,
+    name: &str,
     absolute_path: bool,
-}
+    show_version: bool,
+) -> String {
+    let info = database.get_dll_info(name);
 
-impl TreePrinter {
-    pub fn new(max_depth: Option<u32>, absolute_path: bool) -> Self {
-        Self {
-            max_depth,
-            absolute_path,
-        }
+    let mut label = name.to_owned();
+    if database.is_missing(name) {
+        return format!("{} [NOT FOUND]", label);
+    }
+
+    if database.is_ignored(name) {
+        return format!("{} [ignored]", label);
     }
 
-    pub fn print(&self, database: &DllDatabase, name: &str, depth: u32, last_child: bool) {
-        TreePrinter::print_prefix(depth, last_child);
-        
-        if self.absolute_path {
-            if let Some(info) = database.get_dll_info(name)  {
-                let path = info.path.to_string_lossy();
-                println!("{}", if path.is_empty() { name } else { &path });
+    if absolute_path {
+        if let Some(info) = info {
+            let path = info.path.to_string_lossy();
+            if !path.is_empty() {
+                label = path.into_owned();
             }
         }
-        else {
-            println!("{}", name);
-        }
-        
-    
-        if let Some(info) = database.get_dll_info(name) {
-            for (index, dll) in info.file.imports.iter().enumerate() {
-                if depth < self.max_depth.unwrap_or(u32::MAX) {
-                    self.print(database, &dll.name, depth + 1, index ==  info.file.imports.len() - 1);
-                }
-            }
+    }
+
+    if let Some(signature) = info.and_then(|info| info.signature) {
+        if !signature.is_trusted() {
+            label = format!("{} [{}]", label, signature);
         }
     }
 
-    fn print_prefix(depth: u32, last_child: bool) {
-        if depth > 1 {
-            for _ in 0..depth - 1 {
-                print!("│   ");
+    if info.and_then(|info| info.checksum_valid) == Some(false) {
+        label = format!("{} [checksum mismatch]", label);
+    }
+
+    if let Some(sha256) = info.and_then(|info| info.sha256.as_ref()) {
+        label = format!("{} [sha256 {}]", label, sha256);
+    }
+
+    if let Some(expected) = database.get_unexpected_location(name) {
+        label = format!("{} [expected in {}]", label, expected.to_string_lossy());
+    }
+
+    let shadowed = database.get_shadowed_dlls(name);
+    if !shadowed.is_empty() {
+        let paths: Vec<_> = shadowed.iter().map(|path| path.to_string_lossy()).collect();
+        label = format!("{} [shadows {}]", label, paths.join(", "));
+    }
+
+    if show_version {
+        if let Some(version_info) = info.and_then(|info| info.file.version_info.as_ref()) {
+            let mut details = Vec::new();
+            if let Some(version) = version_info.file_version_string() {
+                details.push(version);
             }
-        }
-        if depth > 0 {   
-            if last_child {
-                print!("└── ");
+            if let Some(version) = version_info.product_version_string() {
+                details.push(format!("product {}", version));
             }
-            else {
-                print!("├── ");
+            if let Some(company) = version_info.get("CompanyName") {
+                details.push(company.to_owned());
+            }
+
+            if !details.is_empty() {
+                label = format!("{} ({})", label, details.join(", "));
             }
         }
     }
+
+    label
 }
 
-fn print_list(database: &DllDatabase, absolute_path: bool) {
-    let dlls = database.get_all_dlls();
-    for dll in dlls {
-        if absolute_path {
-            if let Some(info) = database.get_dll_info(&dll)  {
-                let path = info.path.to_string_lossy().to_string();
-                println!("{}", if path.is_empty() { &dll } else { &path });
-            }
-        }
-        else {
-            println!("{}", dll);
+// Missing and ignored dlls are already tagged by their label. The tags are the names
+// --only-type accepts.
+fn with_type_tag(label: String, database: &DllDatabase, name: &str) -> String {
+    let dll_type = database.get_dll_info(name).map(|info| info.dll_type);
+    match dll_type.and_then(|dll_type| dll_type.to_possible_value()) {
+        Some(value) => format!("{} [{}]", label, value.get_name()),
+        None => label,
+    }
+}
+
+// The COFF timestamp is seconds since the Unix epoch, unless the image was linked
+// with /Brepro in which case it is a hash of the image
+fn format_timestamp(file: &pe::File) -> String {
+    if file.reproducible {
+        return format!("reproducible build {:08x}", file.timestamp);
+    }
+
+    let (year, month, day) = civil_date(file.timestamp as u64 / 86400);
+    let seconds = file.timestamp % 86400;
+
+    format!(
+        "linked {:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Year, month and day from the number of days since 1970-01-01
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Options of the tree and list commands, on how each dll is printed
+#[derive(Debug)]
+pub struct PrintOptions {
+    pub max_depth: Option<u32>,
+    pub absolute_path: bool,
+    pub show_version: bool,
+    pub show_mitigations: bool,
+    pub show_symbol_counts: bool,
+    pub show_type: bool,
+    pub collapse_system: bool,
+    pub debug_info: bool,
+    pub timestamps: bool,
+    pub group_by_type: bool,
+    pub color: bool,
+    pub markdown: bool,
+    pub sort: Sort,
+}
+
+impl PrintOptions {
+    /// Options given to `command`, the ones it does not have are off
+    fn new(command: &Commands, color: bool) -> Self {
+        let options = PrintOptions {
+            max_depth: None,
+            absolute_path: false,
+            show_version: false,
+            show_mitigations: false,
+            show_symbol_counts: false,
+            show_type: false,
+            collapse_system: false,
+            debug_info: false,
+            timestamps: false,
+            group_by_type: false,
+            color,
+            markdown: false,
+            sort: Sort::Name,
+        };
+
+        match *command {
+            Commands::Tree {
+                absolute_path,
+                depth,
+                show_version,
+                mitigations,
+                symbol_counts,
+                show_type,
+                collapse_system,
+                format,
+                sort,
+                ..
+            } => PrintOptions {
+                max_depth: depth,
+                absolute_path,
+                show_version,
+                show_mitigations: mitigations,
+                show_symbol_counts: symbol_counts,
+                show_type,
+                collapse_system,
+                markdown: format == Format::Markdown,
+                sort,
+                ..options
+            },
+            Commands::List {
+                absolute_path,
+                show_version,
+                depth,
+                debug_info,
+                timestamps,
+                symbol_counts,
+                group_by_type,
+                format,
+                sort,
+                ..
+            } => PrintOptions {
+                max_depth: depth,
+                absolute_path,
+                show_version,
+                show_symbol_counts: symbol_counts,
+                debug_info,
+                timestamps,
+                group_by_type,
+                markdown: format == Format::Markdown,
+                sort,
+                ..options
+            },
+            _ => options,
         }
     }
 }
 
+/// How a dll of the tree is loaded by its importer
+pub struct TreeEdge {
+    importer_architecture: Option<Architecture>,
+    imported_symbols: Option<usize>,
+    kind: DependencyKind,
+    last_child: bool,
+}
 
-fn main() {
-    env_logger::init();
+pub struct TreePrinter<'a> {
+    options: &'a PrintOptions,
+    /// Lowercase names of the dlls to show, all of them when None
+    visible: Option<HashSet<String>>,
+}
 
-    let args = Arguments::parse();
+impl<'a> TreePrinter<'a> {
+    pub fn new(options: &'a PrintOptions, visible: Option<HashSet<String>>) -> Self {
+        Self { options, visible }
+    }
 
-    let current_directory = std::env::current_dir().expect("Failed to get current directory");
+    /// `ancestors` are the dlls importing `name` down from the root, a dll importing one of
+    /// them is marked as a cycle and its imports are not printed again. `edge` is how the
+    /// importer depends on it, None for the root.
+    pub fn print(
+        &self,
+        out: &mut dyn Write,
+        database: &DllDatabase,
+        name: &str,
+        ancestors: &mut Vec<String>,
+        edge: Option<&TreeEdge>,
+    ) -> io::Result<()> {
+        let options = self.options;
+        let depth = ancestors.len() as u32;
+        let importer_architecture = edge.and_then(|edge| edge.importer_architecture);
+        if options.markdown {
+            write!(out, "{}- ", "  ".repeat(depth as usize))?;
+        } else {
+            TreePrinter::print_prefix(out, depth, edge.map_or(false, |edge| edge.last_child))?;
+        }
 
-    let file = match &args.command {
-        Commands::Tree { file, ..} => file,
-        Commands::List { file, ..} => file,
-    };
+        let mut info = database.get_dll_info(name);
+        let mut label = dll_label(database, name, options.absolute_path, options.show_version);
+        let dll_type = info.map(|info| info.dll_type);
+        if options.show_type {
+            label = with_type_tag(label, database, name);
+        }
 
-    let base_directory = file.parent().unwrap_or(&current_directory);
+        // An umbrella dll is shown along with the host implementing its api set, the host
+        // dependencies follow
+        let host = info
+            .filter(|info| info.dll_type == DllType::Umbrella)
+            .and_then(|info| info.api_set_host.as_deref());
+        if let Some(host) = host {
+            let mut host_label =
+                dll_label(database, host, options.absolute_path, options.show_version);
+            if options.show_type {
+                host_label = with_type_tag(host_label, database, host);
+            }
+            label = format!("{} → {}", label, host_label);
+            info = database.get_dll_info(host);
+        }
 
-    let mut database = DllDatabase::new(base_directory, &current_directory)
-        .expect("Failed to initialize the dll database");
+        if options.show_symbol_counts {
+            if let Some(count) = edge.and_then(|edge| edge.imported_symbols) {
+                label = format!("{} [{} functions]", label, count);
+            }
+        }
 
-    let file = file.file_name().unwrap().to_string_lossy();
+        if options.show_type {
+            match edge.map(|edge| edge.kind) {
+                Some(DependencyKind::Delay) => label = format!("{} [delay]", label),
+                Some(DependencyKind::Forwarder) => label = format!("{} [forwarded]", label),
+                Some(DependencyKind::Assembly) => label = format!("{} [assembly]", label),
+                Some(DependencyKind::Import) | None => {}
+            }
+        }
 
-    walk_dlls(&mut database, &file);
+        // The loader refuses to map a dll built for another architecture
+        let architecture = info.and_then(|info| info.architecture());
+        if let (Some(expected), Some(found)) = (importer_architecture, architecture) {
+            if !expected.is_compatible_with(found) {
+                label = format!("{} [{}, expected {}]", label, found, expected);
+            }
+        }
 
-    match args.command {
-        Commands::Tree { absolute_path, depth , ..} => {
-            let printer = TreePrinter::new(depth, absolute_path);
-            printer.print(&database, &file, 0, false);
-        },
-        Commands::List { absolute_path , ..} => {
-            print_list(&database, absolute_path);
-        },
+        if let Some(info) = info.filter(|info| info.has_headers()) {
+            if depth > 0 && info.is_executable() {
+                label = format!("{} [executable]", label);
+            }
+            if info.file.relocations_stripped() && info.file.needs_relocations() {
+                label = format!("{} [relocations stripped]", label);
+            }
+            if info.file.is_resource_only() {
+                label = format!("{} [resource only]", label);
+            }
+        }
+
+        if options.show_mitigations {
+            // Mitigations only matter for dlls that contain code
+            let info = info.filter(|info| info.has_headers() && !info.file.is_resource_only());
+            if let Some(info) = info {
+                let mut issues: Vec<String> = info
+                    .file
+                    .missing_mitigations()
+                    .iter()
+                    .map(|mitigation| format!("no {}", mitigation))
+                    .collect();
+                for section in &info.file.writable_executable_sections {
+                    issues.push(format!("rwx {}", section));
+                }
+
+                if !issues.is_empty() {
+                    label = format!("{} [{}]", label, issues.join(", "));
+                }
+            }
+        }
+
+        // The root is always expanded
+        let collapsed =
+            options.collapse_system && depth > 0 && dll_type.map_or(false, filter::is_system);
+        if collapsed {
+            let count = count_dependencies(database, name);
+            match count {
+                0 => {}
+                1 => label = format!("{} [+1 dll]", label),
+                count => label = format!("{} [+{} dlls]", label, count),
+            }
+        }
+
+        let cycle = ancestors.iter().any(|ancestor| ancestor.eq_ignore_ascii_case(name));
+        if cycle {
+            label = format!("{} (cycle)", label);
+        }
+
+        if options.markdown {
+            label = format!("`{}`", label);
+        } else if options.color {
+            label = color::paint(&label, dll_type, database.is_missing(name));
+        }
+
+        writeln!(out, "{}", label)?;
+
+        if let Some(info) = info.filter(|_| !cycle && !collapsed) {
+            ancestors.push(name.to_owned());
+            let mut dependencies = info.dependencies();
+            if options.sort != Sort::Import {
+                dependencies.sort_by_key(|dll| sort_key(database, &dll.name, options.sort));
+            }
+            if let Some(visible) = &self.visible {
+                dependencies.retain(|dll| visible.contains(&dll.name.to_lowercase()));
+            }
+            for (index, dll) in dependencies.iter().enumerate() {
+                if depth < options.max_depth.unwrap_or(u32::MAX) {
+                    let edge = TreeEdge {
+                        importer_architecture: architecture.or(importer_architecture),
+                        imported_symbols: info.file.imported_symbol_count(&dll.name),
+                        kind: dll.kind,
+                        last_child: index == dependencies.len() - 1,
+                    };
+                    self.print(out, database, &dll.name, ancestors, Some(&edge))?;
+                }
+            }
+            ancestors.pop();
+        }
+        Ok(())
+    }
+
+    fn print_prefix(out: &mut dyn Write, depth: u32, last_child: bool) -> io::Result<()> {
+        if depth > 1 {
+            for _ in 0..depth - 1 {
+                write!(out, "│   ")?;
+            }
+        }
+        if depth > 0 {   
+            if last_child {
+                write!(out, "└── ")?;
+            }
+            else {
+                write!(out, "├── ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Dll the tree starts from, the file or the `root` it loads with the name it was loaded
+// with. The imports of every dll are kept, any of them can be printed as a tree.
+fn tree_root(database: &DllDatabase, file_name: &str, root: Option<&str>) -> Option<String> {
+    match root {
+        Some(root) => database
+            .get_all_dlls()
+            .into_iter()
+            .find(|name| name.eq_ignore_ascii_case(root)),
+        None => Some(file_name.to_owned()),
+    }
+}
+
+// Number of dlls loaded by `name`, directly or not
+fn count_dependencies(database: &DllDatabase, name: &str) -> usize {
+    let mut found = HashSet::from([name.to_lowercase()]);
+    let mut queue = vec![name.to_owned()];
+    while let Some(name) = queue.pop() {
+        let dependencies = database.get_dll_info(&name).map(|info| info.dependencies());
+        for dll in dependencies.into_iter().flatten() {
+            if found.insert(dll.name.to_lowercase()) {
+                queue.push(dll.name);
+            }
+        }
+    }
+    found.len() - 1
+}
+
+// Section of a dll in a list grouped by type: missing dlls first, then the dlls found but
+// not parsed, then each type
+fn list_group(database: &DllDatabase, dll: &str) -> (Option<DllType>, bool) {
+    let dll_type = database.get_dll_info(dll).map(|info| info.dll_type);
+    (dll_type, !database.is_missing(dll))
+}
+
+// Dlls of the list matching `filter`, in the order of `options.sort`. `walk_order` holds
+// the dlls in the order the walk found them.
+fn list_dlls(
+    database: &DllDatabase,
+    filter: &Filter,
+    options: &PrintOptions,
+    walk_order: &[String],
+) -> Vec<String> {
+    let mut dlls = database.get_all_dlls();
+    dlls.retain(|dll| filter.matches(database, dll));
+    if let Some(depth) = options.max_depth {
+        dlls.retain(|dll| database.get_depth(dll).map_or(false, |found| found <= depth));
+    }
+    match options.sort {
+        Sort::Import => dlls.sort_by_key(|dll| {
+            walk_order.iter().position(|name| name.eq_ignore_ascii_case(dll))
+        }),
+        _ => dlls.sort_by_key(|dll| sort_key(database, dll, options.sort)),
+    }
+    dlls
+}
+
+fn print_list(
+    out: &mut dyn Write,
+    database: &DllDatabase,
+    dlls: &[String],
+    options: &PrintOptions,
+) -> io::Result<()> {
+    let mut dlls: Vec<&String> = dlls.iter().collect();
+    if options.group_by_type {
+        dlls.sort_by_key(|dll| list_group(database, dll));
+    }
+
+    let mut current_group = None;
+    for dll in dlls {
+        let mut indent = "";
+        if options.group_by_type {
+            let group = list_group(database, dll);
+            if current_group != Some(group) {
+                if current_group.is_some() {
+                    writeln!(out)?;
+                }
+                match group {
+                    (Some(dll_type), _) => writeln!(out, "{}:", dll_type)?,
+                    (None, false) => writeln!(out, "missing:")?,
+                    (None, true) => writeln!(out, "unresolved:")?,
+                }
+                current_group = Some(group);
+            }
+            indent = "    ";
+        }
+
+        let mut label = dll_label(database, dll, options.absolute_path, options.show_version);
+
+        if options.show_symbol_counts {
+            let count = database.get_imported_symbol_count(dll);
+            if count > 0 {
+                label = format!("{} [{} functions imported]", label, count);
+            }
+        }
+
+        if options.timestamps {
+            let info = database
+                .get_dll_info(dll)
+                .filter(|info| info.has_headers());
+            if let Some(info) = info {
+                label = format!("{} [{}]", label, format_timestamp(&info.file));
+            }
+        }
+
+        if options.debug_info {
+            let pdb_info = database
+                .get_dll_info(dll)
+                .and_then(|info| info.file.pdb_info.as_ref());
+            if let Some(pdb_info) = pdb_info {
+                label = format!("{} (pdb: {}, {})", label, pdb_info.path, pdb_info.symbol_id());
+            }
+        }
+
+        if options.color {
+            let dll_type = database.get_dll_info(dll).map(|info| info.dll_type);
+            label = color::paint(&label, dll_type, database.is_missing(dll));
+        }
+
+        writeln!(out, "{}{}", indent, label)?;
+    }
+    Ok(())
+}
+
+fn print_chains(out: &mut dyn Write, report: &Report, max_depth: Option<u32>) -> io::Result<()> {
+    for chain in report.chains(max_depth) {
+        writeln!(out, "{}", chain.join(" → "))?;
+    }
+    Ok(())
+}
+
+fn print_chains_to(
+    out: &mut dyn Write,
+    report: &Report,
+    name: &str,
+    max_chains: usize,
+) -> io::Result<()> {
+    let chains = report.chains_to(name, max_chains);
+    if chains.is_empty() {
+        writeln!(out, "{} is not imported by {}", name, report.root)?;
+    }
+
+    for chain in chains {
+        writeln!(out, "{}", chain.join(" → "))?;
+    }
+    Ok(())
+}
+
+fn print_diff(out: &mut dyn Write, old: &Report, new: &Report) -> io::Result<()> {
+    for change in diff::compare(old, new) {
+        match change {
+            Change::Added(node) => writeln!(out, "+ {} ({})", node.name, diff::location(node))?,
+            Change::Removed(node) => {
+                writeln!(out, "- {} ({})", node.name, diff::location(node))?
+            }
+            Change::Changed(old, new) => {
+                let mut changes = Vec::new();
+                let (old_location, new_location) = (diff::location(old), diff::location(new));
+                if old_location != new_location {
+                    changes.push(format!("{} → {}", old_location, new_location));
+                }
+                if old.version != new.version {
+                    let version = |node: &Node| node.version.clone().unwrap_or("none".to_owned());
+                    changes.push(format!("version {} → {}", version(old), version(new)));
+                }
+                writeln!(out, "~ {}: {}", new.name, changes.join(", "))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_modules(
+    out: &mut dyn Write,
+    database: &DllDatabase,
+    process: &Process,
+    modules: &[Module],
+) -> io::Result<()> {
+    writeln!(out, "{} (pid {})", process.name, process.id)?;
+
+    let mut modules = modules.to_vec();
+    modules.sort_by_key(|module| module.name.to_lowercase());
+    for module in &modules {
+        let path = module.path.to_string_lossy();
+        match process::origin(database, module) {
+            Origin::Imported => writeln!(out, "    {}: {}", module.name, path)?,
+            Origin::Dynamic => writeln!(out, "    {}: {} [loaded at runtime]", module.name, path)?,
+            Origin::Unexpected(expected) => writeln!(
+                out,
+                "    {}: {} [expected {}]",
+                module.name,
+                path,
+                expected.to_string_lossy()
+            )?,
+        }
+    }
+    Ok(())
+}
+
+fn print_requirements(out: &mut dyn Write, requirements: &[Requirement]) -> io::Result<()> {
+    if requirements.is_empty() {
+        return writeln!(out, "No redistributable runtime is needed");
+    }
+
+    for requirement in requirements {
+        match requirement.architecture {
+            Some(architecture) => writeln!(out, "{} ({})", requirement.runtime.name, architecture)?,
+            None => writeln!(out, "{}", requirement.runtime.name)?,
+        }
+        writeln!(out, "    Dlls: {}", requirement.dlls.join(", "))?;
+        if requirement.app_local {
+            writeln!(out, "    Deployed with the application, no install is needed")?;
+        } else if let Some(note) = requirement.runtime.note {
+            writeln!(out, "    {}", note)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_minimum_version(
+    out: &mut dyn Write,
+    version: WindowsVersion,
+    requirements: &[windows_version::Requirement],
+) -> io::Result<()> {
+    writeln!(out, "Minimum version: {}", version)?;
+    for requirement in requirements {
+        writeln!(out, "    {}: {}", requirement.dll, requirement.reason)?;
+    }
+    Ok(())
+}
+
+fn print_stats(out: &mut dyn Write, stats: &Stats) -> io::Result<()> {
+    let count: usize = stats.types.iter().map(|(_, count)| count).sum();
+    writeln!(out, "Dlls: {}", count + stats.missing + stats.unresolved)?;
+    for (dll_type, count) in &stats.types {
+        writeln!(out, "    {}: {}", dll_type, count)?;
+    }
+    writeln!(out, "Missing: {}", stats.missing)?;
+    if stats.unresolved > 0 {
+        writeln!(out, "Unresolved: {}", stats.unresolved)?;
+    }
+    writeln!(out, "Maximum depth: {}", stats.max_depth())?;
+    writeln!(out, "Longest chain: {}", stats.longest_chain.join(" → "))?;
+    writeln!(out, "Total size: {} bytes", stats.total_size)?;
+    Ok(())
+}
+
+fn print_top(out: &mut dyn Write, entries: &[top::Entry], count: usize) -> io::Result<()> {
+    let count = if count == 0 { entries.len() } else { count.min(entries.len()) };
+    let rows: Vec<[String; 3]> = entries[..count]
+        .iter()
+        .map(|entry| {
+            [entry.file_size.to_string(), entry.image_size.to_string(), entry.name.clone()]
+        })
+        .collect();
+    let file_width = rows.iter().map(|row| row[0].len()).fold("File size".len(), usize::max);
+    let image_width = rows.iter().map(|row| row[1].len()).fold("Image size".len(), usize::max);
+
+    writeln!(
+        out,
+        "{:>file$}  {:>image$}  Name",
+        "File size",
+        "Image size",
+        file = file_width,
+        image = image_width
+    )?;
+    for row in rows {
+        writeln!(
+            out,
+            "{:>file$}  {:>image$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            file = file_width,
+            image = image_width
+        )?;
+    }
+
+    let (application, system) = top::totals(entries);
+    let total = top::Total::new(entries);
+    for (label, total) in [("Application", application), ("System", system), ("Total", total)] {
+        writeln!(
+            out,
+            "{}: {} dlls, {} bytes on disk, {} bytes in memory",
+            label, total.dlls, total.file_size, total.image_size
+        )?;
+    }
+    Ok(())
+}
+
+fn print_manifest(out: &mut dyn Write, manifest: &Manifest) -> io::Result<()> {
+    if let Some(level) = &manifest.execution_level {
+        writeln!(out, "Execution level: {}", level)?;
+    }
+    if let Some(ui_access) = &manifest.ui_access {
+        writeln!(out, "UI access: {}", ui_access)?;
+    }
+
+    if !manifest.dependent_assemblies.is_empty() {
+        writeln!(out, "Dependent assemblies:")?;
+        for assembly in &manifest.dependent_assemblies {
+            let details: Vec<&str> = [&assembly.version, &assembly.processor_architecture]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            match details.as_slice() {
+                [] => writeln!(out, "    {}", assembly.name)?,
+                details => writeln!(out, "    {} ({})", assembly.name, details.join(", "))?,
+            }
+        }
+    }
+
+    if !manifest.supported_os.is_empty() {
+        writeln!(out, "Supported OS:")?;
+        for id in &manifest.supported_os {
+            match manifest::supported_os_name(id) {
+                Some(name) => writeln!(out, "    {}", name)?,
+                None => writeln!(out, "    {}", id)?,
+            }
+        }
+    }
+
+    if !manifest.windows_settings.is_empty() {
+        writeln!(out, "Windows settings:")?;
+        for (name, value) in &manifest.windows_settings {
+            writeln!(out, "    {}: {}", name, value)?;
+        }
+    }
+
+    if !manifest.private_paths.is_empty() {
+        writeln!(out, "Private paths: {}", manifest.private_paths.join(";"))?;
+    }
+    Ok(())
+}
+
+fn print_cache_stats(out: &mut dyn Write, file: &Path, stats: &CacheStats) -> io::Result<()> {
+    writeln!(out, "Cache: {}", file.to_string_lossy())?;
+    writeln!(out, "Directories: {}", stats.directories)?;
+    writeln!(out, "Stale directories: {}", stats.stale_directories)?;
+    writeln!(out, "Files: {}", stats.files)?;
+    writeln!(out, "Size: {} bytes", stats.size)?;
+    Ok(())
+}
+
+fn print_dependents(
+    out: &mut dyn Write,
+    directory: &Path,
+    dll: &str,
+    dependents: &[(PathBuf, Vec<String>)],
+) -> io::Result<()> {
+    if dependents.is_empty() {
+        return writeln!(out, "No file under {} loads {}", directory.to_string_lossy(), dll);
+    }
+
+    for (binary, chain) in dependents {
+        let path = binary.strip_prefix(directory).unwrap_or(binary);
+        writeln!(out, "{}: {}", path.to_string_lossy(), chain.join(" → "))?;
+    }
+    Ok(())
+}
+
+// Returns whether one of the problems is fatal
+fn print_problems(
+    out: &mut dyn Write,
+    database: &DllDatabase,
+    root: &str,
+    policy: &Policy,
+    fatal: &[Problem],
+) -> io::Result<bool> {
+    let mut problems = Vec::new();
+    for name in database.get_all_dlls() {
+        if database.is_missing(&name) {
+            problems.push((Problem::Missing, format!("{} is not found", name)));
+        }
+
+        if let (Some(expected), Some(info)) = (
+            database.get_unexpected_location(&name),
+            database.get_dll_info(&name),
+        ) {
+            let message = format!(
+                "{} is loaded from {} instead of {}",
+                name,
+                info.path.to_string_lossy(),
+                expected.to_string_lossy()
+            );
+            problems.push((Problem::Location, message));
+        }
+    }
+
+    let mut mismatches = database.get_architecture_mismatches();
+    mismatches.sort();
+    for (importer, imported) in mismatches {
+        let message = format!(
+            "{} imports {} which targets a different architecture",
+            importer, imported
+        );
+        problems.push((Problem::Architecture, message));
+    }
+
+    for message in policy.violations(database, root) {
+        problems.push((Problem::Policy, message));
+    }
+
+    let mut failed = false;
+    for (problem, message) in problems {
+        if fatal.contains(&problem) {
+            failed = true;
+            writeln!(out, "error: {}", message)?;
+        } else {
+            writeln!(out, "warning: {}", message)?;
+        }
+    }
+    Ok(failed)
+}
+
+fn print_missing_symbols(out: &mut dyn Write, database: &DllDatabase) -> io::Result<()> {
+    for name in database.get_all_dlls() {
+        if let Some(info) = database.get_dll_info(&name) {
+            for dll in &info.file.imports {
+                if let Some(mut imported) = database.get_dll_info(&dll.name) {
+                    // Umbrella dlls have no export table, their host's is checked instead
+                    if imported.dll_type == DllType::Umbrella {
+                        let host = imported.api_set_host.as_ref();
+                        match host.and_then(|host| database.get_dll_info(host)) {
+                            Some(host) => imported = host,
+                            None => continue,
+                        }
+                    }
+
+                    // The exports of the dlls of an inventory are not known
+                    if imported.from_inventory {
+                        continue;
+                    }
+
+                    for symbol in &dll.symbols {
+                        if !imported.file.exports.contains(symbol) {
+                            writeln!(out, "{} -> {}: {}", name, dll.name, symbol)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_hijackable_dlls(out: &mut dyn Write, database: &DllDatabase) -> io::Result<()> {
+    let mut hijackable = match database.get_hijackable_dlls() {
+        Ok(hijackable) => hijackable,
+        Err(UnknownPermissions::NoSystemRoot) => {
+            let reason = "the permissions of the directories are unknown without --system-root";
+            return writeln!(out, "Cannot find the hijackable dlls, {}", reason);
+        }
+        Err(error) => return writeln!(out, "Cannot find the hijackable dlls, {}", error),
+    };
+
+    // Planting locations stay in search order
+    hijackable.sort_by_key(|(name, _)| name.to_lowercase());
+
+    for (name, directory) in hijackable {
+        match database.get_dll_info(name) {
+            Some(info) => writeln!(
+                out,
+                "{}: {} (loaded from {})",
+                name,
+                directory.to_string_lossy(),
+                info.path.to_string_lossy()
+            )?,
+            None => writeln!(out, "{}: {} (not found)", name, directory.to_string_lossy())?,
+        }
+    }
+    Ok(())
+}
+
+fn print_search_order(
+    out: &mut dyn Write,
+    search_path: &SearchPath,
+    kernel_mode: bool,
+    load_flags: u32,
+) -> io::Result<()> {
+    let locations = if kernel_mode {
+        writeln!(out, "Kernel loader search order")?;
+        search_path.kernel_search_order()
+    } else {
+        let safe_search = if search_path.is_safe_search_enabled() { "on" } else { "off" };
+        writeln!(out, "Safe dll search mode: {}", safe_search)?;
+
+        // Named like the --search-flags values
+        let load_flags = search_path.effective_load_flags(load_flags);
+        let search_flags: Vec<_> = SearchFlag::value_variants()
+            .iter()
+            .filter(|flag| load_flags & flag.load_flag() != 0)
+            .filter_map(|flag| Some(flag.to_possible_value()?.get_name().to_owned()))
+            .collect();
+        if search_flags.is_empty() {
+            writeln!(out, "Search flags: none, standard search order")?;
+        } else {
+            writeln!(out, "Search flags: {}", search_flags.join(", "))?;
+        }
+
+        search_path.search_order(load_flags)
+    };
+
+    let mut index = 1;
+    if !kernel_mode {
+        writeln!(
+            out,
+            "{}. activation context ({} dlls)",
+            index,
+            search_path.side_by_side_count()
+        )?;
+        writeln!(out, "{}. known dlls ({} dlls)", index + 1, search_path.known_dll_count())?;
+        index += 2;
+    }
+
+    for location in locations {
+        writeln!(
+            out,
+            "{}. {} [{}]",
+            index,
+            location.directory.to_string_lossy(),
+            location.description
+        )?;
+        index += 1;
+    }
+    Ok(())
+}
+
+fn print_explanation(out: &mut dyn Write, database: &DllDatabase, name: &str) -> io::Result<()> {
+    let steps = database.explain(name);
+    for (index, step) in steps.iter().enumerate() {
+        let location = match step.directory {
+            Some(directory) => format!("{} [{}]", directory.to_string_lossy(), step.description),
+            None => step.description.to_owned(),
+        };
+
+        let outcome = match &step.outcome {
+            SearchOutcome::Found(_) if step.dll_type == DllType::Umbrella => {
+                match database.search_path().resolve_api_set(name) {
+                    Some(host) => format!("implemented by {}", host),
+                    None => "no host in the api set schema".to_owned(),
+                }
+            }
+            SearchOutcome::Found(path) => format!("found {}", path.to_string_lossy()),
+            SearchOutcome::NotPresent => "not present".to_owned(),
+            SearchOutcome::WrongArchitecture(path, architecture) => {
+                format!("skipped {}, built for {}", path.to_string_lossy(), architecture)
+            }
+            SearchOutcome::NotSearched(path) => format!(
+                "{} is not searched with the search flags of the importer",
+                path.to_string_lossy()
+            ),
+        };
+
+        writeln!(out, "{}. {}: {}", index + 1, location, outcome)?;
+    }
+
+    match steps.last() {
+        Some(step) if matches!(step.outcome, SearchOutcome::Found(_)) => {
+            writeln!(out, "Resolved by the {} rule ({})", step.description, step.dll_type)?
+        }
+        _ => writeln!(out, "{} is not found", name)?,
+    }
+    Ok(())
+}
+
+fn print_exporters(out: &mut dyn Write, database: &DllDatabase, symbol: &str) -> io::Result<()> {
+    let exporters = database.find_exporters(symbol);
+    if exporters.is_empty() {
+        writeln!(out, "{} is not exported by the dependencies", symbol)?;
+    }
+
+    for (name, info, export) in exporters {
+        let path = info.path.to_string_lossy();
+        let mut line = format!("{} ({}): ordinal {}", name, path, export.ordinal);
+        if let Some(forwarder) = &export.forwarder {
+            line = format!("{}, forwarded to {}", line, forwarder);
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn copy_dependencies(
+    out: &mut dyn Write,
+    database: &DllDatabase,
+    root: &str,
+    directory: &Path,
+    dry_run: bool,
+    overwrite: Overwrite,
+) -> io::Result<()> {
+    let deployments = deploy::plan(database, root, directory, overwrite);
+    if !dry_run && deployments.iter().any(|deployment| !deployment.skipped) {
+        if let Err(err) = std::fs::create_dir_all(directory) {
+            error!("Failed to create {}: {}", directory.to_string_lossy(), err);
+            return Ok(());
+        }
+    }
+
+    for deployment in deployments {
+        let source = deployment.source.to_string_lossy();
+        let destination = deployment.destination.to_string_lossy();
+        if deployment.skipped {
+            writeln!(out, "Kept {}", destination)?;
+        } else if dry_run {
+            writeln!(out, "Would copy {} to {}", source, destination)?;
+        } else {
+            match std::fs::copy(&deployment.source, &deployment.destination) {
+                Ok(_) => writeln!(out, "Copied {} to {}", source, destination)?,
+                Err(err) => error!("Failed to copy {} to {}: {}", source, destination, err),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_version_info(
+    out: &mut dyn Write,
+    database: &DllDatabase,
+    names: &[String],
+) -> io::Result<()> {
+    for name in names {
+        let info = match database.get_dll_info(name) {
+            Some(info) => info,
+            None => continue,
+        };
+        writeln!(out, "{}: {}", name, info.path.to_string_lossy())?;
+
+        let version_info = match &info.file.version_info {
+            Some(version_info) => version_info,
+            None => {
+                writeln!(out, "    No version resource")?;
+                continue;
+            }
+        };
+
+        if let Some(version) = version_info.file_version_string() {
+            writeln!(out, "    File version: {}", version)?;
+        }
+        if let Some(version) = version_info.product_version_string() {
+            writeln!(out, "    Product version: {}", version)?;
+        }
+        if let Some(file_type) = version_info.file_type_name() {
+            writeln!(out, "    File type: {}", file_type)?;
+        }
+        let flags = version_info.file_flag_names();
+        if !flags.is_empty() {
+            writeln!(out, "    Flags: {}", flags.join(", "))?;
+        }
+        if !version_info.translations.is_empty() {
+            let translations: Vec<String> = version_info
+                .translations
+                .iter()
+                .map(|(language, code_page)| format!("{:04x} ({})", language, code_page))
+                .collect();
+            writeln!(out, "    Translations: {}", translations.join(", "))?;
+        }
+
+        for table in &version_info.string_tables {
+            match (table.language_id(), table.code_page()) {
+                (Some(language), Some(code_page)) => writeln!(
+                    out,
+                    "    Language {:04x}, code page {}:",
+                    language, code_page
+                )?,
+                _ => writeln!(out, "    {}:", table.language)?,
+            }
+            for (key, value) in &table.strings {
+                writeln!(out, "        {}: {}", key, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_known_dlls(out: &mut dyn Write, search_path: &SearchPath) -> io::Result<()> {
+    for (name, path, importer) in search_path.known_dlls() {
+        match importer {
+            Some(importer) => writeln!(
+                out,
+                "{}: {} (imported by {})",
+                name,
+                path.to_string_lossy(),
+                importer
+            )?,
+            None => writeln!(out, "{}: {}", name, path.to_string_lossy())?,
+        }
+    }
+    Ok(())
+}
+
+// Returns whether a file is not trusted. Off Windows no signature is verified.
+fn print_signatures(out: &mut dyn Write, database: &DllDatabase) -> io::Result<bool> {
+    let mut unsigned = Vec::new();
+    let mut untrusted = Vec::new();
+    for name in database.get_all_dlls() {
+        let info = match database.get_dll_info(&name) {
+            Some(info) if info.has_headers() => info,
+            _ => continue,
+        };
+        let status = match info.signature {
+            Some(status) => status,
+            None => continue,
+        };
+
+        match status.reason() {
+            Some(reason) => writeln!(out, "{}: {}, {}", name, status, reason)?,
+            None => writeln!(out, "{}: {}", name, status)?,
+        }
+        if let Some(signer) = &info.signer {
+            writeln!(out, "    Signer: {}", signer)?;
+        }
+
+        match status {
+            SignatureStatus::Unsigned => unsigned.push(name),
+            status if !status.is_trusted() => untrusted.push(name),
+            _ => {}
+        }
+    }
+
+    if !unsigned.is_empty() {
+        writeln!(out, "Unsigned: {}", unsigned.join(", "))?;
+    }
+    if !untrusted.is_empty() {
+        writeln!(out, "Untrusted: {}", untrusted.join(", "))?;
+    }
+    Ok(!unsigned.is_empty() || !untrusted.is_empty())
+}
+
+fn print_info(out: &mut dyn Write, database: &DllDatabase) -> io::Result<()> {
+    for name in database.get_all_dlls() {
+        if let Some(info) = database.get_dll_info(&name) {
+            match info.architecture() {
+                Some(architecture) => writeln!(out, "{} ({})", name, architecture)?,
+                None => writeln!(out, "{}", name)?,
+            }
+
+            if !info.has_headers() {
+                continue;
+            }
+
+            if let Some(subsystem) = info.file.subsystem {
+                writeln!(out, "    Subsystem: {}", subsystem)?;
+            }
+            writeln!(
+                out,
+                "    Image type: {}",
+                if info.file.is_dll() { "dll" } else { "executable" }
+            )?;
+            writeln!(out, "    Image base: 0x{:x}", info.file.image_base)?;
+            if info.file.relocations_stripped() {
+                if info.file.needs_relocations() {
+                    let relocations = "stripped, loading fails if the image is rebased";
+                    writeln!(out, "    Relocations: {}", relocations)?;
+                } else {
+                    writeln!(out, "    Relocations: stripped")?;
+                }
+            }
+            writeln!(out, "    Entry point: 0x{:x}", info.file.entry_point)?;
+            writeln!(out, "    Timestamp: {}", format_timestamp(&info.file))?;
+
+            if info.file.is_resource_only() {
+                writeln!(out, "    Resource only: no code and no imports")?;
+            } else {
+                let mitigations: Vec<String> = info
+                    .file
+                    .mitigations()
+                    .iter()
+                    .map(|(mitigation, enabled)| {
+                        format!("{} {}", mitigation, if *enabled { "on" } else { "off" })
+                    })
+                    .collect();
+                writeln!(out, "    Mitigations: {}", mitigations.join(", "))?;
+            }
+
+            if !info.file.writable_executable_sections.is_empty() {
+                writeln!(
+                    out,
+                    "    Writable and executable sections: {}",
+                    info.file.writable_executable_sections.join(", ")
+                )?;
+            }
+
+            if !info.file.bound_imports.is_empty() {
+                writeln!(out, "    Bound imports:")?;
+                for bound in &info.file.bound_imports {
+                    print_bound_import(out, database, &bound.name, bound.time_date_stamp)?;
+                    for forwarder in &bound.forwarders {
+                        let timestamp = forwarder.time_date_stamp;
+                        print_bound_import(out, database, &forwarder.name, timestamp)?;
+                    }
+                }
+            }
+
+            if let Some(rich_header) = &info.file.rich_header {
+                writeln!(out, "    Toolchain:")?;
+                for entry in &rich_header.entries {
+                    writeln!(out, "        {}", entry)?;
+                }
+            }
+
+            if !info.file.warnings.is_empty() {
+                writeln!(out, "    Parse warnings:")?;
+                for warning in &info.file.warnings {
+                    writeln!(out, "        {}", warning)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// The loader only uses the addresses bound to a dll when the dll loaded has the timestamp
+// recorded by the binding
+fn print_bound_import(
+    out: &mut dyn Write,
+    database: &DllDatabase,
+    name: &str,
+    timestamp: u32,
+) -> io::Result<()> {
+    let loaded = database.get_dll_info(name).filter(|info| info.has_headers());
+    match loaded.map(|info| info.file.timestamp) {
+        Some(loaded) if loaded != timestamp => writeln!(
+            out,
+            "        {} {:08x}, stale: the dll loaded has timestamp {:08x}",
+            name, timestamp, loaded
+        ),
+        _ => writeln!(out, "        {} {:08x}", name, timestamp),
+    }
+}
+
+// Database resolving the dependencies of `file` with the loader settings of the command
+// line, the manifest of the file and its configuration files
+fn open_database(
+    args: &Arguments,
+    file: &Path,
+    current_directory: &Path,
+    environment: &[(String, String)],
+    cache_directory: Option<&Path>,
+) -> (DllDatabase, Option<pe::File>) {
+    let file = &long_path::absolute(file, current_directory);
+    let root = read_root(file);
+    let mut database = new_database(
+        args,
+        file,
+        root.as_ref(),
+        current_directory,
+        environment,
+        cache_directory,
+    );
+    add_application_settings(&mut database, file, root.as_ref());
+    (database, root)
+}
+
+fn read_root(file: &Path) -> Option<pe::File> {
+    file_provider::map_file(file)
+        .ok()
+        .and_then(|data| pe::File::parse_from(&data).ok())
+}
+
+// The shell only applies App Paths to the executables it starts
+fn app_paths_executable(args: &Arguments, file: &Path, root: Option<&pe::File>) -> Option<String> {
+    let is_executable = root.map_or(false, |root| !root.is_dll());
+    (args.app_paths && is_executable)
+        .then(|| file.file_name().unwrap().to_string_lossy().into_owned())
+}
+
+// Database with the loader settings of the command line, for the application `file`
+fn new_database(
+    args: &Arguments,
+    file: &Path,
+    root: Option<&pe::File>,
+    current_directory: &Path,
+    environment: &[(String, String)],
+    cache_directory: Option<&Path>,
+) -> DllDatabase {
+    let base_directory = file.parent().unwrap_or(current_directory);
+    let architecture = root.and_then(|root| root.architecture);
+    let app_paths_executable = app_paths_executable(args, file, root);
+
+    // Relative paths on the command line stay relative to the shell directory
+    let application_current_directory = match &args.cwd {
+        Some(cwd) => long_path::absolute(cwd, current_directory),
+        None => current_directory.to_owned(),
+    };
+
+    let inventory = args.inventory.as_deref().map(|path| match Inventory::load(path) {
+        Ok(inventory) => inventory,
+        Err(err) => {
+            error!("Failed to read {}: {}", path.to_string_lossy(), err);
+            std::process::exit(1);
+        }
+    });
+
+    let search_path = SearchPath::builder(base_directory)
+        .current_directory(&application_current_directory)
+        .architecture(architecture)
+        .app_paths_executable(app_paths_executable.as_deref())
+        .system_root(args.system_root.as_deref())
+        .environment(environment)
+        .inventory(inventory)
+        .cache_directory(cache_directory)
+        .build()
+        .expect("Failed to initialize the search path");
+    let mut database = DllDatabase::new(search_path);
+
+    match args.safe_search {
+        SafeSearch::On => database.set_safe_search_enabled(true),
+        SafeSearch::Off => database.set_safe_search_enabled(false),
+        SafeSearch::Auto => {}
+    }
+
+    for directory in &args.add_dir {
+        if let Err(err) = database.add_user_directory(directory) {
+            warn!("Failed to read {}: {}", directory.to_string_lossy(), err);
+        }
+    }
+
+    // Added directories are only searched once SetDefaultDllDirectories was called
+    let search_flags = if args.search_flags.is_empty() && !args.add_dir.is_empty() {
+        vec![SearchFlag::DefaultDirs]
+    } else {
+        args.search_flags.clone()
+    };
+    let default_load_flags = search_flags
+        .iter()
+        .fold(0, |load_flags, flag| load_flags | flag.load_flag());
+    database.set_default_load_flags(default_load_flags);
+
+    database
+}
+
+// Settings of the application `file`: its kind, its manifest and its configuration files
+fn add_application_settings(database: &mut DllDatabase, file: &Path, root: Option<&pe::File>) {
+    let base_directory = file.parent().unwrap();
+
+    if root.map_or(false, |root| root.is_kernel_image()) {
+        info!("Kernel image, resolving imports like the kernel loader");
+        database.set_kernel_mode(true);
+    }
+
+    if let Some(text) = root.and_then(|root| root.manifest.as_ref()) {
+        match Manifest::parse(text) {
+            Ok(manifest) => {
+                database.add_side_by_side_assemblies(&manifest.dependent_assemblies);
+                add_private_paths(database, base_directory, &manifest.private_paths);
+            }
+            Err(err) => warn!("Failed to parse the manifest: {}", err),
+        }
+    }
+
+    match Config::load(base_directory) {
+        Ok(Some(config)) => {
+            for name in &config.ignore {
+                database.ignore_dll(name);
+            }
+            for (name, directory) in &config.locations {
+                database.expect_directory(name, directory);
+            }
+        }
+        Ok(None) => {}
+        Err(err) => warn!("Failed to read {}: {}", config::CONFIG_FILE_NAME, err),
+    }
+
+    // Managed applications declare their private paths in app.exe.config
+    let mut config_path = file.as_os_str().to_owned();
+    config_path.push(".config");
+    if let Ok(text) = std::fs::read_to_string(&config_path) {
+        match Manifest::parse(text.trim_start_matches('\u{feff}')) {
+            Ok(config) => add_private_paths(database, base_directory, &config.private_paths),
+            Err(err) => warn!("Failed to parse {}: {}", config_path.to_string_lossy(), err),
+        }
+    }
+}
+
+// Walks the dependencies of `file` and prints their problems like the check command, returns
+// whether one of them is fatal
+fn run_check(
+    out: &mut dyn Write,
+    args: &Arguments,
+    file: &Path,
+    current_directory: &Path,
+    environment: &[(String, String)],
+    cache_directory: Option<&Path>,
+) -> io::Result<bool> {
+    let (fail_on, policy) = match &args.command {
+        Commands::Check { fail_on, policy, .. } => (fail_on, policy.as_deref()),
+        _ => unreachable!(),
+    };
+
+    let (mut database, _) =
+        open_database(args, file, current_directory, environment, cache_directory);
+    let file_name = file.file_name().unwrap().to_string_lossy().into_owned();
+    database.walk(&file_name, |_| {});
+
+    let policy = load_policy(file, current_directory, policy);
+    print_problems(out, &database, &file_name, &policy, fail_on)
+}
+
+// Policy of the application `file` from its .dllwalk.toml file, extended by `policy_file`
+fn load_policy(file: &Path, current_directory: &Path, policy_file: Option<&Path>) -> Policy {
+    let file = long_path::absolute(file, current_directory);
+    let directory = file.parent().unwrap_or(current_directory);
+
+    // Errors in .dllwalk.toml were reported when opening the database
+    let mut policy = match Config::load(directory) {
+        Ok(Some(config)) => config.policy,
+        _ => Policy::default(),
+    };
+    if let Some(path) = policy_file {
+        match Policy::load(path) {
+            Ok(other) => policy.extend(other),
+            Err(err) => {
+                error!("Failed to read {}: {}", path.to_string_lossy(), err);
+                std::process::exit(1);
+            }
+        }
+    }
+    policy
+}
+
+/// A file given with `-o`, created on the first write or flush so that a run failing
+/// before that leaves the output of the previous one alone
+struct OutputFile {
+    path: PathBuf,
+    writer: Option<io::BufWriter<std::fs::File>>,
+}
+
+impl OutputFile {
+    fn new(path: &Path) -> Self {
+        OutputFile {
+            path: path.to_owned(),
+            writer: None,
+        }
+    }
+
+    fn writer(&mut self) -> io::Result<&mut io::BufWriter<std::fs::File>> {
+        if self.writer.is_none() {
+            let file = std::fs::File::create(&self.path).map_err(|err| {
+                let path = self.path.to_string_lossy();
+                io::Error::new(err.kind(), format!("cannot create {}: {}", path, err))
+            })?;
+            self.writer = Some(io::BufWriter::new(file));
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer()?.flush()
+    }
+}
+
+// Like a closed pipe, an output file that cannot be written ends the run
+fn exit_on_output_error(err: io::Error) {
+    error!("Failed to write the output: {}", err);
+    std::process::exit(1);
+}
+
+fn add_private_paths(database: &mut DllDatabase, base_directory: &Path, private_paths: &[String]) {
+    for private_path in private_paths {
+        // Either separator may be used, paths outside of the application directory are
+        // ignored by the loader
+        let relative: PathBuf = private_path.split(['\\', '/']).collect();
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            warn!("Ignoring private path outside of the application directory: {}", private_path);
+            continue;
+        }
+
+        let directory = base_directory.join(relative);
+        if let Err(err) = database.add_private_directory(&directory) {
+            warn!("Failed to read {}: {}", directory.to_string_lossy(), err);
+        }
+    }
+}
+
+// Options of the configuration files are only used when they are not on the command line
+fn apply_defaults(args: &mut Arguments, matches: &ArgMatches, defaults: Defaults) {
+    fn parse<T: ArgEnum>(option: &str, value: &str) -> Option<T> {
+        match T::from_str(value, true) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("Invalid {} in the configuration: {}", option, value);
+                None
+            }
+        }
+    }
+    let is_given = |matches: &ArgMatches, id: &str| {
+        matches.value_source(id) == Some(ValueSource::CommandLine)
+    };
+
+    if !is_given(matches, "color") {
+        let color = defaults.color.and_then(|color| parse("color", &color));
+        args.color = color.unwrap_or(args.color);
+    }
+    if !is_given(matches, "add-dir") {
+        args.add_dir = defaults.add_dir.unwrap_or_default();
+    }
+
+    let subcommand = match matches.subcommand() {
+        Some((_, subcommand)) => subcommand,
+        None => return,
+    };
+    if let Commands::Tree {
+        format,
+        exclude_system,
+        only_type,
+        filter,
+        ..
+    }
+    | Commands::List {
+        format,
+        exclude_system,
+        only_type,
+        filter,
+        ..
+    } = &mut args.command
+    {
+        if !is_given(subcommand, "format") {
+            let default = defaults.format.and_then(|value| parse("format", &value));
+            *format = default.unwrap_or(*format);
+        }
+        if !is_given(subcommand, "exclude-system") {
+            *exclude_system = defaults.exclude_system.unwrap_or(false);
+        }
+        if !is_given(subcommand, "only-type") {
+            let types = defaults.only_type.unwrap_or_default();
+            *only_type = types.iter().filter_map(|value| parse("type", value)).collect();
+        }
+        if !is_given(subcommand, "filter") {
+            let default = defaults.filter.and_then(|value| match parse_filter(&value) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    warn!("Invalid filter in the configuration: {}", err);
+                    None
+                }
+            });
+            *filter = default;
+        }
+    }
+}
+
+fn main() {
+    let matches = Arguments::command().get_matches();
+    let mut args = Arguments::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    // Quiet runs only log errors, whatever RUST_LOG enables
+    let mut logger = env_logger::Builder::from_default_env();
+    if args.quiet {
+        logger.filter_level(log::LevelFilter::Error);
+    }
+    logger.init();
+
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(OutputFile::new(path)),
+        None => Box::new(io::stdout().lock()),
+    };
+    let out = output.as_mut();
+
+    let current_directory = std::env::current_dir().expect("Failed to get current directory");
+
+    match Defaults::load(&current_directory) {
+        Ok(defaults) => apply_defaults(&mut args, &matches, defaults),
+        Err(err) => warn!("Failed to read the configuration: {}", err),
+    }
+
+    let mut environment = args.env.clone();
+    if let Some(path) = &args.path {
+        environment.push(("PATH".to_owned(), path.clone()));
+    }
+
+    let cache_directory = if args.no_cache {
+        None
+    } else {
+        directory_cache::default_directory()
+    };
+
+    let file = match &args.command {
+        Commands::Tree { file, ..} => file,
+        Commands::List { file, ..} => file,
+        Commands::Check { file, watch: true, .. } => {
+            let absolute = long_path::absolute(file, &current_directory);
+            let directory = absolute.parent().unwrap_or(&current_directory);
+            watch::run(directory, || {
+                let cache_directory = cache_directory.as_deref();
+                run_check(out, &args, file, &current_directory, &environment, cache_directory)
+                    .and_then(|failed| {
+                        if !args.quiet {
+                            let status = if failed { "failed" } else { "passed" };
+                            writeln!(out, "Check {}, waiting for changes", status)?;
+                        }
+                        out.flush()
+                    })
+                    .unwrap_or_else(exit_on_output_error);
+            })
+        }
+        Commands::Check { file, .. } => file,
+        Commands::CheckSymbols { file } => file,
+        Commands::SignCheck { file } => {
+            if !cfg!(windows) {
+                error!("Signatures can only be verified on Windows");
+                std::process::exit(1);
+            }
+            file
+        }
+        Commands::Info { file } => file,
+        Commands::VersionInfo { file, .. } => file,
+        Commands::Hijack { file } => file,
+        Commands::SearchOrder { file } => file,
+        Commands::Report { file, .. } => file,
+        Commands::Explain { file, .. } => file,
+        Commands::Why { file, .. } => file,
+        Commands::FindSymbol { file, .. } => file,
+        Commands::KnownDlls { file } => file,
+        Commands::CopyDeps { file, .. } => file,
+        Commands::RuntimeRequirements { file } => file,
+        Commands::MinWindowsVersion { file } => file,
+        Commands::Stats { file } => file,
+        Commands::Top { file, .. } => file,
+        Commands::Tui { file } => file,
+        Commands::Diff { old, new } => {
+            let open = |file: &Path| {
+                let (mut database, _) = open_database(
+                    &args,
+                    file,
+                    &current_directory,
+                    &environment,
+                    cache_directory.as_deref(),
+                );
+                let file_name = file.file_name().unwrap().to_string_lossy().into_owned();
+                database.walk(&file_name, |_| {});
+                Report::new(&database, &file_name)
+            };
+            let (old, new) = (open(old), open(new));
+            print_diff(out, &old, &new)
+                .and_then(|_| out.flush())
+                .unwrap_or_else(exit_on_output_error);
+            return;
+        }
+        Commands::Process { target } => {
+            let processes = process::find_processes(target).expect("Failed to list the processes");
+            let process = match processes.as_slice() {
+                [process] => process,
+                [] => {
+                    error!("No process {} is running", target);
+                    std::process::exit(1);
+                }
+                _ => {
+                    let ids: Vec<String> =
+                        processes.iter().map(|process| process.id.to_string()).collect();
+                    error!("Several processes are named {}: {}", target, ids.join(", "));
+                    std::process::exit(1);
+                }
+            };
+
+            // The executable is the first module
+            let modules =
+                process::loaded_modules(process.id).expect("Failed to list the loaded modules");
+            let executable = match modules.first() {
+                Some(module) => module.path.clone(),
+                None => {
+                    error!("No module is loaded in process {}", process.id);
+                    std::process::exit(1);
+                }
+            };
+
+            let (mut database, _) = open_database(
+                &args,
+                &executable,
+                &current_directory,
+                &environment,
+                cache_directory.as_deref(),
+            );
+            let file_name = executable.file_name().unwrap().to_string_lossy().into_owned();
+            database.walk(&file_name, |_| {});
+            print_modules(out, &database, process, &modules[1..])
+                .and_then(|_| out.flush())
+                .unwrap_or_else(exit_on_output_error);
+            return;
+        }
+        Commands::Rdeps { directory, dll } => {
+            // The search path is built once for each architecture, and for each executable
+            // when App Paths apply, only the application directory changes between binaries
+            let mut databases = HashMap::new();
+            let mut dependents = Vec::new();
+            for binary in rdeps::find_binaries(directory) {
+                let file = long_path::absolute(&binary, &current_directory);
+                let root = read_root(&file);
+                let key = (
+                    root.as_ref().and_then(|root| root.architecture).map(Architecture::machine),
+                    app_paths_executable(&args, &file, root.as_ref()),
+                );
+                let database = match databases.entry(key) {
+                    Entry::Occupied(entry) => {
+                        let database: &mut DllDatabase = entry.into_mut();
+                        let base_directory = file.parent().unwrap();
+                        if let Err(err) = database.set_base_directory(base_directory) {
+                            warn!("Failed to read {}: {}", base_directory.to_string_lossy(), err);
+                            continue;
+                        }
+                        database
+                    }
+                    Entry::Vacant(entry) => entry.insert(new_database(
+                        &args,
+                        &file,
+                        root.as_ref(),
+                        &current_directory,
+                        &environment,
+                        cache_directory.as_deref(),
+                    )),
+                };
+                add_application_settings(database, &file, root.as_ref());
+                let file_name = binary.file_name().unwrap().to_string_lossy().into_owned();
+                database.walk(&file_name, |_| {});
+
+                // The dll itself is not one of its dependents
+                let report = Report::new(database, &file_name);
+                let chain = report.shortest_chain(dll).filter(|chain| chain.len() > 1);
+                if let Some(chain) = chain {
+                    let chain = chain.iter().map(|name| name.to_string()).collect();
+                    dependents.push((binary, chain));
+                }
+            }
+            print_dependents(out, directory, dll, &dependents)
+                .and_then(|_| out.flush())
+                .unwrap_or_else(exit_on_output_error);
+            return;
+        }
+        Commands::Cache { action } => {
+            // --no-cache only stops the other commands from using the cache
+            let directory = match directory_cache::default_directory() {
+                Some(directory) => directory,
+                None => {
+                    error!("No cache directory, the home directory is unknown");
+                    std::process::exit(1);
+                }
+            };
+            let file = directory_cache::cache_file(&directory);
+            match action {
+                CacheAction::Stats => {
+                    let stats = DirectoryCache::load(Some(&directory)).stats();
+                    print_cache_stats(out, &file, &stats)
+                }
+                CacheAction::Clear => match directory_cache::clear(&directory) {
+                    Ok(true) => writeln!(out, "Deleted {}", file.to_string_lossy()),
+                    Ok(false) => writeln!(out, "No cache to delete"),
+                    Err(err) => {
+                        error!("Failed to delete {}: {}", file.to_string_lossy(), err);
+                        std::process::exit(1);
+                    }
+                },
+                CacheAction::Path => writeln!(out, "{}", directory.to_string_lossy()),
+            }
+            .and_then(|_| out.flush())
+            .unwrap_or_else(exit_on_output_error);
+            return;
+        }
+        Commands::Manifest { file, raw } => {
+            let root = file_provider::map_file(&long_path::absolute(file, &current_directory))
+                .ok()
+                .and_then(|data| pe::File::parse_from(&data).ok());
+            let text = match root.and_then(|root| root.manifest) {
+                Some(text) => text,
+                None => {
+                    error!("No manifest is embedded in {}", file.to_string_lossy());
+                    std::process::exit(1);
+                }
+            };
+
+            if *raw {
+                writeln!(out, "{}", text.trim_end())
+            } else {
+                match Manifest::parse(&text) {
+                    Ok(manifest) => print_manifest(out, &manifest),
+                    Err(err) => {
+                        error!("Failed to parse the manifest: {}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            .and_then(|_| out.flush())
+            .unwrap_or_else(exit_on_output_error);
+            return;
+        }
+        Commands::Snapshot { file } => {
+            let mut search_path = SearchPath::builder(&current_directory)
+                .system_root(args.system_root.as_deref())
+                .environment(&environment)
+                .cache_directory(cache_directory.as_deref())
+                .build()
+                .expect("Failed to initialize the search path");
+            let inventory = Inventory::capture(&search_path);
+            search_path.save_directory_cache();
+            let saved = match file {
+                Some(file) => inventory.save(OutputFile::new(file)),
+                None => inventory.save(&mut *out),
+            };
+            if let Err(err) = saved {
+                error!("Failed to save the snapshot: {}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+    };
+
+    let (mut database, root) =
+        open_database(&args, file, &current_directory, &environment, cache_directory.as_deref());
+    let file_name = file.file_name().unwrap().to_string_lossy().into_owned();
+
+    // Records are printed as the walk resolves the dlls, without waiting for it to end
+    let ndjson = matches!(
+        args.command,
+        Commands::Tree { format: Format::Ndjson, .. }
+            | Commands::List { format: Format::Ndjson, .. }
+    );
+    let mut walk_order = Vec::new();
+    database.walk(&file_name, |event| {
+        if !matches!(event, WalkEvent::EdgeVisited { .. }) {
+            walk_order.push(event.name().to_owned());
+        }
+        if ndjson {
+            let record = Record::of_event(&event);
+            writeln!(out, "{}", record.to_json()).unwrap_or_else(exit_on_output_error);
+        }
+    });
+
+    let (verify_signatures, verify_checksums, hash, shadowed) = match &args.command {
+        Commands::Tree {
+            verify_signatures,
+            verify_checksums,
+            hash,
+            shadowed,
+            ..
+        }
+        | Commands::List {
+            verify_signatures,
+            verify_checksums,
+            hash,
+            shadowed,
+            ..
+        } => (*verify_signatures, *verify_checksums, *hash, *shadowed),
+        Commands::SignCheck { .. } => (true, false, None, false),
+        _ => (false, false, None, false),
+    };
+
+    if verify_signatures {
+        database.verify_signatures();
+    }
+
+    if verify_checksums {
+        database.verify_checksums();
+    }
+
+    if hash == Some(HashAlgorithm::Sha256) {
+        database.compute_hashes();
+    }
+
+    if shadowed {
+        database.find_shadowed_dlls();
+    }
+
+    // The commands may exit without dropping the database
+    database.save_directory_cache();
+
+    for (importer, imported) in database.get_architecture_mismatches() {
+        warn!("{} imports {} which targets a different architecture", importer, imported);
+    }
+
+    for cycle in Report::new(&database, &file_name).cycles {
+        warn!("Import cycle: {}", cycle.join(" → "));
+    }
+
+    for (importer, imported) in database.get_executable_imports() {
+        warn!("{} imports {} which is an executable, not a dll", importer, imported);
+    }
+
+    for name in database.get_all_dlls() {
+        if let (Some(expected), Some(info)) = (
+            database.get_unexpected_location(&name),
+            database.get_dll_info(&name),
+        ) {
+            warn!(
+                "{} is loaded from {} instead of {}",
+                name,
+                info.path.to_string_lossy(),
+                expected.to_string_lossy()
+            );
+        }
+    }
+
+    let color = match args.color {
+        ColorChoice::Auto => args.output.is_none() && color::is_supported(),
+        ColorChoice::Always => {
+            color::enable();
+            true
+        }
+        ColorChoice::Never => false,
+    };
+
+    let options = PrintOptions::new(&args.command, color);
+    let mut failed = false;
+    let result = match args.command {
+        Commands::Tree { format: Format::Json, .. }
+        | Commands::List { format: Format::Json, .. } => {
+            writeln!(out, "{}", Report::new(&database, &file_name).to_json())
+        },
+        Commands::Tree { format: Format::Ndjson, .. }
+        | Commands::List { format: Format::Ndjson, .. } => Ok(()),
+        Commands::Tree { format: Format::Table, .. }
+        | Commands::List { format: Format::Table, .. } => {
+            write!(out, "{}", Report::new(&database, &file_name).to_table(false))
+        },
+        Commands::List { format: Format::Markdown, .. } => {
+            write!(out, "{}", Report::new(&database, &file_name).to_table(true))
+        },
+        Commands::Tree { format: Format::Xml, .. }
+        | Commands::List { format: Format::Xml, .. } => {
+            write!(out, "{}", Report::new(&database, &file_name).to_xml())
+        },
+        Commands::Tree { format: Format::Dot, .. }
+        | Commands::List { format: Format::Dot, .. } => {
+            write!(out, "{}", Report::new(&database, &file_name).to_dot())
+        },
+        Commands::Tree { format: Format::Graphml, .. }
+        | Commands::List { format: Format::Graphml, .. } => {
+            write!(out, "{}", Report::new(&database, &file_name).to_graphml())
+        },
+        Commands::Tree { format: Format::Spdx, .. }
+        | Commands::List { format: Format::Spdx, .. } => {
+            let created = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            writeln!(out, "{}", Report::new(&database, &file_name).to_spdx(created))
+        },
+        Commands::Tree { file: argument, format: Format::Sarif, .. }
+        | Commands::List { file: argument, format: Format::Sarif, .. } => {
+            writeln!(out, "{}", sarif::to_sarif(&database, &argument))
+        },
+        Commands::Tree { format: Format::Chains, depth, .. } => {
+            print_chains(out, &Report::new(&database, &file_name), depth)
+        },
+        Commands::List { format: Format::Chains, depth, .. } => {
+            print_chains(out, &Report::new(&database, &file_name), depth)
+        },
+        Commands::Tree {
+            exclude_system,
+            only_type,
+            filter,
+            root: dll,
+            ..
+        } => {
+            let tree_root = match tree_root(&database, &file_name, dll.as_deref()) {
+                Some(tree_root) => tree_root,
+                None => {
+                    error!("{} is not loaded by {}", dll.unwrap_or_default(), file_name);
+                    std::process::exit(1);
+                }
+            };
+
+            let filter = Filter {
+                exclude_system,
+                only_types: only_type,
+                name: filter,
+            };
+            let visible = (!filter.is_empty()).then(|| filter.visible(&database));
+            let printer = TreePrinter::new(&options, visible);
+            printer.print(out, &database, &tree_root, &mut Vec::new(), None)
+        },
+        Commands::List {
+            exclude_system,
+            only_type,
+            filter,
+            ..
+        } => {
+            let filter = Filter {
+                exclude_system,
+                only_types: only_type,
+                name: filter,
+            };
+            let dlls = list_dlls(&database, &filter, &options, &walk_order);
+            print_list(out, &database, &dlls, &options)
+        },
+        Commands::Check { file: argument, fail_on, policy, .. } => {
+            let policy = load_policy(&argument, &current_directory, policy.as_deref());
+            print_problems(out, &database, &file_name, &policy, &fail_on)
+                .map(|fatal| failed = fatal)
+        },
+        Commands::CheckSymbols { .. } => {
+            print_missing_symbols(out, &database)
+        },
+        Commands::SignCheck { .. } => {
+            print_signatures(out, &database).map(|untrusted| failed = untrusted)
+        },
+        Commands::Info { .. } => {
+            print_info(out, &database)
+        },
+        Commands::VersionInfo { all, .. } => {
+            let names = if all { database.get_all_dlls() } else { vec![file_name.clone()] };
+            print_version_info(out, &database, &names)
+        },
+        Commands::Hijack { .. } => {
+            print_hijackable_dlls(out, &database)
+        },
+        Commands::SearchOrder { .. } => {
+            let kernel_mode = root.as_ref().map_or(false, |root| root.is_kernel_image());
+            let load_flags = root
+                .as_ref()
+                .map_or(0, |root| root.dependent_load_flags as u32);
+            print_search_order(out, database.search_path(), kernel_mode, load_flags)
+        },
+        Commands::Report { .. } => {
+            write!(out, "{}", Report::new(&database, &file_name).to_html())
+        },
+        Commands::Explain { dll, .. } => {
+            if !database.get_all_dlls().iter().any(|name| name.eq_ignore_ascii_case(&dll)) {
+                warn!("{} is not imported by {}", dll, file_name);
+            }
+            print_explanation(out, &database, &dll)
+        },
+        Commands::Why { dll, max_chains, .. } => {
+            print_chains_to(out, &Report::new(&database, &file_name), &dll, max_chains)
+        },
+        Commands::FindSymbol { symbol, .. } => {
+            print_exporters(out, &database, &symbol)
+        },
+        Commands::CopyDeps {
+            file: argument,
+            destination,
+            dry_run,
+            overwrite,
+            ..
+        } => {
+            let file = long_path::absolute(&argument, &current_directory);
+            let directory = destination.unwrap_or_else(|| {
+                file.parent().unwrap_or(&current_directory).to_owned()
+            });
+            copy_dependencies(out, &database, &file_name, &directory, dry_run, overwrite)
+        },
+        Commands::RuntimeRequirements { .. } => {
+            print_requirements(out, &runtime::requirements(&database, &file_name))
+        },
+        Commands::MinWindowsVersion { .. } => {
+            let (version, requirements) = windows_version::minimum_version(&database, &file_name);
+            print_minimum_version(out, version, &requirements)
+        },
+        Commands::Stats { .. } => {
+            print_stats(out, &Stats::new(&Report::new(&database, &file_name)))
+        },
+        Commands::Top { by, count, .. } => {
+            let entries = top::entries(&database, &file_name, by == SizeOrder::Image);
+            print_top(out, &entries, count)
+        },
+        Commands::Tui { .. } => tui::run(&database, &Report::new(&database, &file_name)),
+        Commands::KnownDlls { .. } => {
+            print_known_dlls(out, database.search_path())
+        },
+        Commands::Diff { .. }
+        | Commands::Cache { .. }
+        | Commands::Manifest { .. }
+        | Commands::Process { .. }
+        | Commands::Rdeps { .. }
+        | Commands::Snapshot { .. } => unreachable!(),
+    };
+    result
+        .and_then(|_| out.flush())
+        .unwrap_or_else(exit_on_output_error);
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use dllwalk_core::{file_provider::MemoryFiles, inventory::InventoryFile};
+
+    use super::*;
+
+    /// Image of the inventory importing `dependencies`, without a machine
+    pub fn file(dependencies: &[&str]) -> InventoryFile {
+        InventoryFile {
+            machine: None,
+            dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// Database of the dlls loaded by the first of `files`, the others are missing. Files
+    /// are in the application directory of an inventory, or in its system directory when
+    /// their name starts with it, like `C:\Windows\System32\kernel32.dll`.
+    pub fn database(files: Vec<(&str, InventoryFile)>) -> DllDatabase {
+        let directory = PathBuf::from(r"C:\App");
+        let system_directory = PathBuf::from(r"C:\Windows\System32");
+        let root = files[0].0.to_owned();
+        let mut application_files = MemoryFiles::new();
+        let mut directories: BTreeMap<PathBuf, BTreeMap<String, InventoryFile>> =
+            BTreeMap::new();
+        for (name, file) in files {
+            let (parent, name) = match name.rsplit_once('\\') {
+                Some((parent, name)) => (PathBuf::from(parent), name),
+                None => {
+                    application_files.insert(directory.join(name), []);
+                    (directory.clone(), name)
+                }
+            };
+            directories.entry(parent).or_default().insert(name.to_owned(), file);
+        }
+        let inventory = Inventory {
+            windows_directory: PathBuf::from(r"C:\Windows"),
+            system_directory,
+            directories,
+            ..Inventory::default()
+        };
+
+        let search_path = SearchPath::builder(&directory)
+            .current_directory(&directory)
+            .inventory(Some(inventory))
+            .file_provider(application_files)
+            .build()
+            .unwrap();
+        let mut database = DllDatabase::new(search_path);
+        database.walk(&root, |_| {});
+        database
+    }
+
+    fn print_tree(database: &DllDatabase, arguments: &[&str]) -> String {
+        let args = Arguments::parse_from(["dllwalk", "tree", "app.exe"].iter().chain(arguments));
+        let options = PrintOptions::new(&args.command, false);
+        let root = match &args.command {
+            Commands::Tree { root, .. } => tree_root(database, "app.exe", root.as_deref()),
+            _ => unreachable!(),
+        };
+        let mut out = Vec::new();
+        TreePrinter::new(&options, None)
+            .print(&mut out, database, &root.unwrap(), &mut Vec::new(), None)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn list(database: &DllDatabase, arguments: &[&str], walk_order: &[&str]) -> Vec<String> {
+        let args = Arguments::parse_from(["dllwalk", "list", "app.exe"].iter().chain(arguments));
+        let options = PrintOptions::new(&args.command, false);
+        let walk_order: Vec<String> = walk_order.iter().map(|name| name.to_string()).collect();
+        list_dlls(database, &Filter::default(), &options, &walk_order)
+    }
+
+    #[test]
+    fn sorted_output() {
+        let database = database(vec![
+            ("app.exe", file(&["zlib1.dll", "Bar.dll", "alpha.dll"])),
+            ("alpha.dll", file(&[])),
+            ("bar.dll", file(&[])),
+            ("zlib1.dll", file(&[])),
+        ]);
+
+        // Children are sorted by name ignoring case, or kept in import order
+        assert_eq!(
+            print_tree(&database, &[]),
+            "app.exe\n├── alpha.dll\n├── Bar.dll\n└── zlib1.dll\n"
+        );
+        assert_eq!(
+            print_tree(&database, &["--sort", "import"]),
+            "app.exe\n├── zlib1.dll\n├── Bar.dll\n└── alpha.dll\n"
+        );
+
+        let walk_order = ["app.exe", "alpha.dll", "Bar.dll", "zlib1.dll"];
+        assert_eq!(
+            list(&database, &[], &walk_order),
+            ["alpha.dll", "app.exe", "Bar.dll", "zlib1.dll"]
+        );
+        assert_eq!(list(&database, &["--sort", "import"], &walk_order), walk_order);
+    }
+
+    #[test]
+    fn list_depth() {
+        // zlib1.dll is imported by the root as well as one level down
+        let database = database(vec![
+            ("app.exe", file(&["zlib1.dll", "qt5core.dll"])),
+            ("qt5core.dll", file(&["zlib1.dll", "icuuc.dll"])),
+            ("zlib1.dll", file(&[])),
+            ("icuuc.dll", file(&[])),
+        ]);
+
+        assert_eq!(database.get_depth("zlib1.dll"), Some(1));
+        assert_eq!(list(&database, &["--depth", "0"], &[]), ["app.exe"]);
+        assert_eq!(
+            list(&database, &["--depth", "1"], &[]),
+            ["app.exe", "qt5core.dll", "zlib1.dll"]
+        );
+        assert_eq!(list(&database, &[], &[]).len(), 4);
+    }
+
+    #[test]
+    fn defaults() {
+        let with_defaults = |arguments: &[&str], defaults| {
+            let arguments = ["dllwalk", "list", "app.exe"].iter().chain(arguments);
+            let matches = Arguments::command().try_get_matches_from(arguments).unwrap();
+            let mut args = Arguments::from_arg_matches(&matches).unwrap();
+            apply_defaults(&mut args, &matches, defaults);
+            args
+        };
+        let defaults = || Defaults {
+            format: Some("json".to_owned()),
+            color: Some("never".to_owned()),
+            exclude_system: Some(true),
+            only_type: Some(vec!["user".to_owned(), "unknown".to_owned()]),
+            ..Defaults::default()
+        };
+
+        // Options of the command line win, the defaults replace the default values
+        let args = with_defaults(&["--format", "text", "--color", "always"], defaults());
+        assert_eq!(args.color, ColorChoice::Always);
+        match args.command {
+            Commands::List {
+                format,
+                exclude_system,
+                only_type,
+                ..
+            } => {
+                assert_eq!(format, Format::Text);
+                assert!(exclude_system);
+                assert_eq!(only_type, [DllType::User]);
+            }
+            _ => unreachable!(),
+        }
+
+        let args = with_defaults(&["--only-type", "system"], defaults());
+        assert_eq!(args.color, ColorChoice::Never);
+        match args.command {
+            Commands::List { format, only_type, .. } => {
+                assert_eq!(format, Format::Json);
+                assert_eq!(only_type, [DllType::System]);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn type_tags() {
+        let database = database(vec![
+            ("app.exe", file(&["zlib1.dll", "missing.dll"])),
+            ("zlib1.dll", file(&[])),
+        ]);
+
+        // Missing dlls are only tagged by their label
+        assert_eq!(
+            print_tree(&database, &["--show-type"]),
+            "app.exe [user]\n├── missing.dll [NOT FOUND]\n└── zlib1.dll [user]\n"
+        );
+        assert_eq!(
+            print_tree(&database, &[]),
+            "app.exe\n├── missing.dll [NOT FOUND]\n└── zlib1.dll\n"
+        );
+    }
+
+    #[test]
+    fn tree_of_a_dependency() {
+        let database = database(vec![
+            ("app.exe", file(&["Qt5Core.dll"])),
+            ("qt5core.dll", file(&["zlib1.dll"])),
+            ("zlib1.dll", file(&[])),
+        ]);
+
+        // The root keeps the spelling of its import
+        assert_eq!(
+            print_tree(&database, &["--root", "QT5CORE.DLL"]),
+            "Qt5Core.dll\n└── zlib1.dll\n"
+        );
+        assert_eq!(tree_root(&database, "app.exe", Some("icuuc.dll")), None);
+    }
+
+    #[test]
+    fn system_dlls() {
+        let database = database(vec![
+            ("app.exe", file(&["KERNEL32.dll", "icuuc.dll"])),
+            (r"C:\Windows\System32\kernel32.dll", file(&["ntdll.dll"])),
+            (r"C:\Windows\System32\ntdll.dll", file(&[])),
+            ("icuuc.dll", file(&["missing.dll"])),
+        ]);
+
+        // The imports of system dlls are counted instead of printed
+        assert_eq!(
+            print_tree(&database, &["--collapse-system"]),
+            concat!(
+                "app.exe\n",
+                "├── icuuc.dll\n",
+                "│   └── missing.dll [NOT FOUND]\n",
+                "└── KERNEL32.dll [+1 dll]\n",
+            )
+        );
+
+        let args = Arguments::parse_from(["dllwalk", "list", "app.exe", "--group-by-type"]);
+        let options = PrintOptions::new(&args.command, false);
+        let dlls = list_dlls(&database, &Filter::default(), &options, &[]);
+        let mut out = Vec::new();
+        print_list(&mut out, &database, &dlls, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "missing:\n",
+                "    missing.dll [NOT FOUND]\n",
+                "\n",
+                "user-dll:\n",
+                "    app.exe\n",
+                "    icuuc.dll\n",
+                "\n",
+                "system-dll:\n",
+                "    KERNEL32.dll\n",
+                "    ntdll.dll\n",
+            )
+        );
+    }
+
+    #[test]
+    fn sort_orders() {
+        let database = database(vec![
+            ("app.exe", file(&["zlib1.dll", "KERNEL32.dll", "missing.dll"])),
+            (r"C:\Windows\System32\kernel32.dll", file(&[])),
+            ("zlib1.dll", file(&["bz2.dll"])),
+            ("bz2.dll", file(&[])),
+        ]);
+
+        // Missing dlls come first, ties are sorted by name
+        assert_eq!(
+            list(&database, &["--sort", "type"], &[]),
+            ["missing.dll", "app.exe", "bz2.dll", "zlib1.dll", "KERNEL32.dll"]
+        );
+        assert_eq!(
+            list(&database, &["--sort", "depth"], &[]),
+            ["app.exe", "KERNEL32.dll", "missing.dll", "zlib1.dll", "bz2.dll"]
+        );
+        assert_eq!(
+            list(&database, &["--sort", "path"], &[]),
+            ["missing.dll", "app.exe", "bz2.dll", "zlib1.dll", "KERNEL32.dll"]
+        );
+    }
+
+    #[test]
+    fn tree_cycle() {
+        let database = database(vec![
+            ("app.exe", file(&["a.dll", "c.dll"])),
+            ("a.dll", file(&["b.dll", "c.dll"])),
+            ("b.dll", file(&["A.dll"])),
+            ("c.dll", file(&[])),
+        ]);
+
+        // The imports of a dll importing one of its ancestors are not printed again, a dll
+        // imported twice without a cycle is
+        assert_eq!(
+            print_tree(&database, &[]),
+            concat!(
+                "app.exe\n",
+                "├── a.dll\n",
+                "│   ├── b.dll\n",
+                "│   │   └── A.dll (cycle)\n",
+                "│   └── c.dll\n",
+                "└── c.dll\n",
+            )
+        );
+        assert_eq!(Report::new(&database, "app.exe").cycles, [["a.dll", "b.dll", "a.dll"]]);
+    }
+
+    #[test]
+    fn check() {
+        let database = database(vec![
+            (
+                "app.exe",
+                InventoryFile {
+                    machine: Some(0x8664),
+                    ..file(&["missing.dll", "zlib1.dll"])
+                },
+            ),
+            (
+                "zlib1.dll",
+                InventoryFile {
+                    machine: Some(0x14c),
+                    ..file(&[])
+                },
+            ),
+        ]);
+        let check = |fatal: &[Problem]| {
+            let mut out = Vec::new();
+            let failed =
+                print_problems(&mut out, &database, "app.exe", &Policy::default(), fatal).unwrap();
+            (failed, String::from_utf8(out).unwrap())
+        };
+
+        // Only the fatal problems fail the check, the others are warnings
+        let (failed, output) = check(&[Problem::Missing]);
+        assert!(failed);
+        assert_eq!(
+            output,
+            concat!(
+                "error: missing.dll is not found\n",
+                "warning: app.exe imports zlib1.dll which targets a different architecture\n",
+            )
+        );
+        assert!(!check(&[Problem::Location, Problem::Policy]).0);
+    }
+
+    #[test]
+    fn explanation() {
+        let database = database(vec![
+            ("app.exe", file(&["kernel32.dll", "missing.dll"])),
+            (r"C:\Windows\System32\kernel32.dll", file(&[])),
+        ]);
+        let explain = |name| {
+            let mut out = Vec::new();
+            print_explanation(&mut out, &database, name).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        // Every rule is listed up to the one resolving the dll
+        let explanation = explain("kernel32.dll");
+        let lines: Vec<&str> = explanation.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[2], r"3. C:\App [application directory]: not present");
+        assert!(lines[4].starts_with(r"5. C:\Windows\System32 [system directory]: found "));
+        assert_eq!(lines[5], "Resolved by the system directory rule (system-dll)");
+
+        let explanation = explain("missing.dll");
+        assert!(explanation.contains("\n6. C:\\Windows [Windows directory]: not present\n"));
+        assert!(explanation.ends_with("\n7. api set: not present\nmissing.dll is not found\n"));
+    }
+
+    #[test]
+    fn output_file() {
+        let directory = std::env::temp_dir().join("dllwalk-output-file");
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("report.txt");
+        std::fs::write(&path, "previous run").unwrap();
+
+        // The file of the previous run is kept until the first write
+        let mut output = OutputFile::new(&path);
+        let previous = std::fs::read_to_string(&path).unwrap();
+        writeln!(output, "app.exe").unwrap();
+        output.flush().unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        let mut missing = OutputFile::new(&directory.join("missing").join("report.txt"));
+        let err = missing.flush().unwrap_err();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(previous, "previous run");
+        assert_eq!(written, "app.exe\n");
+        assert!(err.to_string().starts_with("cannot create "));
+    }
+
+    #[test]
+    fn markdown_tree() {
+        let database = database(vec![
+            ("app.exe", file(&["zlib1.dll"])),
+            ("zlib1.dll", file(&["missing.dll"])),
+        ]);
+
+        assert_eq!(
+            print_tree(&database, &["--format", "markdown"]),
+            concat!(
+                "- `app.exe`\n",
+                "  - `zlib1.dll`\n",
+                "    - `missing.dll [NOT FOUND]`\n",
+            )
+        );
     }
 }
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use dllwalk_core::{search_path::same_directory, DllDatabase};
+use serde::Deserialize;
+
+use crate::filter;
+
+/// Dlls an application may load, read from the [policy] table of its .dllwalk.toml file
+/// and from the file given to check --policy. Names may contain `*`, matching any
+/// characters, and are compared ignoring case.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Policy {
+    /// Dlls that must not be loaded, like debug runtimes
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Directories no dll may be loaded from, their subdirectories included, absolute or
+    /// relative to the application directory
+    #[serde(default)]
+    pub deny_paths: Vec<String>,
+
+    /// When not empty, the only dlls that may be loaded besides the ones of Windows
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// File each dll must be loaded from, absolute or relative to the application
+    /// directory
+    #[serde(default)]
+    pub require: HashMap<String, String>,
+}
+
+impl Policy {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(Policy::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Adds the rules of `other`, whose required files replace the ones of `self`
+    pub fn extend(&mut self, other: Policy) {
+        self.deny.extend(other.deny);
+        self.deny_paths.extend(other.deny_paths);
+        self.allow.extend(other.allow);
+        self.require.extend(other.require);
+    }
+
+    /// Messages describing how the dlls loaded by `root` violate the policy, by dll name
+    pub fn violations(&self, database: &DllDatabase, root: &str) -> Vec<String> {
+        let search_path = database.search_path();
+        let denied_directories: Vec<_> = self
+            .deny_paths
+            .iter()
+            .map(|directory| search_path.application_path(directory))
+            .collect();
+
+        let mut names = database.get_all_dlls();
+        names.sort_by_key(|name| name.to_lowercase());
+
+        let mut violations = Vec::new();
+        for name in names.iter().filter(|name| !name.eq_ignore_ascii_case(root)) {
+            if let Some(pattern) = self.deny.iter().find(|pattern| matches(pattern, name)) {
+                violations.push(format!("{} is denied by the policy ({})", name, pattern));
+            }
+
+            let info = database.get_dll_info(name);
+            if let Some(info) = info {
+                let denied = denied_directories
+                    .iter()
+                    .find(|directory| info.path.ancestors().any(|a| same_directory(a, directory)));
+                if let Some(directory) = denied {
+                    violations.push(format!(
+                        "{} is loaded from {} which is denied by the policy",
+                        name,
+                        directory.to_string_lossy()
+                    ));
+                }
+
+                if !self.allow.is_empty()
+                    && !filter::is_system(info.dll_type)
+                    && !self.allow.iter().any(|pattern| matches(pattern, name))
+                {
+                    violations.push(format!("{} is not allowed by the policy", name));
+                }
+            }
+
+            let required = self
+                .require
+                .iter()
+                .find(|(required, _)| required.eq_ignore_ascii_case(name))
+                .map(|(_, path)| search_path.application_path(path));
+            match (required, info) {
+                (Some(required), Some(info)) if !same_directory(&info.path, &required) => {
+                    violations.push(format!(
+                        "{} is loaded from {} instead of {} required by the policy",
+                        name,
+                        info.path.to_string_lossy(),
+                        required.to_string_lossy()
+                    ));
+                }
+                (Some(required), None) => violations.push(format!(
+                    "{} is not found, the policy requires {}",
+                    name,
+                    required.to_string_lossy()
+                )),
+                _ => {}
+            }
+        }
+        violations
+    }
+}
+
+// Whether `name` matches `pattern`, in which `*` matches any characters, ignoring case
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        assert!(super::matches("msvcp140d.dll", "MSVCP140D.dll"));
+        assert!(!super::matches("msvcp140d.dll", "msvcp140.dll"));
+        assert!(super::matches("*d.dll", "ucrtbased.dll"));
+        assert!(super::matches("msvc*d.dll", "msvcp140d.dll"));
+        assert!(!super::matches("msvc*d.dll", "msvcp140.dll"));
+        assert!(super::matches("vendor*_*.dll", "vendor_net_1.dll"));
+        assert!(!super::matches("a*ab.dll", "ab.dll"));
+        assert!(super::matches("*", "kernel32.dll"));
+    }
+
+    #[test]
+    fn parse() {
+        let policy = Policy::parse(
+            r#"
+            deny = ["*d.dll"]
+            deny-paths = ['C:\Program Files\Vendor']
+            allow = ["qt5*.dll"]
+
+            [require]
+            "zlib1.dll" = 'lib\zlib1.dll'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(policy.deny, vec!["*d.dll"]);
+        assert_eq!(policy.deny_paths, vec![r"C:\Program Files\Vendor"]);
+        assert_eq!(policy.allow, vec!["qt5*.dll"]);
+        assert_eq!(policy.require["zlib1.dll"], r"lib\zlib1.dll");
+        assert!(Policy::parse("denied = []").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use dllwalk_core::{search_path::same_directory, DllDatabase};
+
+#[cfg(windows)]
+mod toolhelp;
+
+/// A running process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    pub id: u32,
+    pub name: String,
+}
+
+/// A module mapped in a running process, the executable first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// How a loaded module relates to the import closure of the executable
+#[derive(Debug, PartialEq, Eq)]
+pub enum Origin<'a> {
+    /// Imported and loaded from the path the walk resolved
+    Imported,
+    /// Not imported, loaded at runtime by LoadLibrary or as a dependency of such a module
+    Dynamic,
+    /// Imported but loaded from another path than the one the walk resolved, the search
+    /// order of the process differs from the simulated one
+    Unexpected(&'a Path),
+}
+
+/// Processes whose id or executable name is `target`, the extension may be left out
+pub fn find_processes(target: &str) -> Result<Vec<Process>, Box<dyn Error>> {
+    let processes = running_processes()?;
+    if let Ok(id) = target.parse::<u32>() {
+        return Ok(processes.into_iter().filter(|process| process.id == id).collect());
+    }
+
+    Ok(processes
+        .into_iter()
+        .filter(|process| is_named(&process.name, target))
+        .collect())
+}
+
+fn is_named(executable: &str, name: &str) -> bool {
+    let stem = executable.rsplit_once('.').map_or(executable, |(stem, _)| stem);
+    executable.eq_ignore_ascii_case(name) || stem.eq_ignore_ascii_case(name)
+}
+
+#[cfg(windows)]
+fn running_processes() -> Result<Vec<Process>, Box<dyn Error>> {
+    toolhelp::processes()
+}
+
+#[cfg(not(windows))]
+fn running_processes() -> Result<Vec<Process>, Box<dyn Error>> {
+    Err("Processes can only be inspected on Windows".into())
+}
+
+/// Modules mapped in the process `id`, which must be readable by the current user
+#[cfg(windows)]
+pub fn loaded_modules(id: u32) -> Result<Vec<Module>, Box<dyn Error>> {
+    toolhelp::modules(id)
+}
+
+#[cfg(not(windows))]
+pub fn loaded_modules(_id: u32) -> Result<Vec<Module>, Box<dyn Error>> {
+    Err("Processes can only be inspected on Windows".into())
+}
+
+/// Where a module loaded in a process comes from, compared to the dlls the walk of its
+/// executable resolved
+pub fn origin<'a>(database: &'a DllDatabase, module: &Module) -> Origin<'a> {
+    let name = database
+        .get_all_dlls()
+        .into_iter()
+        .find(|name| name.eq_ignore_ascii_case(&module.name));
+    let info = match name.as_deref().and_then(|name| database.get_dll_info(name)) {
+        Some(info) => info,
+        None => return Origin::Dynamic,
+    };
+
+    // Files are compared like directories, ignoring case and the \\?\ prefix
+    if same_directory(&info.path, &module.path) {
+        Origin::Imported
+    } else {
+        Origin::Unexpected(&info.path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn is_named() {
+        assert!(super::is_named("Notepad.exe", "notepad.exe"));
+        assert!(super::is_named("Notepad.exe", "notepad"));
+        assert!(!super::is_named("Notepad.exe", "note"));
+        assert!(!super::is_named("notepad++.exe", "notepad"));
+    }
+}
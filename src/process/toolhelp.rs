@@ -0,0 +1,103 @@
+use std::{error::Error, path::PathBuf, time::Duration};
+
+use bindings::Windows::Win32::{
+    Foundation::{CloseHandle, HANDLE},
+    System::Diagnostics::{
+        Debug::ERROR_BAD_LENGTH,
+        ToolHelp::{
+            CreateToolhelp32Snapshot, Module32FirstW, Module32NextW, Process32FirstW,
+            Process32NextW, CREATE_TOOLHELP_SNAPSHOT_FLAGS, MODULEENTRY32W, PROCESSENTRY32W,
+            TH32CS_SNAPMODULE, TH32CS_SNAPMODULE32, TH32CS_SNAPPROCESS,
+        },
+    },
+};
+
+use super::{Module, Process};
+
+// ERROR_BAD_LENGTH is returned while the modules of a starting process are being loaded,
+// the snapshot is taken again a few times before giving up
+const SNAPSHOT_ATTEMPTS: u32 = 10;
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+// Closes the snapshot when dropped
+struct Snapshot(HANDLE);
+
+impl Snapshot {
+    fn take(
+        flags: CREATE_TOOLHELP_SNAPSHOT_FLAGS,
+        process_id: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut attempt = 1;
+        loop {
+            let handle = unsafe { CreateToolhelp32Snapshot(flags, process_id) };
+            if !handle.is_invalid() {
+                return Ok(Snapshot(handle));
+            }
+
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(ERROR_BAD_LENGTH.0 as i32)
+                || attempt == SNAPSHOT_ATTEMPTS
+            {
+                return Err(Box::new(error));
+            }
+            attempt += 1;
+            std::thread::sleep(SNAPSHOT_RETRY_DELAY);
+        }
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+pub fn processes() -> Result<Vec<Process>, Box<dyn Error>> {
+    let snapshot = Snapshot::take(TH32CS_SNAPPROCESS, 0)?;
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut processes = Vec::new();
+    let mut found = unsafe { Process32FirstW(snapshot.0, &mut entry) };
+    while found.as_bool() {
+        processes.push(Process {
+            id: entry.th32ProcessID,
+            name: from_wide(&entry.szExeFile),
+        });
+        found = unsafe { Process32NextW(snapshot.0, &mut entry) };
+    }
+
+    Ok(processes)
+}
+
+// The modules of 32-bit processes are only listed with TH32CS_SNAPMODULE32
+pub fn modules(process_id: u32) -> Result<Vec<Module>, Box<dyn Error>> {
+    let flags = CREATE_TOOLHELP_SNAPSHOT_FLAGS(TH32CS_SNAPMODULE.0 | TH32CS_SNAPMODULE32.0);
+    let snapshot = Snapshot::take(flags, process_id)?;
+    let mut entry = MODULEENTRY32W {
+        dwSize: std::mem::size_of::<MODULEENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut modules = Vec::new();
+    let mut found = unsafe { Module32FirstW(snapshot.0, &mut entry) };
+    while found.as_bool() {
+        modules.push(Module {
+            name: from_wide(&entry.szModule),
+            path: PathBuf::from(from_wide(&entry.szExePath)),
+        });
+        found = unsafe { Module32NextW(snapshot.0, &mut entry) };
+    }
+
+    Ok(modules)
+}
+
+fn from_wide(text: &[u16]) -> String {
+    let length = text
+        .iter()
+        .position(|&character| character == 0)
+        .unwrap_or(text.len());
+    String::from_utf16_lossy(&text[..length])
+}
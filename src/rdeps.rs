@@ -0,0 +1,51 @@
+use std::path::{Path, PathBuf};
+
+// Extensions of the files the loader maps, plugins and control panel items included
+const BINARY_EXTENSIONS: [&str; 6] = ["exe", "dll", "ocx", "cpl", "sys", "drv"];
+
+/// Executables and dlls under `directory` and its subdirectories, sorted by path.
+/// Unreadable directories are skipped.
+pub fn find_binaries(directory: &Path) -> Vec<PathBuf> {
+    let mut binaries = Vec::new();
+    let mut directories = vec![directory.to_owned()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(&directory).into_iter().flatten().flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => directories.push(path),
+                Ok(_) if is_binary(&path) => binaries.push(path),
+                _ => {}
+            }
+        }
+    }
+
+    binaries.sort();
+    binaries
+}
+
+fn is_binary(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| {
+        let extension = extension.to_string_lossy();
+        BINARY_EXTENSIONS.iter().any(|binary| extension.eq_ignore_ascii_case(binary))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn find_binaries() {
+        let directory = std::env::temp_dir().join("dllwalk-rdeps");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("plugins")).unwrap();
+        for file in ["app.EXE", "readme.txt", "plugins/zlib1.dll", "plugins/dll"] {
+            std::fs::write(directory.join(file), b"MZ").unwrap();
+        }
+
+        assert_eq!(
+            super::find_binaries(&directory),
+            vec![directory.join("app.EXE"), directory.join("plugins/zlib1.dll")]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
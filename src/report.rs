@@ -0,0 +1,1302 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use dllwalk_core::{pe::DependencyKind, DllDatabase, DllInfo, DllType, WalkEvent};
+use serde::Serialize;
+
+use crate::civil_date;
+
+const REPORT_VERSION: u32 = 1;
+
+/// Dependency graph of a file, printed by `--format json`, `xml`, `dot`, `graphml` or `spdx`.
+/// Nodes are sorted by name and the edges of each importer follow its import order.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub version: u32,
+    pub root: String,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// Import cycles reachable from the root, each from its first dll by name back to it
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// A dll of the walk. A node that is neither found nor ignored, and has no type, could
+/// not be parsed.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Node {
+    pub name: String,
+    /// None for umbrella dlls, which have no file, and unresolved dlls
+    pub path: Option<String>,
+    #[serde(rename = "type")]
+    pub dll_type: Option<DllType>,
+    pub architecture: Option<String>,
+    /// Size of the file in bytes
+    pub size: Option<u64>,
+    /// File version of the version resource
+    pub version: Option<String>,
+    /// SHA-256 of the file, computed with `--hash sha256`
+    pub sha256: Option<String>,
+    pub api_set_host: Option<String>,
+    /// Dlls the file was bound against by its bound import table, with their timestamps
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bound_imports: Vec<BoundImport>,
+    pub not_found: bool,
+    pub ignored: bool,
+}
+
+/// A dll an image was bound against, or the target of one of its forwarded exports. The
+/// binding is used when the dll loaded has the same timestamp.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct BoundImport {
+    pub name: String,
+    pub timestamp: u32,
+}
+
+/// `importer` loads `imported`, through its import table, an export forwarded to it or
+/// an assembly reference
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub importer: String,
+    pub imported: String,
+    pub kind: &'static str,
+}
+
+/// A node or an edge, printed one per line by `--format ndjson` as the walk resolves them
+#[derive(Debug, Serialize)]
+#[serde(tag = "record", rename_all = "lowercase")]
+pub enum Record {
+    Node(Node),
+    Edge(Edge),
+}
+
+impl Record {
+    /// Record of an event of the walk, the node of a dll once it was searched or one of its
+    /// edges
+    pub fn of_event(event: &WalkEvent) -> Self {
+        match *event {
+            WalkEvent::Resolved { name, info } => {
+                Record::Node(Node::from_info(name, Some(info), false, false))
+            }
+            WalkEvent::Missing { name } => Record::Node(Node::from_info(name, None, true, false)),
+            WalkEvent::ParseError { name, .. } => {
+                Record::Node(Node::from_info(name, None, false, false))
+            }
+            WalkEvent::Ignored { name } => Record::Node(Node::from_info(name, None, false, true)),
+            WalkEvent::EdgeVisited {
+                importer,
+                imported,
+                kind,
+            } => Record::Edge(Edge {
+                importer: importer.to_owned(),
+                imported: imported.to_owned(),
+                kind: kind_name(kind),
+            }),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialize the record")
+    }
+}
+
+impl Node {
+    pub fn new(database: &DllDatabase, name: &str) -> Self {
+        Node::from_info(
+            name,
+            database.get_dll_info(name),
+            database.is_missing(name),
+            database.is_ignored(name),
+        )
+    }
+
+    fn from_info(name: &str, info: Option<&DllInfo>, not_found: bool, ignored: bool) -> Self {
+        let path = info
+            .map(|info| info.path.to_string_lossy().into_owned())
+            .filter(|path| !path.is_empty());
+        Node {
+            name: name.to_owned(),
+            path,
+            dll_type: info.map(|info| info.dll_type),
+            architecture: info
+                .and_then(|info| info.architecture())
+                .map(|architecture| architecture.to_string()),
+            size: info.and_then(|info| info.size),
+            version: info
+                .and_then(|info| info.file.version_info.as_ref())
+                .and_then(|version_info| version_info.file_version_string()),
+            sha256: info.and_then(|info| info.sha256.clone()),
+            api_set_host: info.and_then(|info| info.api_set_host.clone()),
+            bound_imports: info
+                .into_iter()
+                .flat_map(|info| &info.file.bound_imports)
+                .flat_map(|bound_import| {
+                    let forwarders = bound_import
+                        .forwarders
+                        .iter()
+                        .map(|forwarder| (&forwarder.name, forwarder.time_date_stamp));
+                    std::iter::once((&bound_import.name, bound_import.time_date_stamp))
+                        .chain(forwarders)
+                })
+                .map(|(name, timestamp)| BoundImport {
+                    name: name.clone(),
+                    timestamp,
+                })
+                .collect(),
+            not_found,
+            ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Node {
+    /// A found dll without any detail, for the reports of the tests
+    pub fn named(name: &str) -> Self {
+        Node {
+            name: name.to_owned(),
+            path: None,
+            dll_type: None,
+            architecture: None,
+            size: None,
+            version: None,
+            sha256: None,
+            api_set_host: None,
+            bound_imports: Vec::new(),
+            not_found: false,
+            ignored: false,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Edge {
+    /// An import of `imported` by `importer`, for the reports of the tests
+    pub fn import(importer: &str, imported: &str) -> Self {
+        Edge {
+            importer: importer.to_owned(),
+            imported: imported.to_owned(),
+            kind: "import",
+        }
+    }
+}
+
+#[cfg(test)]
+impl Report {
+    /// A report of the tests, without cycles
+    pub fn of(root: &str, nodes: Vec<Node>, edges: Vec<Edge>) -> Self {
+        Report {
+            version: REPORT_VERSION,
+            root: root.to_owned(),
+            nodes,
+            edges,
+            cycles: Vec::new(),
+        }
+    }
+}
+
+impl Edge {
+    /// Edges from the dll `name` to its dependencies, none when it is not resolved
+    pub fn from_dependencies<'a>(
+        database: &'a DllDatabase,
+        name: &'a str,
+    ) -> impl Iterator<Item = Self> + 'a {
+        let dependencies = database.get_dll_info(name).map(|info| info.dependencies());
+        dependencies.into_iter().flatten().map(move |dependency| Edge {
+            importer: name.to_owned(),
+            imported: dependency.name,
+            kind: kind_name(dependency.kind),
+        })
+    }
+}
+
+fn kind_name(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Import => "import",
+        DependencyKind::Delay => "delay",
+        DependencyKind::Forwarder => "forwarder",
+        DependencyKind::Assembly => "assembly",
+    }
+}
+
+impl Report {
+    pub fn new(database: &DllDatabase, root: &str) -> Self {
+        let mut names = database.get_all_dlls();
+        names.sort_by_key(|name| name.to_lowercase());
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for name in names {
+            edges.extend(Edge::from_dependencies(database, &name));
+            nodes.push(Node::new(database, &name));
+        }
+
+        Report {
+            version: REPORT_VERSION,
+            root: root.to_owned(),
+            cycles: find_cycles(root, &edges),
+            nodes,
+            edges,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize the report")
+    }
+
+    /// XML document of the report, with an element per node and edge whose attributes are
+    /// the JSON fields. Missing values are left out, report.xsd is its schema.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<report version=\"{}\" root=\"{}\">\n",
+            self.version,
+            escape_xml(&self.root)
+        ));
+
+        xml.push_str("  <nodes>\n");
+        for node in &self.nodes {
+            let attributes = [
+                ("name", Some(node.name.clone())),
+                ("path", node.path.clone()),
+                ("type", node.dll_type.map(|dll_type| dll_type.to_string())),
+                ("architecture", node.architecture.clone()),
+                ("size", node.size.map(|size| size.to_string())),
+                ("version", node.version.clone()),
+                ("sha256", node.sha256.clone()),
+                ("api_set_host", node.api_set_host.clone()),
+                ("not_found", Some(node.not_found.to_string())),
+                ("ignored", Some(node.ignored.to_string())),
+            ];
+            let attributes: Vec<String> = attributes
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some(format!("{}=\"{}\"", name, escape_xml(value.as_ref()?)))
+                })
+                .collect();
+            xml.push_str(&format!("    <node {}/>\n", attributes.join(" ")));
+        }
+        xml.push_str("  </nodes>\n");
+
+        xml.push_str("  <edges>\n");
+        for edge in &self.edges {
+            xml.push_str(&format!(
+                "    <edge importer=\"{}\" imported=\"{}\" kind=\"{}\"/>\n",
+                escape_xml(&edge.importer),
+                escape_xml(&edge.imported),
+                edge.kind
+            ));
+        }
+        xml.push_str("  </edges>\n");
+
+        xml.push_str("  <cycles>\n");
+        for cycle in &self.cycles {
+            xml.push_str("    <cycle>\n");
+            for name in cycle {
+                xml.push_str(&format!("      <dll name=\"{}\"/>\n", escape_xml(name)));
+            }
+            xml.push_str("    </cycle>\n");
+        }
+        xml.push_str("  </cycles>\n</report>\n");
+        xml
+    }
+
+    /// The shortest import chain from the root to each dll, sorted by the dll it leads to.
+    /// `max_depth` limits the number of imports.
+    pub fn chains(&self, max_depth: Option<u32>) -> Vec<Vec<&str>> {
+        let imports = import_map(&self.edges);
+        let importers =
+            shortest_importers(&imports, &self.root, &HashSet::new(), &HashSet::new());
+
+        let max_length = max_depth.map_or(usize::MAX, |depth| depth as usize + 1);
+        let mut chains: Vec<Vec<&str>> = importers
+            .keys()
+            .filter(|&name| *name != self.root.to_lowercase())
+            .filter_map(|name| chain_from(&importers, name))
+            .filter(|chain| chain.len() <= max_length)
+            .collect();
+        chains.sort_by_cached_key(|chain| {
+            chain.iter().rev().map(|name| name.to_lowercase()).collect::<Vec<_>>()
+        });
+        chains
+    }
+
+    /// The shortest import chain from the root to the dll `name`, None when it does not load it
+    pub fn shortest_chain(&self, name: &str) -> Option<Vec<&str>> {
+        let imports = import_map(&self.edges);
+        chain_to(&imports, &self.root, &name.to_lowercase(), &HashSet::new(), &HashSet::new())
+    }
+
+    /// Import chains from the root to the dll `name`, shortest first, at most `max_chains`
+    /// of them. A dll appears once in a chain. Each chain is found breadth first by leaving
+    /// one of the imports of the chains before it, so that the chains of large graphs are
+    /// never all enumerated.
+    pub fn chains_to(&self, name: &str, max_chains: usize) -> Vec<Vec<&str>> {
+        let imports = import_map(&self.edges);
+        let target = name.to_lowercase();
+
+        let mut chains: Vec<Vec<&str>> = Vec::new();
+        let mut candidates: Vec<Vec<&str>> = Vec::new();
+        let mut next = chain_to(&imports, &self.root, &target, &HashSet::new(), &HashSet::new());
+        while let Some(chain) = next.filter(|_| chains.len() < max_chains) {
+            // The chains branching from this one after each of its dlls
+            for spur in 0..chain.len() - 1 {
+                let prefix = &chain[..=spur];
+                let removed = chains
+                    .iter()
+                    .chain([&chain])
+                    .filter(|other| other.len() > spur + 1 && same_chain(&other[..=spur], prefix))
+                    .map(|other| (other[spur].to_lowercase(), other[spur + 1].to_lowercase()))
+                    .collect();
+                let excluded = prefix[..spur].iter().map(|name| name.to_lowercase()).collect();
+                if let Some(rest) = chain_to(&imports, chain[spur], &target, &excluded, &removed) {
+                    let candidate: Vec<&str> = prefix[..spur].iter().copied().chain(rest).collect();
+                    if !candidates.iter().any(|other| same_chain(other, &candidate)) {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+            chains.push(chain);
+
+            candidates.retain(|candidate| !chains.iter().any(|chain| same_chain(chain, candidate)));
+            let shortest = candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, chain)| {
+                    let names: Vec<String> = chain.iter().map(|name| name.to_lowercase()).collect();
+                    (chain.len(), names)
+                })
+                .map(|(index, _)| index);
+            next = shortest.map(|index| candidates.swap_remove(index));
+        }
+        chains
+    }
+
+    /// Single HTML page with the report and the script browsing it, a collapsible tree
+    /// and a search of the dlls with the chain importing them
+    pub fn to_html(&self) -> String {
+        // The JSON is embedded in a script element, which ends at the first </
+        let json = serde_json::to_string(self)
+            .expect("Failed to serialize the report")
+            .replace("</", "<\\/");
+        include_str!("report.html").replace("/*REPORT*/", &json)
+    }
+
+    /// Graphviz digraph of the report, nodes are filled with the color of their type and
+    /// unresolved ones are outlined in red
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n", quote(&self.root));
+        dot.push_str("    node [shape=box, style=filled];\n");
+
+        for node in &self.nodes {
+            let mut attributes = Vec::new();
+            match node.dll_type {
+                Some(dll_type) => attributes.push(format!("fillcolor={}", color(dll_type))),
+                None => attributes.push("fillcolor=white".to_owned()),
+            }
+            if node.not_found {
+                attributes.push("color=red".to_owned());
+            }
+            if node.ignored {
+                attributes.push(r#"style="filled,dashed""#.to_owned());
+            }
+            if let Some(path) = &node.path {
+                attributes.push(format!("tooltip={}", quote(path)));
+            }
+
+            dot.push_str(&format!("    {} [{}];\n", quote(&node.name), attributes.join(", ")));
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                "delay" => " [color=gray]",
+                "forwarder" => " [style=dashed]",
+                "assembly" => " [style=dotted]",
+                _ => "",
+            };
+            dot.push_str(&format!(
+                "    {} -> {}{};\n",
+                quote(&edge.importer),
+                quote(&edge.imported),
+                style
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// GraphML document of the report, the fields of the nodes and edges are typed
+    /// attributes
+    pub fn to_graphml(&self) -> String {
+        let mut graphml = String::new();
+        graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        graphml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (target, name, attribute_type) in GRAPHML_KEYS {
+            graphml.push_str(&format!(
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>\n",
+                name, target, name, attribute_type
+            ));
+        }
+        graphml.push_str(&format!(
+            "  <graph id=\"{}\" edgedefault=\"directed\">\n",
+            escape_xml(&self.root)
+        ));
+
+        for node in &self.nodes {
+            let data = [
+                ("path", node.path.clone()),
+                ("type", node.dll_type.map(|dll_type| dll_type.to_string())),
+                ("architecture", node.architecture.clone()),
+                ("size", node.size.map(|size| size.to_string())),
+                ("version", node.version.clone()),
+                ("sha256", node.sha256.clone()),
+                ("api_set_host", node.api_set_host.clone()),
+                ("not_found", Some(node.not_found.to_string())),
+                ("ignored", Some(node.ignored.to_string())),
+            ];
+
+            graphml.push_str(&format!("    <node id=\"{}\">\n", escape_xml(&node.name)));
+            for (key, value) in data {
+                if let Some(value) = value {
+                    graphml.push_str(&format!(
+                        "      <data key=\"{}\">{}</data>\n",
+                        key,
+                        escape_xml(&value)
+                    ));
+                }
+            }
+            graphml.push_str("    </node>\n");
+        }
+
+        for edge in &self.edges {
+            graphml.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n",
+                escape_xml(&edge.importer),
+                escape_xml(&edge.imported)
+            ));
+            graphml.push_str(&format!("      <data key=\"kind\">{}</data>\n", edge.kind));
+            graphml.push_str("    </edge>\n");
+        }
+
+        graphml.push_str("  </graph>\n</graphml>\n");
+        graphml
+    }
+
+    /// Table of the nodes printed by `--format table`, aligned on the widest cell of each
+    /// column, or a Markdown table with `markdown`
+    pub fn to_table(&self, markdown: bool) -> String {
+        let mut rows = vec![[
+            "Name".to_owned(),
+            "Size".to_owned(),
+            "Version".to_owned(),
+            "Arch".to_owned(),
+            "Type".to_owned(),
+            "Path".to_owned(),
+        ]];
+        for node in &self.nodes {
+            let dll_type = match node.dll_type {
+                Some(dll_type) => dll_type.to_string(),
+                None if node.not_found => "NOT FOUND".to_owned(),
+                None if node.ignored => "ignored".to_owned(),
+                None => "unreadable".to_owned(),
+            };
+            rows.push([
+                node.name.clone(),
+                node.size.map(|size| size.to_string()).unwrap_or_default(),
+                node.version.clone().unwrap_or_default(),
+                node.architecture.clone().unwrap_or_default(),
+                dll_type,
+                node.path.clone().unwrap_or_default(),
+            ]);
+        }
+
+        let mut table = String::new();
+        if markdown {
+            for (index, row) in rows.iter().enumerate() {
+                let cells: Vec<String> =
+                    row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                table.push_str(&format!("| {} |\n", cells.join(" | ")));
+                if index == 0 {
+                    table.push_str("|---|---:|---|---|---|---|\n");
+                }
+            }
+            return table;
+        }
+
+        let mut widths = [0; 6];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        // Sizes are right aligned, the path is the last column and is not padded
+        for row in &rows {
+            let line = format!(
+                "{:<name$}  {:>size$}  {:<version$}  {:<arch$}  {:<dll_type$}  {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                row[4],
+                row[5],
+                name = widths[0],
+                size = widths[1],
+                version = widths[2],
+                arch = widths[3],
+                dll_type = widths[4],
+            );
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+
+    /// SPDX 2.3 JSON document of the dlls that are loaded, created at `created` seconds
+    /// since the Unix epoch. Umbrella dlls have no file, their importers depend on the
+    /// host of their api set instead.
+    pub fn to_spdx(&self, created: u64) -> String {
+        let mut packages = Vec::new();
+        let mut identifiers = HashMap::new();
+        for node in &self.nodes {
+            if node.path.is_none() || node.dll_type.is_none() {
+                continue;
+            }
+
+            // Names may only contain letters, digits, dots and dashes
+            let sanitized: String = node
+                .name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' })
+                .collect();
+            let spdx_id = format!("SPDXRef-{}-{}", packages.len() + 1, sanitized);
+            identifiers.insert(node.name.as_str(), spdx_id.clone());
+
+            packages.push(SpdxPackage {
+                name: node.name.clone(),
+                spdx_id,
+                version_info: node.version.clone(),
+                package_file_name: node.path.clone(),
+                checksums: node
+                    .sha256
+                    .iter()
+                    .map(|sha256| SpdxChecksum {
+                        algorithm: "SHA256",
+                        checksum_value: sha256.clone(),
+                    })
+                    .collect(),
+                download_location: "NOASSERTION",
+                files_analyzed: false,
+                comment: node.dll_type.map(|dll_type| dll_type.to_string()),
+            });
+        }
+
+        for node in &self.nodes {
+            let host = node.api_set_host.as_deref();
+            if let Some(spdx_id) = host.and_then(|host| identifiers.get(host)).cloned() {
+                identifiers.insert(node.name.as_str(), spdx_id);
+            }
+        }
+
+        let mut relationships = Vec::new();
+        if let Some(root) = identifiers.get(self.root.as_str()) {
+            relationships.push(SpdxRelationship {
+                spdx_element_id: "SPDXRef-DOCUMENT".to_owned(),
+                relationship_type: "DESCRIBES",
+                related_spdx_element: root.clone(),
+            });
+        }
+        for edge in &self.edges {
+            let importer = identifiers.get(edge.importer.as_str());
+            let imported = identifiers.get(edge.imported.as_str());
+            if let (Some(importer), Some(imported)) = (importer, imported) {
+                let relationship = SpdxRelationship {
+                    spdx_element_id: importer.clone(),
+                    relationship_type: "DEPENDS_ON",
+                    related_spdx_element: imported.clone(),
+                };
+                if importer != imported && !relationships.contains(&relationship) {
+                    relationships.push(relationship);
+                }
+            }
+        }
+
+        let (year, month, day) = civil_date(created / 86400);
+        let seconds = created % 86400;
+        let document = SpdxDocument {
+            spdx_version: "SPDX-2.3",
+            data_license: "CC0-1.0",
+            spdx_id: "SPDXRef-DOCUMENT",
+            name: self.root.clone(),
+            document_namespace: format!(
+                "https://spdx.org/spdxdocs/dllwalk-{}-{}",
+                self.root.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "-"),
+                created
+            ),
+            creation_info: SpdxCreationInfo {
+                created: format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                    year,
+                    month,
+                    day,
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                ),
+                creators: vec![format!("Tool: dllwalk-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            packages,
+            relationships,
+        };
+        serde_json::to_string_pretty(&document).expect("Failed to serialize the report")
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxDocument {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: SpdxCreationInfo,
+    packages: Vec<SpdxPackage>,
+    relationships: Vec<SpdxRelationship>,
+}
+
+#[derive(Debug, Serialize)]
+struct SpdxCreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxPackage {
+    name: String,
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_info: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package_file_name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checksums: Vec<SpdxChecksum>,
+    download_location: &'static str,
+    files_analyzed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxChecksum {
+    algorithm: &'static str,
+    checksum_value: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxRelationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+// Element, name and type of the GraphML attributes
+const GRAPHML_KEYS: [(&str, &str, &str); 10] = [
+    ("node", "path", "string"),
+    ("node", "type", "string"),
+    ("node", "architecture", "string"),
+    ("node", "size", "long"),
+    ("node", "version", "string"),
+    ("node", "sha256", "string"),
+    ("node", "api_set_host", "string"),
+    ("node", "not_found", "boolean"),
+    ("node", "ignored", "boolean"),
+    ("edge", "kind", "string"),
+];
+
+fn find_cycles(root: &str, edges: &[Edge]) -> Vec<Vec<String>> {
+    let mut imports: HashMap<String, Vec<&str>> = HashMap::new();
+    for edge in edges {
+        imports.entry(edge.importer.to_lowercase()).or_default().push(&edge.imported);
+    }
+
+    let mut cycles = Vec::new();
+    add_cycles(&imports, root, &mut Vec::new(), &mut HashSet::new(), &mut cycles);
+    cycles.sort();
+    cycles.dedup();
+    cycles
+}
+
+// Walks the imports of `name` depth first, an import of a dll of `chain` closes a cycle.
+// Each dll is walked once, which finds a cycle at least in each group of dlls importing
+// each other.
+fn add_cycles<'a>(
+    imports: &HashMap<String, Vec<&'a str>>,
+    name: &'a str,
+    chain: &mut Vec<&'a str>,
+    visited: &mut HashSet<String>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    if let Some(start) = chain.iter().position(|dll| dll.eq_ignore_ascii_case(name)) {
+        let mut cycle: Vec<String> = chain[start..].iter().map(|dll| dll.to_string()).collect();
+        let first = (0..cycle.len()).min_by_key(|&index| cycle[index].to_lowercase());
+        cycle.rotate_left(first.unwrap_or(0));
+        cycle.push(cycle[0].clone());
+        cycles.push(cycle);
+        return;
+    }
+
+    let key = name.to_lowercase();
+    if !visited.insert(key.clone()) {
+        return;
+    }
+    chain.push(name);
+    for &imported in imports.get(&key).into_iter().flatten() {
+        add_cycles(imports, imported, chain, visited, cycles);
+    }
+    chain.pop();
+}
+
+// Imports of each dll by lowercase name, importers may spell a dll differently
+fn import_map(edges: &[Edge]) -> HashMap<String, Vec<&str>> {
+    let mut imports: HashMap<String, Vec<&str>> = HashMap::new();
+    for edge in edges {
+        imports.entry(edge.importer.to_lowercase()).or_default().push(&edge.imported);
+    }
+    imports
+}
+
+// Breadth first from `from`, so that each dll is reached through its shortest chain: the
+// dlls found by lowercase name, with their importer on that chain. The `excluded` dlls and
+// the `removed` imports, lowercase, are not gone through.
+fn shortest_importers<'a>(
+    imports: &HashMap<String, Vec<&'a str>>,
+    from: &'a str,
+    excluded: &HashSet<String>,
+    removed: &HashSet<(String, String)>,
+) -> HashMap<String, (&'a str, Option<String>)> {
+    let mut importers = HashMap::new();
+    importers.insert(from.to_lowercase(), (from, None));
+    let mut queue = VecDeque::from([from.to_lowercase()]);
+    while let Some(name) = queue.pop_front() {
+        for &imported in imports.get(&name).into_iter().flatten() {
+            let key = imported.to_lowercase();
+            if excluded.contains(&key) || removed.contains(&(name.clone(), key.clone())) {
+                continue;
+            }
+            if let Entry::Vacant(entry) = importers.entry(key.clone()) {
+                entry.insert((imported, Some(name.clone())));
+                queue.push_back(key);
+            }
+        }
+    }
+    importers
+}
+
+// Chain leading to the dll `name`, lowercase, through the importers found breadth first
+fn chain_from<'a>(
+    importers: &HashMap<String, (&'a str, Option<String>)>,
+    name: &str,
+) -> Option<Vec<&'a str>> {
+    let mut chain = Vec::new();
+    let mut next = Some(name.to_owned());
+    while let Some(name) = next {
+        let (spelling, importer) = importers.get(&name)?;
+        chain.push(*spelling);
+        next = importer.clone();
+    }
+    chain.reverse();
+    Some(chain)
+}
+
+// Shortest chain from `from` to the dll `target`, lowercase, see `shortest_importers`
+fn chain_to<'a>(
+    imports: &HashMap<String, Vec<&'a str>>,
+    from: &'a str,
+    target: &str,
+    excluded: &HashSet<String>,
+    removed: &HashSet<(String, String)>,
+) -> Option<Vec<&'a str>> {
+    chain_from(&shortest_importers(imports, from, excluded, removed), target)
+}
+
+fn same_chain(chain: &[&str], other: &[&str]) -> bool {
+    chain.len() == other.len()
+        && chain.iter().zip(other).all(|(name, other)| name.eq_ignore_ascii_case(other))
+}
+
+fn color(dll_type: DllType) -> &'static str {
+    match dll_type {
+        DllType::User => "lightblue",
+        DllType::Path => "lightcyan",
+        DllType::System => "lightgrey",
+        DllType::Known => "palegreen",
+        DllType::Umbrella => "lightyellow",
+        DllType::Managed => "plum",
+        DllType::SxS => "wheat",
+        DllType::Kernel => "lightsalmon",
+        DllType::Driver => "peachpuff",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // An application loading a known dll and delay loading an ignored plugin
+    fn sample() -> Report {
+        Report::of(
+            "app.exe",
+            vec![
+                Node {
+                    path: Some(r"C:\App\app.exe".to_owned()),
+                    dll_type: Some(DllType::User),
+                    architecture: Some("x64".to_owned()),
+                    size: Some(1200),
+                    version: Some("1.0.0.0".to_owned()),
+                    sha256: Some("e3b0c442".to_owned()),
+                    ..Node::named("app.exe")
+                },
+                Node {
+                    path: Some(r"C:\Windows\System32\kernel32.dll".to_owned()),
+                    dll_type: Some(DllType::Known),
+                    ..Node::named("kernel32.dll")
+                },
+                Node {
+                    ignored: true,
+                    ..Node::named("plugin.dll")
+                },
+            ],
+            vec![
+                Edge::import("app.exe", "kernel32.dll"),
+                Edge {
+                    kind: "delay",
+                    ..Edge::import("app.exe", "plugin.dll")
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn report() {
+        let report = Report::of(
+            "app.exe",
+            vec![Node {
+                not_found: true,
+                ..Node::named("zlib1.dll")
+            }],
+            vec![Edge::import("app.exe", "zlib1.dll")],
+        );
+
+        let value: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "version": 1,
+                "root": "app.exe",
+                "nodes": [{
+                    "name": "zlib1.dll",
+                    "path": null,
+                    "type": null,
+                    "architecture": null,
+                    "size": null,
+                    "version": null,
+                    "sha256": null,
+                    "api_set_host": null,
+                    "not_found": true,
+                    "ignored": false
+                }],
+                "edges": [{ "importer": "app.exe", "imported": "zlib1.dll", "kind": "import" }],
+                "cycles": []
+            })
+        );
+
+        let bound = Node {
+            bound_imports: vec![BoundImport {
+                name: "zlib1.dll".to_owned(),
+                timestamp: 0x5f5e100,
+            }],
+            ..Node::named("app.exe")
+        };
+        assert_eq!(
+            serde_json::to_value(&bound).unwrap()["bound_imports"],
+            serde_json::json!([{ "name": "zlib1.dll", "timestamp": 100000000 }])
+        );
+
+        assert_eq!(
+            report.to_dot(),
+            concat!(
+                "digraph \"app.exe\" {\n",
+                "    node [shape=box, style=filled];\n",
+                "    \"zlib1.dll\" [fillcolor=white, color=red];\n",
+                "    \"app.exe\" -> \"zlib1.dll\";\n",
+                "}\n"
+            )
+        );
+        assert_eq!(quote(r#"C:\"App""#), r#""C:\\\"App\"""#);
+
+        // The document is well formed and the attributes are typed
+        let graphml = report.to_graphml();
+        let document = roxmltree::Document::parse(&graphml).unwrap();
+        let node = document.descendants().find(|node| node.has_tag_name("node")).unwrap();
+        assert_eq!(node.attribute("id"), Some("zlib1.dll"));
+        let data: Vec<_> = node
+            .children()
+            .filter(|data| data.has_tag_name("data"))
+            .map(|data| (data.attribute("key").unwrap(), data.text().unwrap()))
+            .collect();
+        assert_eq!(data, vec![("not_found", "true"), ("ignored", "false")]);
+        let size_key = r#"<key id="size" for="node" attr.name="size" attr.type="long"/>"#;
+        assert!(graphml.contains(size_key));
+        assert_eq!(escape_xml(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+
+        let xml = report.to_xml();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+        let node = document.descendants().find(|node| node.has_tag_name("node")).unwrap();
+        assert_eq!(node.attribute("name"), Some("zlib1.dll"));
+        assert_eq!(node.attribute("not_found"), Some("true"));
+        assert_eq!(node.attribute("path"), None);
+        let edge = document.descendants().find(|node| node.has_tag_name("edge")).unwrap();
+        assert_eq!(edge.attribute("kind"), Some("import"));
+
+        let record = Record::Edge(Edge::import("app.exe", "zlib1.dll"));
+        assert_eq!(
+            record.to_json(),
+            r#"{"record":"edge","importer":"app.exe","imported":"zlib1.dll","kind":"import"}"#
+        );
+
+        // The report cannot end the script element it is embedded in
+        let html = Report {
+            root: "</script>.exe".to_owned(),
+            ..report
+        }
+        .to_html();
+        assert!(html.contains(r#"const report = {"version":1,"root":"<\/script>.exe","#));
+        assert_eq!(html.matches("</script>").count(), 1);
+    }
+
+    #[test]
+    fn dot() {
+        // Nodes are filled with the color of their type, delay loads are gray
+        assert_eq!(
+            sample().to_dot(),
+            concat!(
+                "digraph \"app.exe\" {\n",
+                "    node [shape=box, style=filled];\n",
+                r#"    "app.exe" [fillcolor=lightblue, tooltip="C:\\App\\app.exe"];"#,
+                "\n",
+                r#"    "kernel32.dll" [fillcolor=palegreen, "#,
+                r#"tooltip="C:\\Windows\\System32\\kernel32.dll"];"#,
+                "\n",
+                r#"    "plugin.dll" [fillcolor=white, style="filled,dashed"];"#,
+                "\n",
+                "    \"app.exe\" -> \"kernel32.dll\";\n",
+                "    \"app.exe\" -> \"plugin.dll\" [color=gray];\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn graphml() {
+        let graphml = sample().to_graphml();
+        let document = roxmltree::Document::parse(&graphml).unwrap();
+
+        // Each attribute of a node is typed by its key, the ones without a value are left out
+        let keys: Vec<_> = document
+            .descendants()
+            .filter(|key| key.has_tag_name("key"))
+            .map(|key| (key.attribute("id").unwrap(), key.attribute("attr.type").unwrap()))
+            .collect();
+        let node = document.descendants().find(|node| node.has_tag_name("node")).unwrap();
+        let data: Vec<_> = node
+            .children()
+            .filter(|data| data.has_tag_name("data"))
+            .map(|data| {
+                let key = data.attribute("key").unwrap();
+                let key_type = keys.iter().find(|(id, _)| *id == key).unwrap().1;
+                (key, key_type, data.text().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            data,
+            [
+                ("path", "string", r"C:\App\app.exe"),
+                ("type", "string", "user-dll"),
+                ("architecture", "string", "x64"),
+                ("size", "long", "1200"),
+                ("version", "string", "1.0.0.0"),
+                ("sha256", "string", "e3b0c442"),
+                ("not_found", "boolean", "false"),
+                ("ignored", "boolean", "false"),
+            ]
+        );
+
+        let edges: Vec<_> = document
+            .descendants()
+            .filter(|edge| edge.has_tag_name("edge"))
+            .map(|edge| {
+                let kind = edge.children().find(|data| data.has_tag_name("data")).unwrap();
+                (edge.attribute("target").unwrap(), kind.text().unwrap())
+            })
+            .collect();
+        assert_eq!(edges, [("kernel32.dll", "import"), ("plugin.dll", "delay")]);
+    }
+
+    #[test]
+    fn html() {
+        let report = sample();
+        let html = report.to_html();
+
+        // The page needs no server, the report is embedded as the JSON of --format json
+        let start = html.find("const report = ").unwrap() + "const report = ".len();
+        let end = start + html[start..].find(";\n").unwrap();
+        let embedded: serde_json::Value = serde_json::from_str(&html[start..end]).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(embedded, json);
+        assert!(!html.contains("<script src="));
+        assert!(html.contains(r#"<input id="search" type="search""#));
+    }
+
+    #[test]
+    fn records() {
+        let record = |event| serde_json::to_value(Record::of_event(&event)).unwrap();
+
+        // A node is printed as soon as the dll is searched, without waiting for the walk
+        let missing = record(WalkEvent::Missing { name: "zlib1.dll" });
+        assert_eq!(missing["record"], "node");
+        assert_eq!(missing["name"], "zlib1.dll");
+        assert_eq!(missing["not_found"], true);
+        assert_eq!(record(WalkEvent::Ignored { name: "plugin.dll" })["ignored"], true);
+
+        let unreadable = record(WalkEvent::ParseError {
+            name: "broken.dll",
+            path: std::path::Path::new(r"C:\App\broken.dll"),
+            error: "not a PE image",
+        });
+        assert_eq!(unreadable["type"], serde_json::Value::Null);
+        assert_eq!(unreadable["not_found"], false);
+        assert_eq!(unreadable["ignored"], false);
+
+        let edge = Record::of_event(&WalkEvent::EdgeVisited {
+            importer: "app.exe",
+            imported: "plugin.dll",
+            kind: DependencyKind::Delay,
+        });
+        assert_eq!(
+            edge.to_json(),
+            r#"{"record":"edge","importer":"app.exe","imported":"plugin.dll","kind":"delay"}"#
+        );
+    }
+
+    #[test]
+    fn xml() {
+        let report = Report {
+            cycles: vec![vec!["a.dll".to_owned(), "b.dll".to_owned(), "a.dll".to_owned()]],
+            ..sample()
+        };
+        let xml = report.to_xml();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+        let root = document.root_element();
+        assert_eq!((root.tag_name().name(), root.attribute("root")), ("report", Some("app.exe")));
+
+        // The attributes mirror the fields of the JSON report, the ones without a value are
+        // left out
+        let node = document.descendants().find(|node| node.has_tag_name("node")).unwrap();
+        let attributes: Vec<_> = node
+            .attributes()
+            .iter()
+            .map(|attribute| (attribute.name(), attribute.value()))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("name", "app.exe"),
+                ("path", r"C:\App\app.exe"),
+                ("type", "user-dll"),
+                ("architecture", "x64"),
+                ("size", "1200"),
+                ("version", "1.0.0.0"),
+                ("sha256", "e3b0c442"),
+                ("not_found", "false"),
+                ("ignored", "false"),
+            ]
+        );
+
+        let kinds: Vec<_> = document
+            .descendants()
+            .filter(|edge| edge.has_tag_name("edge"))
+            .map(|edge| edge.attribute("kind").unwrap())
+            .collect();
+        assert_eq!(kinds, ["import", "delay"]);
+        let cycle: Vec<_> = document
+            .descendants()
+            .filter(|dll| dll.has_tag_name("dll"))
+            .map(|dll| dll.attribute("name").unwrap())
+            .collect();
+        assert_eq!(cycle, ["a.dll", "b.dll", "a.dll"]);
+    }
+
+    #[test]
+    fn chains() {
+        let report = Report::of(
+            "app.exe",
+            Vec::new(),
+            vec![
+                Edge::import("app.exe", "foo.dll"),
+                Edge::import("app.exe", "bar.dll"),
+                Edge::import("foo.dll", "bar.dll"),
+                Edge::import("bar.dll", "foo.dll"),
+            ],
+        );
+
+        assert_eq!(
+            report.chains(None),
+            vec![vec!["app.exe", "bar.dll"], vec!["app.exe", "foo.dll"]]
+        );
+        assert_eq!(report.chains(Some(0)).len(), 0);
+
+        assert_eq!(
+            report.chains_to("FOO.dll", 10),
+            vec![vec!["app.exe", "foo.dll"], vec!["app.exe", "bar.dll", "foo.dll"]]
+        );
+        assert_eq!(report.shortest_chain("foo.dll"), Some(vec!["app.exe", "foo.dll"]));
+        assert_eq!(report.chains_to("app.exe", 10), vec![vec!["app.exe"]]);
+        assert!(report.chains_to("zlib1.dll", 10).is_empty());
+
+        // 2^30 chains lead to the last dll of a ladder of dlls importing both dlls below them
+        let mut ladder = Report {
+            edges: vec![Edge::import("app.exe", "a0.dll"), Edge::import("app.exe", "b0.dll")],
+            ..report
+        };
+        for level in 0..30 {
+            for importer in [format!("a{}.dll", level), format!("b{}.dll", level)] {
+                ladder.edges.push(Edge::import(&importer, &format!("a{}.dll", level + 1)));
+                ladder.edges.push(Edge::import(&importer, &format!("b{}.dll", level + 1)));
+            }
+        }
+        let chains = ladder.chains_to("a30.dll", 3);
+        assert_eq!(chains.len(), 3);
+        assert!(chains.iter().all(|chain| chain.len() == 32));
+        assert_eq!(ladder.chains(None).len(), 62);
+
+        assert_eq!(
+            find_cycles("app.exe", &report.edges),
+            vec![vec!["bar.dll", "foo.dll", "bar.dll"]]
+        );
+        let edges = [
+            Edge::import("app.exe", "zlib1.dll"),
+            Edge::import("ZLIB1.dll", "zlib1.dll"),
+        ];
+        assert_eq!(find_cycles("app.exe", &edges), vec![vec!["zlib1.dll", "zlib1.dll"]]);
+        assert!(find_cycles("app.exe", &edges[..1]).is_empty());
+    }
+
+    #[test]
+    fn table() {
+        let report = Report::of(
+            "app.exe",
+            vec![
+                Node {
+                    path: Some(r"C:\App\app.exe".to_owned()),
+                    dll_type: Some(DllType::User),
+                    architecture: Some("x64".to_owned()),
+                    size: Some(1200),
+                    version: Some("1.0.0.0".to_owned()),
+                    ..Node::named("app.exe")
+                },
+                Node {
+                    not_found: true,
+                    ..Node::named("zlib1.dll")
+                },
+            ],
+            Vec::new(),
+        );
+
+        // Columns are as wide as their widest cell, trailing blanks are trimmed
+        assert_eq!(
+            report.to_table(false),
+            concat!(
+                "Name       Size  Version  Arch  Type       Path\n",
+                "app.exe    1200  1.0.0.0  x64   user-dll   C:\\App\\app.exe\n",
+                "zlib1.dll                       NOT FOUND\n",
+            )
+        );
+
+        // Pipes would end the cells of Markdown
+        let report = Report::of("a|b.exe", vec![Node::named("a|b.exe")], Vec::new());
+        assert_eq!(
+            report.to_table(true),
+            concat!(
+                "| Name | Size | Version | Arch | Type | Path |\n",
+                "|---|---:|---|---|---|---|\n",
+                "| a\\|b.exe |  |  |  | unreadable |  |\n",
+            )
+        );
+    }
+
+    #[test]
+    fn spdx() {
+        let node = |name: &str, dll_type: DllType, api_set_host: Option<&str>| Node {
+            path: (dll_type != DllType::Umbrella).then(|| format!(r"C:\App\{}", name)),
+            dll_type: Some(dll_type),
+            sha256: (dll_type == DllType::User).then(|| "e3b0c442".to_owned()),
+            api_set_host: api_set_host.map(str::to_owned),
+            ..Node::named(name)
+        };
+        let report = Report::of(
+            "app.exe",
+            vec![
+                node("api-ms-win-core-file-l1-1-0.dll", DllType::Umbrella, Some("kernelbase.dll")),
+                node("app.exe", DllType::User, None),
+                node("kernelbase.dll", DllType::System, None),
+            ],
+            vec![
+                Edge::import("api-ms-win-core-file-l1-1-0.dll", "kernelbase.dll"),
+                Edge::import("app.exe", "api-ms-win-core-file-l1-1-0.dll"),
+                Edge::import("app.exe", "kernelbase.dll"),
+            ],
+        );
+
+        let value: serde_json::Value = serde_json::from_str(&report.to_spdx(86400)).unwrap();
+        assert_eq!(value["creationInfo"]["created"], "1970-01-02T00:00:00Z");
+        assert_eq!(value["packages"][0]["SPDXID"], "SPDXRef-1-app.exe");
+        assert_eq!(value["packages"][1]["packageFileName"], r"C:\App\kernelbase.dll");
+        assert_eq!(
+            value["packages"][0]["checksums"],
+            serde_json::json!([{ "algorithm": "SHA256", "checksumValue": "e3b0c442" }])
+        );
+        assert_eq!(value["packages"][1]["checksums"], serde_json::Value::Null);
+        assert_eq!(
+            value["relationships"],
+            serde_json::json!([
+                {
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": "SPDXRef-1-app.exe"
+                },
+                {
+                    "spdxElementId": "SPDXRef-1-app.exe",
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": "SPDXRef-2-kernelbase.dll"
+                }
+            ])
+        );
+    }
+}
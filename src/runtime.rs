@@ -0,0 +1,233 @@
+use dllwalk_core::{pe::Architecture, DllDatabase, DllType};
+
+/// A redistributable the dlls of an application may need installed
+#[derive(Debug, PartialEq, Eq)]
+pub struct Runtime {
+    pub name: &'static str,
+    /// How to install it, or why it cannot be
+    pub note: Option<&'static str>,
+    /// Lowercase names of its dlls, a `*` matches any characters
+    patterns: &'static [&'static str],
+}
+
+// Debug runtimes come first, their names start like the release ones
+static RUNTIMES: [Runtime; 14] = [
+    Runtime {
+        name: "Visual C++ 2015-2022 debug runtime",
+        note: Some("Installed with Visual Studio only and not redistributable, ship a release"),
+        patterns: &[
+            "msvcp140d.dll",
+            "msvcp140d_*.dll",
+            "vcruntime140d.dll",
+            "vcruntime140_1d.dll",
+            "concrt140d.dll",
+            "vccorlib140d.dll",
+            "vcomp140d.dll",
+            "mfc140d.dll",
+            "mfc140ud.dll",
+            "ucrtbased.dll",
+        ],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ 2015-2022 Redistributable",
+        note: None,
+        patterns: &[
+            "msvcp140.dll",
+            "msvcp140_*.dll",
+            "vcruntime140.dll",
+            "vcruntime140_1.dll",
+            "concrt140.dll",
+            "vccorlib140.dll",
+            "vcomp140.dll",
+            "vcamp140.dll",
+            "mfc140*.dll",
+            "mfcm140*.dll",
+        ],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ 2013 Redistributable",
+        note: None,
+        patterns: &[
+            "msvcr120.dll",
+            "msvcp120.dll",
+            "vcomp120.dll",
+            "vccorlib120.dll",
+            "mfc120*.dll",
+        ],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ 2012 Redistributable",
+        note: None,
+        patterns: &[
+            "msvcr110.dll",
+            "msvcp110.dll",
+            "vcomp110.dll",
+            "vccorlib110.dll",
+            "mfc110*.dll",
+        ],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ 2010 Redistributable",
+        note: None,
+        patterns: &["msvcr100.dll", "msvcp100.dll", "vcomp100.dll", "mfc100*.dll"],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ 2008 Redistributable",
+        note: Some("Installed as side-by-side assemblies, the manifest selects the version"),
+        patterns: &["msvcr90.dll", "msvcp90.dll", "msvcm90.dll", "vcomp90.dll", "mfc90*.dll"],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ 2005 Redistributable",
+        note: Some("Installed as side-by-side assemblies, the manifest selects the version"),
+        patterns: &["msvcr80.dll", "msvcp80.dll", "msvcm80.dll", "vcomp.dll", "mfc80*.dll"],
+    },
+    Runtime {
+        name: "Microsoft Visual C++ .NET 2003 runtime",
+        note: Some("Has no installer, ship the dlls with the application"),
+        patterns: &["msvcr71.dll", "msvcp71.dll", "mfc71*.dll"],
+    },
+    Runtime {
+        name: "Universal C Runtime",
+        note: Some(
+            "Part of Windows 10 and later, installed by update KB2999226 or the Visual C++ \
+             Redistributable on earlier versions",
+        ),
+        patterns: &["ucrtbase.dll", "api-ms-win-crt-*.dll"],
+    },
+    Runtime {
+        name: ".NET Framework",
+        note: Some("Managed executable, install the version of the .NET Framework it targets"),
+        patterns: &["mscoree.dll"],
+    },
+    Runtime {
+        name: ".NET runtime",
+        note: Some("Install the .NET runtime the application targets, unless it is self-contained"),
+        patterns: &["hostfxr.dll", "hostpolicy.dll", "coreclr.dll"],
+    },
+    Runtime {
+        name: "DirectX End-User Runtime (June 2010)",
+        note: Some("Windows does not include the D3DX, XInput 1.3 and XAudio 2.7 dlls"),
+        patterns: &[
+            "d3dx9_*.dll",
+            "d3dx10_*.dll",
+            "d3dx11_*.dll",
+            "d3dcompiler_33.dll",
+            "d3dcompiler_34.dll",
+            "d3dcompiler_35.dll",
+            "d3dcompiler_36.dll",
+            "d3dcompiler_37.dll",
+            "d3dcompiler_38.dll",
+            "d3dcompiler_39.dll",
+            "d3dcompiler_40.dll",
+            "d3dcompiler_41.dll",
+            "d3dcompiler_42.dll",
+            "d3dcompiler_43.dll",
+            "xinput1_1.dll",
+            "xinput1_2.dll",
+            "xinput1_3.dll",
+            "xaudio2_0.dll",
+            "xaudio2_1.dll",
+            "xaudio2_2.dll",
+            "xaudio2_3.dll",
+            "xaudio2_4.dll",
+            "xaudio2_5.dll",
+            "xaudio2_6.dll",
+            "xaudio2_7.dll",
+            "x3daudio1_*.dll",
+            "xactengine*.dll",
+        ],
+    },
+    Runtime {
+        name: "Vulkan runtime",
+        note: Some("Installed with the graphics driver"),
+        patterns: &["vulkan-1.dll"],
+    },
+    Runtime {
+        name: "OpenCL runtime",
+        note: Some("Installed with the graphics driver"),
+        patterns: &["opencl.dll"],
+    },
+];
+
+/// A runtime needed by the dlls of an application
+#[derive(Debug, PartialEq, Eq)]
+pub struct Requirement {
+    pub runtime: &'static Runtime,
+    /// Architecture of its dlls, or of the application when they are missing
+    pub architecture: Option<Architecture>,
+    pub dlls: Vec<String>,
+    /// Whether all its dlls were found next to the application, which then needs no install
+    pub app_local: bool,
+}
+
+/// Runtimes needed by the dlls loaded by `root`, found or missing, in the order of
+/// `RUNTIMES`
+pub fn requirements(database: &DllDatabase, root: &str) -> Vec<Requirement> {
+    let root_architecture = database.get_dll_info(root).and_then(|info| info.architecture());
+
+    let mut dlls = database.get_all_dlls();
+    dlls.sort_by_key(|name| name.to_lowercase());
+
+    let mut requirements: Vec<Requirement> = Vec::new();
+    for runtime in &RUNTIMES {
+        let runtime_dlls: Vec<String> = dlls
+            .iter()
+            .filter(|name| !name.eq_ignore_ascii_case(root) && find_runtime(name) == Some(runtime))
+            .cloned()
+            .collect();
+        if runtime_dlls.is_empty() {
+            continue;
+        }
+
+        let infos: Vec<_> = runtime_dlls
+            .iter()
+            .map(|name| database.get_dll_info(name))
+            .collect();
+        let architecture = infos
+            .iter()
+            .flatten()
+            .find_map(|info| info.architecture())
+            .or(root_architecture);
+        let app_local = infos
+            .iter()
+            .all(|info| info.map_or(false, |info| info.dll_type == DllType::User));
+
+        requirements.push(Requirement {
+            runtime,
+            architecture,
+            dlls: runtime_dlls,
+            app_local,
+        });
+    }
+    requirements
+}
+
+fn find_runtime(dll: &str) -> Option<&'static Runtime> {
+    let dll = dll.to_lowercase();
+    RUNTIMES.iter().find(|runtime| {
+        runtime.patterns.iter().any(|pattern| match pattern.split_once('*') {
+            Some((prefix, suffix)) => {
+                dll.len() >= prefix.len() + suffix.len()
+                    && dll.starts_with(prefix)
+                    && dll.ends_with(suffix)
+            }
+            None => dll == *pattern,
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn find_runtime() {
+        let name = |dll: &str| super::find_runtime(dll).map(|runtime| runtime.name);
+        assert_eq!(name("MSVCP140.dll"), Some("Microsoft Visual C++ 2015-2022 Redistributable"));
+        assert_eq!(name("msvcp140_1.dll"), Some("Microsoft Visual C++ 2015-2022 Redistributable"));
+        assert_eq!(name("vcruntime140d.dll"), Some("Visual C++ 2015-2022 debug runtime"));
+        assert_eq!(name("mfc140ud.dll"), Some("Visual C++ 2015-2022 debug runtime"));
+        assert_eq!(name("api-ms-win-crt-runtime-l1-1-0.dll"), Some("Universal C Runtime"));
+        assert_eq!(name("d3dx9_43.dll"), Some("DirectX End-User Runtime (June 2010)"));
+        assert_eq!(name("d3d11.dll"), None);
+        assert_eq!(name("xinput1_4.dll"), None);
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::Path;
+
+use dllwalk_core::DllDatabase;
+use serde_json::{json, Value};
+
+const MISSING_DLL: &str = "DW001";
+const ARCHITECTURE_MISMATCH: &str = "DW002";
+const DLL_HIJACKING: &str = "DW003";
+
+// Identifier, name, level and description of the rules
+const RULES: [(&str, &str, &str, &str); 3] = [
+    (MISSING_DLL, "MissingDll", "error", "An imported dll is not found"),
+    (
+        ARCHITECTURE_MISMATCH,
+        "ArchitectureMismatch",
+        "error",
+        "An imported dll is built for another architecture",
+    ),
+    (
+        DLL_HIJACKING,
+        "DllHijacking",
+        "warning",
+        "A standard user can plant a dll that is loaded instead of a dependency",
+    ),
+];
+
+/// SARIF 2.1.0 log of the problems found in the dependencies of `file`: missing dlls,
+/// architecture mismatches and directories where a dll can be planted. Every result is
+/// located in `file`, the imports have no line.
+pub fn to_sarif(database: &DllDatabase, file: &Path) -> String {
+    let uri = to_uri(file);
+    let mut results = Vec::new();
+
+    let mut missing: Vec<String> = database
+        .get_all_dlls()
+        .into_iter()
+        .filter(|name| database.is_missing(name))
+        .collect();
+    missing.sort_by_key(|name| name.to_lowercase());
+    for name in missing {
+        let message = format!("{} is not found", name);
+        results.push(result(MISSING_DLL, &message, &uri));
+    }
+
+    let mut mismatches = database.get_architecture_mismatches();
+    mismatches.sort();
+    for (importer, imported) in mismatches {
+        let message = format!(
+            "{} imports {} which targets a different architecture",
+            importer, imported
+        );
+        results.push(result(ARCHITECTURE_MISMATCH, &message, &uri));
+    }
+
+    // Unknown permissions are left out, like the checks that do not apply
+    let mut hijackable = database.get_hijackable_dlls().unwrap_or_default();
+    hijackable.sort_by_key(|(name, _)| name.to_lowercase());
+    for (name, directory) in hijackable {
+        let message = format!(
+            "{} can be planted in {} by a standard user",
+            name,
+            directory.to_string_lossy()
+        );
+        results.push(result(DLL_HIJACKING, &message, &uri));
+    }
+
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|(id, name, level, description)| {
+            json!({
+                "id": id,
+                "name": name,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": level },
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "dllwalk",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }]
+    });
+    serde_json::to_string_pretty(&log).expect("Failed to serialize the SARIF log")
+}
+
+fn result(rule_id: &str, message: &str, uri: &str) -> Value {
+    let level = RULES
+        .iter()
+        .find(|(id, ..)| *id == rule_id)
+        .map_or("warning", |(_, _, level, _)| level);
+
+    json!({
+        "ruleId": rule_id,
+        "level": level,
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": { "artifactLocation": { "uri": uri } }
+        }],
+    })
+}
+
+// Relative paths stay relative, to the root of the repository when dllwalk runs there in
+// CI, absolute ones become file URIs
+fn to_uri(path: &Path) -> String {
+    let mut uri = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '\\' => uri.push('/'),
+            '%' | ' ' | '#' | '?' => uri.push_str(&format!("%{:02X}", c as u32)),
+            _ => uri.push(c),
+        }
+    }
+
+    let drive = uri.as_bytes().get(1) == Some(&b':');
+    if drive {
+        format!("file:///{}", uri)
+    } else if uri.starts_with("//") {
+        format!("file:{}", uri)
+    } else if uri.starts_with('/') {
+        format!("file://{}", uri)
+    } else {
+        uri
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dllwalk_core::inventory::InventoryFile;
+
+    use super::*;
+    use crate::test::{database, file};
+
+    #[test]
+    fn to_sarif() {
+        let database = database(vec![
+            (
+                "app.exe",
+                InventoryFile {
+                    machine: Some(0x8664),
+                    ..file(&["zlib1.dll", "missing.dll"])
+                },
+            ),
+            (
+                "zlib1.dll",
+                InventoryFile {
+                    machine: Some(0x14c),
+                    ..file(&[])
+                },
+            ),
+        ]);
+
+        let sarif = super::to_sarif(&database, Path::new("app.exe"));
+        let log: Value = serde_json::from_str(&sarif).unwrap();
+        let run = &log["runs"][0];
+        let rules: Vec<&Value> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| &rule["id"])
+            .collect();
+        assert_eq!(rules, ["DW001", "DW002", "DW003"]);
+
+        // Which directories a dll can be planted in depends on the host running the test
+        let results: Vec<(&Value, &Value, &Value)> = run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|result| result["ruleId"] != DLL_HIJACKING)
+            .map(|result| (&result["ruleId"], &result["level"], &result["message"]["text"]))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (&json!("DW001"), &json!("error"), &json!("missing.dll is not found")),
+                (
+                    &json!("DW002"),
+                    &json!("error"),
+                    &json!("app.exe imports zlib1.dll which targets a different architecture")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn to_uri() {
+        let uri = |path: &str| super::to_uri(Path::new(path));
+        assert_eq!(uri(r"C:\Program Files\App\app.exe"), "file:///C:/Program%20Files/App/app.exe");
+        assert_eq!(uri(r"\\server\share\app.exe"), "file://server/share/app.exe");
+        assert_eq!(uri("/opt/app/app.exe"), "file:///opt/app/app.exe");
+        assert_eq!(uri(r"build\release\app#1.exe"), "build/release/app%231.exe");
+
+        let result = result(MISSING_DLL, "zlib1.dll is not found", "app.exe");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "app.exe"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque};
+
+use dllwalk_core::DllType;
+
+use crate::report::Report;
+
+/// Summary of the dependencies of a file, printed by the stats command
+#[derive(Debug, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of dlls of each type, sorted by type, the root excluded
+    pub types: Vec<(DllType, usize)>,
+    pub missing: usize,
+    /// Dlls found but not parsed, or ignored by .dllwalk.toml
+    pub unresolved: usize,
+    /// Size in bytes of the files of the walk, the root included
+    pub total_size: u64,
+    /// Shortest import chain to the dll the furthest from the root, its length is the
+    /// maximum depth
+    pub longest_chain: Vec<String>,
+}
+
+impl Stats {
+    pub fn new(report: &Report) -> Self {
+        let mut types: BTreeMap<DllType, usize> = BTreeMap::new();
+        let mut missing = 0;
+        let mut unresolved = 0;
+        for node in report.nodes.iter().filter(|node| node.name != report.root) {
+            match node.dll_type {
+                Some(dll_type) => *types.entry(dll_type).or_default() += 1,
+                None if node.not_found => missing += 1,
+                None => unresolved += 1,
+            }
+        }
+
+        Stats {
+            types: types.into_iter().collect(),
+            missing,
+            unresolved,
+            total_size: report.nodes.iter().filter_map(|node| node.size).sum(),
+            longest_chain: longest_chain(report),
+        }
+    }
+
+    /// Number of imports from the root to the dll the furthest from it
+    pub fn max_depth(&self) -> usize {
+        self.longest_chain.len().saturating_sub(1)
+    }
+}
+
+// Breadth first, so that each dll is reached through its shortest chain. Dll names are
+// compared ignoring case, importers may spell them differently.
+fn longest_chain(report: &Report) -> Vec<String> {
+    let mut imports: HashMap<String, Vec<&str>> = HashMap::new();
+    for edge in &report.edges {
+        imports.entry(edge.importer.to_lowercase()).or_default().push(&edge.imported);
+    }
+
+    let mut importers: HashMap<String, Option<&str>> = HashMap::new();
+    importers.insert(report.root.to_lowercase(), None);
+    let mut queue = VecDeque::from([report.root.as_str()]);
+    let mut last = report.root.as_str();
+    while let Some(name) = queue.pop_front() {
+        last = name;
+        for &imported in imports.get(&name.to_lowercase()).into_iter().flatten() {
+            if let Entry::Vacant(entry) = importers.entry(imported.to_lowercase()) {
+                entry.insert(Some(name));
+                queue.push_back(imported);
+            }
+        }
+    }
+
+    let mut chain = vec![last.to_owned()];
+    while let Some(Some(importer)) = importers.get(&chain[chain.len() - 1].to_lowercase()) {
+        chain.push(importer.to_string());
+    }
+    chain.reverse();
+    chain
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::report::{Edge, Node};
+
+    #[test]
+    fn stats() {
+        let node = |name: &str, dll_type: Option<DllType>, size: Option<u64>| Node {
+            path: size.map(|_| format!(r"C:\App\{}", name)),
+            dll_type,
+            size,
+            not_found: dll_type.is_none() && name != "broken.dll",
+            ..Node::named(name)
+        };
+        let report = Report::of(
+            "app.exe",
+            vec![
+                node("app.exe", Some(DllType::User), Some(100)),
+                node("broken.dll", None, None),
+                node("kernel32.dll", Some(DllType::Known), Some(1000)),
+                node("msvcr120.dll", None, None),
+                node("qt5core.dll", Some(DllType::User), Some(10)),
+                node("zlib1.dll", Some(DllType::User), Some(1)),
+            ],
+            vec![
+                Edge::import("app.exe", "kernel32.dll"),
+                Edge::import("app.exe", "QT5CORE.dll"),
+                Edge::import("qt5core.dll", "kernel32.dll"),
+                Edge::import("qt5core.dll", "msvcr120.dll"),
+                Edge::import("qt5core.dll", "zlib1.dll"),
+                Edge::import("zlib1.dll", "broken.dll"),
+            ],
+        );
+
+        let stats = Stats::new(&report);
+        assert_eq!(stats.types, vec![(DllType::User, 2), (DllType::Known, 1)]);
+        assert_eq!((stats.missing, stats.unresolved), (1, 1));
+        assert_eq!(stats.total_size, 1111);
+        assert_eq!(stats.longest_chain, ["app.exe", "QT5CORE.dll", "zlib1.dll", "broken.dll"]);
+        assert_eq!(stats.max_depth(), 3);
+    }
+}
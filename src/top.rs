@@ -0,0 +1,95 @@
+use dllwalk_core::{DllDatabase, DllType};
+
+use crate::filter;
+
+/// What a dependency of a file costs, on disk and mapped in memory
+#[derive(Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub dll_type: DllType,
+    pub file_size: u64,
+    /// SizeOfImage of its optional header
+    pub image_size: u64,
+}
+
+/// Number and sizes of a set of dlls
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Total {
+    pub dlls: usize,
+    pub file_size: u64,
+    pub image_size: u64,
+}
+
+impl Total {
+    pub fn new<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Self {
+        entries.into_iter().fold(Total::default(), |total, entry| Total {
+            dlls: total.dlls + 1,
+            file_size: total.file_size + entry.file_size,
+            image_size: total.image_size + entry.image_size,
+        })
+    }
+}
+
+/// Dlls loaded by `root` that were read, largest first by image size when `by_image`,
+/// by file size otherwise. Missing dlls and the ones of an inventory have no size.
+pub fn entries(database: &DllDatabase, root: &str, by_image: bool) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = database
+        .get_all_dlls()
+        .into_iter()
+        .filter(|name| !name.eq_ignore_ascii_case(root))
+        .filter_map(|name| {
+            let info = database.get_dll_info(&name)?;
+            Some(Entry {
+                file_size: info.size?,
+                image_size: info.file.size_of_image as u64,
+                dll_type: info.dll_type,
+                name,
+            })
+        })
+        .collect();
+
+    entries.sort_by(|a, b| {
+        let (a_size, b_size) = if by_image {
+            (a.image_size, b.image_size)
+        } else {
+            (a.file_size, b.file_size)
+        };
+        b_size
+            .cmp(&a_size)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    entries
+}
+
+/// Totals of the dlls shipped with the application and of the ones of Windows, whose
+/// images are shared with the other processes
+pub fn totals(entries: &[Entry]) -> (Total, Total) {
+    let (system, application): (Vec<&Entry>, Vec<&Entry>) =
+        entries.iter().partition(|entry| filter::is_system(entry.dll_type));
+    (Total::new(application), Total::new(system))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn totals() {
+        let entry = |name: &str, dll_type, file_size, image_size| Entry {
+            name: name.to_owned(),
+            dll_type,
+            file_size,
+            image_size,
+        };
+        let entries = [
+            entry("qt5core.dll", DllType::User, 6000, 7000),
+            entry("kernel32.dll", DllType::Known, 700, 800),
+            entry("zlib1.dll", DllType::User, 90, 100),
+        ];
+
+        let (application, system) = super::totals(&entries);
+        assert_eq!(application, Total { dlls: 2, file_size: 6090, image_size: 7100 });
+        assert_eq!(system, Total { dlls: 1, file_size: 700, image_size: 800 });
+        assert_eq!(Total::new(&entries).file_size, 6790);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+
+use dllwalk_core::DllDatabase;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{color, report::Report};
+
+// Exports listed in the details of a dll, the larger system dlls have thousands
+const MAX_EXPORTS: usize = 200;
+
+/// Browses the dependency tree of a report in the terminal, until q or Escape is pressed
+pub fn run(database: &DllDatabase, report: &Report) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = Browser::new(database, report).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+struct Browser<'a> {
+    database: &'a DllDatabase,
+    report: &'a Report,
+    imports: HashMap<&'a str, Vec<&'a str>>,
+    /// Chains of the dlls whose imports are shown, the same dll may be expanded under one
+    /// importer and not another
+    expanded: HashSet<Vec<&'a str>>,
+    rows: Vec<Vec<&'a str>>,
+    state: ListState,
+    /// Text typed after /, until Enter or Escape
+    search: Option<String>,
+    matches: Vec<&'a str>,
+    next_match: usize,
+    details_scroll: u16,
+}
+
+impl<'a> Browser<'a> {
+    fn new(database: &'a DllDatabase, report: &'a Report) -> Self {
+        let mut imports: HashMap<&str, Vec<&str>> = HashMap::new();
+        for edge in &report.edges {
+            imports.entry(&edge.importer).or_default().push(&edge.imported);
+        }
+
+        let mut browser = Browser {
+            database,
+            report,
+            imports,
+            expanded: HashSet::from([vec![report.root.as_str()]]),
+            rows: Vec::new(),
+            state: ListState::default().with_selected(Some(0)),
+            search: None,
+            matches: Vec::new(),
+            next_match: 0,
+            details_scroll: 0,
+        };
+        browser.update_rows();
+        browser
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            if let Some(search) = &mut self.search {
+                match key.code {
+                    KeyCode::Char(c) => search.push(c),
+                    KeyCode::Backspace => {
+                        search.pop();
+                    }
+                    KeyCode::Enter => {
+                        let text = search.to_lowercase();
+                        self.search = None;
+                        self.find(&text);
+                    }
+                    KeyCode::Esc => self.search = None,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.select_next(),
+                KeyCode::Right | KeyCode::Enter | KeyCode::Char('l') => self.expand(),
+                KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+                KeyCode::Char('/') => self.search = Some(String::new()),
+                KeyCode::Char('n') => self.select_next_match(),
+                KeyCode::PageDown => self.details_scroll = self.details_scroll.saturating_add(10),
+                KeyCode::PageUp => self.details_scroll = self.details_scroll.saturating_sub(10),
+                _ => {}
+            }
+        }
+    }
+
+    fn update_rows(&mut self) {
+        self.rows = visible_rows(&self.imports, &self.report.root, &self.expanded);
+    }
+
+    fn selected(&self) -> &[&'a str] {
+        &self.rows[self.state.selected().unwrap_or(0)]
+    }
+
+    fn select(&mut self, index: usize) {
+        self.state.select(Some(index));
+        self.details_scroll = 0;
+    }
+
+    fn select_previous(&mut self) {
+        let index = self.state.selected().unwrap_or(0);
+        self.select(index.saturating_sub(1));
+    }
+
+    fn select_next(&mut self) {
+        let index = self.state.selected().unwrap_or(0);
+        self.select((index + 1).min(self.rows.len() - 1));
+    }
+
+    fn expand(&mut self) {
+        let chain = self.selected().to_vec();
+        self.expanded.insert(chain);
+        self.update_rows();
+    }
+
+    // Collapses the selected dll, or its importer when it is not expanded
+    fn collapse(&mut self) {
+        let mut chain = self.selected().to_vec();
+        if !self.expanded.contains(&chain) && chain.len() > 1 {
+            chain.pop();
+        }
+        if chain.len() > 1 {
+            self.expanded.remove(&chain);
+        }
+
+        self.update_rows();
+        let index = self.rows.iter().position(|row| *row == chain).unwrap_or(0);
+        self.select(index);
+    }
+
+    fn find(&mut self, text: &str) {
+        self.matches = self
+            .report
+            .nodes
+            .iter()
+            .map(|node| node.name.as_str())
+            .filter(|name| name.to_lowercase().contains(text))
+            .collect();
+        self.next_match = 0;
+        self.select_next_match();
+    }
+
+    // Expands the shortest chain leading to the next match and selects it
+    fn select_next_match(&mut self) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let name = self.matches[self.next_match % self.matches.len()];
+        self.next_match += 1;
+
+        let report = self.report;
+        let chain = match report.shortest_chain(name) {
+            Some(chain) => chain,
+            None => return,
+        };
+        for length in 1..chain.len() {
+            self.expanded.insert(chain[..length].to_vec());
+        }
+
+        self.update_rows();
+        if let Some(index) = self.rows.iter().position(|row| *row == chain) {
+            self.select(index);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree, details] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(main);
+
+        let items: Vec<ListItem> = self.rows.iter().map(|row| self.tree_item(row)).collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(self.report.root.as_str()))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, tree, &mut self.state);
+
+        let selected = self.selected().last().copied().unwrap_or_default();
+        let paragraph = Paragraph::new(self.details(selected))
+            .block(Block::default().borders(Borders::ALL).title(selected))
+            .wrap(Wrap { trim: false })
+            .scroll((self.details_scroll, 0));
+        frame.render_widget(paragraph, details);
+
+        let status_text = match &self.search {
+            Some(search) => format!("/{}", search),
+            None => "↑↓ move  → expand  ← collapse  / search  n next match  \
+                     PgUp PgDn details  q quit"
+                .to_owned(),
+        };
+        frame.render_widget(Paragraph::new(status_text), status);
+    }
+
+    fn tree_item(&self, chain: &[&'a str]) -> ListItem<'a> {
+        let name = chain[chain.len() - 1];
+        let has_imports = self.imports.get(name).map_or(false, |imports| !imports.is_empty());
+        let marker = if chain[..chain.len() - 1].contains(&name) {
+            "↻ "
+        } else if !has_imports {
+            "  "
+        } else if self.expanded.contains(chain) {
+            "▾ "
+        } else {
+            "▸ "
+        };
+
+        let dll_type = self.database.get_dll_info(name).map(|info| info.dll_type);
+        let missing = self.database.is_missing(name);
+        let color = Color::Indexed(color::palette_index(dll_type, missing));
+        let mut spans = vec![
+            Span::raw("  ".repeat(chain.len() - 1)),
+            Span::raw(marker),
+            Span::styled(name, Style::default().fg(color)),
+        ];
+        if missing {
+            spans.push(Span::raw(" [NOT FOUND]"));
+        }
+        ListItem::new(Line::from(spans))
+    }
+
+    fn details(&self, name: &str) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let node = self.report.nodes.iter().find(|node| node.name == name);
+        if let Some(node) = node {
+            let mut field = |label: &str, value: Option<String>| {
+                if let Some(value) = value {
+                    lines.push(Line::from(format!("{}: {}", label, value)));
+                }
+            };
+            field("Path", node.path.clone());
+            field("Type", node.dll_type.map(|dll_type| dll_type.to_string()));
+            field("Architecture", node.architecture.clone());
+            field("Version", node.version.clone());
+            field("Size", node.size.map(|size| format!("{} bytes", size)));
+            field("Api set host", node.api_set_host.clone());
+            field("Status", node.not_found.then(|| "not found".to_owned()));
+            field("Status", node.ignored.then(|| "ignored".to_owned()));
+        }
+
+        let info = self.database.get_dll_info(name);
+        let imports = self.imports.get(name).map(Vec::as_slice).unwrap_or_default();
+        lines.push(Line::from(""));
+        lines.push(Line::from(format!("Imports ({})", imports.len())));
+        for imported in imports {
+            let count = info.and_then(|info| info.file.imported_symbol_count(imported));
+            lines.push(Line::from(match count {
+                Some(count) => format!("  {} ({} functions)", imported, count),
+                None => format!("  {}", imported),
+            }));
+        }
+
+        if let Some(info) = info.filter(|info| info.has_headers()) {
+            let exports = &info.file.exports.exports;
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!("Exports ({})", exports.len())));
+            for export in exports.iter().take(MAX_EXPORTS) {
+                let name = match &export.name {
+                    Some(name) => format!("  {} @{}", name, export.ordinal),
+                    None => format!("  @{}", export.ordinal),
+                };
+                lines.push(Line::from(match &export.forwarder {
+                    Some(forwarder) => format!("{} → {}", name, forwarder),
+                    None => name,
+                }));
+            }
+            if exports.len() > MAX_EXPORTS {
+                lines.push(Line::from(format!("  … {} more", exports.len() - MAX_EXPORTS)));
+            }
+        }
+
+        lines
+    }
+}
+
+// Chains of the dlls shown in the tree, in order: the root, then the imports of each
+// expanded dll below it. A dll importing itself through a cycle is not expanded again.
+fn visible_rows<'a>(
+    imports: &HashMap<&'a str, Vec<&'a str>>,
+    root: &'a str,
+    expanded: &HashSet<Vec<&'a str>>,
+) -> Vec<Vec<&'a str>> {
+    let mut rows = Vec::new();
+    let mut stack = vec![vec![root]];
+    while let Some(chain) = stack.pop() {
+        let name = chain[chain.len() - 1];
+        let cycle = chain[..chain.len() - 1].contains(&name);
+        if expanded.contains(&chain) && !cycle {
+            for &imported in imports.get(name).into_iter().flatten().rev() {
+                let mut child = chain.clone();
+                child.push(imported);
+                stack.push(child);
+            }
+        }
+        rows.push(chain);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn visible_rows() {
+        let imports = HashMap::from([
+            ("app.exe", vec!["foo.dll", "bar.dll"]),
+            ("foo.dll", vec!["bar.dll"]),
+            ("bar.dll", vec!["foo.dll"]),
+        ]);
+
+        let mut expanded = HashSet::from([vec!["app.exe"]]);
+        let rows = super::visible_rows(&imports, "app.exe", &expanded);
+        assert_eq!(
+            rows,
+            vec![vec!["app.exe"], vec!["app.exe", "foo.dll"], vec!["app.exe", "bar.dll"]]
+        );
+
+        // foo.dll is only expanded below app.exe, the cycle back to it stops there
+        expanded.insert(vec!["app.exe", "foo.dll"]);
+        expanded.insert(vec!["app.exe", "foo.dll", "bar.dll"]);
+        expanded.insert(vec!["app.exe", "foo.dll", "bar.dll", "foo.dll"]);
+        let rows = super::visible_rows(&imports, "app.exe", &expanded);
+        assert_eq!(
+            rows,
+            vec![
+                vec!["app.exe"],
+                vec!["app.exe", "foo.dll"],
+                vec!["app.exe", "foo.dll", "bar.dll"],
+                vec!["app.exe", "foo.dll", "bar.dll", "foo.dll"],
+                vec!["app.exe", "bar.dll"],
+            ]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    ffi::OsString,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Names, modification times and sizes of the files of a directory, which change when a
+/// file is added, removed, replaced or rebuilt
+#[derive(Debug, PartialEq, Eq)]
+struct DirectoryState(Vec<(OsString, Option<SystemTime>, u64)>);
+
+impl DirectoryState {
+    fn read(directory: &Path) -> Self {
+        let mut files: Vec<_> = std::fs::read_dir(directory)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                Some((entry.file_name(), metadata.modified().ok(), metadata.len()))
+            })
+            .collect();
+        files.sort();
+        DirectoryState(files)
+    }
+}
+
+/// Runs `check`, then again whenever the files of `directory` change, until dllwalk is
+/// stopped
+pub fn run(directory: &Path, mut check: impl FnMut()) -> ! {
+    loop {
+        check();
+        wait_for_change(directory);
+    }
+}
+
+/// Blocks until the files of `directory` change, then until they stop changing so that
+/// a build or a copy in progress is not caught halfway
+fn wait_for_change(directory: &Path) {
+    let initial = DirectoryState::read(directory);
+    let mut state = DirectoryState::read(directory);
+    while state == initial {
+        std::thread::sleep(POLL_INTERVAL);
+        state = DirectoryState::read(directory);
+    }
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let next = DirectoryState::read(directory);
+        if next == state {
+            return;
+        }
+        state = next;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn directory_state() {
+        let directory = std::env::temp_dir().join("dllwalk-watch");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let empty = DirectoryState::read(&directory);
+        assert_eq!(empty, DirectoryState(Vec::new()));
+
+        std::fs::write(directory.join("zlib1.dll"), b"MZ").unwrap();
+        let state = DirectoryState::read(&directory);
+        assert_ne!(state, empty);
+        assert_eq!(state.0[0].2, 2);
+
+        std::fs::write(directory.join("zlib1.dll"), b"MZ\0").unwrap();
+        assert_ne!(DirectoryState::read(&directory), state);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
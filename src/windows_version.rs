@@ -0,0 +1,191 @@
+use dllwalk_core::{pe::DependencyKind, DllDatabase};
+
+use crate::filter;
+
+/// Windows releases since api sets and the subsystem versions below were introduced
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WindowsVersion {
+    Xp,
+    Vista,
+    Seven,
+    Eight,
+    EightOne,
+    Ten,
+}
+
+impl std::fmt::Display for WindowsVersion {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowsVersion::Xp => write!(formatter, "Windows XP"),
+            WindowsVersion::Vista => write!(formatter, "Windows Vista"),
+            WindowsVersion::Seven => write!(formatter, "Windows 7"),
+            WindowsVersion::Eight => write!(formatter, "Windows 8"),
+            WindowsVersion::EightOne => write!(formatter, "Windows 8.1"),
+            WindowsVersion::Ten => write!(formatter, "Windows 10"),
+        }
+    }
+}
+
+impl WindowsVersion {
+    /// Release of an NT version like the ones of the optional header, None before XP
+    fn from_nt_version((major, minor): (u16, u16)) -> Option<Self> {
+        match (major, minor) {
+            (7.., _) => Some(WindowsVersion::Ten),
+            (6, 3..) => Some(WindowsVersion::EightOne),
+            (6, 2) => Some(WindowsVersion::Eight),
+            (6, 1) => Some(WindowsVersion::Seven),
+            (6, 0) => Some(WindowsVersion::Vista),
+            (5, 1..) => Some(WindowsVersion::Xp),
+            _ => None,
+        }
+    }
+}
+
+// Contracts added after Windows 7 whose level is 1-1-0, or which were added after
+// Windows 8, by lowercase name without extension
+const API_SETS: [(&str, WindowsVersion); 10] = [
+    ("api-ms-win-core-path-l1-1-0", WindowsVersion::Eight),
+    ("api-ms-win-core-winrt-l1-1-0", WindowsVersion::Eight),
+    ("api-ms-win-core-winrt-string-l1-1-0", WindowsVersion::Eight),
+    ("api-ms-win-core-winrt-error-l1-1-0", WindowsVersion::Eight),
+    ("api-ms-win-core-file-l1-2-1", WindowsVersion::EightOne),
+    ("api-ms-win-core-processthreads-l1-1-2", WindowsVersion::EightOne),
+    ("api-ms-win-core-sysinfo-l1-2-1", WindowsVersion::EightOne),
+    ("api-ms-win-core-file-l1-2-2", WindowsVersion::Ten),
+    ("api-ms-win-core-processthreads-l1-1-3", WindowsVersion::Ten),
+    ("api-ms-win-core-synch-l1-2-1", WindowsVersion::Ten),
+];
+
+// System dlls added after XP, by lowercase name
+const SYSTEM_DLLS: [(&str, WindowsVersion); 14] = [
+    ("bcrypt.dll", WindowsVersion::Vista),
+    ("ncrypt.dll", WindowsVersion::Vista),
+    ("dwmapi.dll", WindowsVersion::Vista),
+    ("propsys.dll", WindowsVersion::Vista),
+    ("dxgi.dll", WindowsVersion::Vista),
+    ("d3d10.dll", WindowsVersion::Vista),
+    ("d3d11.dll", WindowsVersion::Vista),
+    ("d2d1.dll", WindowsVersion::Seven),
+    ("dwrite.dll", WindowsVersion::Seven),
+    ("combase.dll", WindowsVersion::Eight),
+    ("dcomp.dll", WindowsVersion::Eight),
+    ("shcore.dll", WindowsVersion::EightOne),
+    ("d3d12.dll", WindowsVersion::Ten),
+    ("windows.storage.dll", WindowsVersion::Ten),
+];
+
+/// Why the dlls of an application need a Windows version
+#[derive(Debug, PartialEq, Eq)]
+pub struct Requirement {
+    pub version: WindowsVersion,
+    pub dll: String,
+    pub reason: String,
+}
+
+/// The minimum Windows version the dlls of an application can run on, with the requirements
+/// raising it to that version. Only the application dlls are considered, system dlls come
+/// with the version they need. The version is a lower bound, the functions each dll imports
+/// may need a later one.
+pub fn minimum_version(database: &DllDatabase, root: &str) -> (WindowsVersion, Vec<Requirement>) {
+    let mut requirements = Vec::new();
+    for name in database.get_all_dlls() {
+        let info = match database.get_dll_info(&name) {
+            Some(info) if info.has_headers() => info,
+            _ => continue,
+        };
+        if filter::is_system(info.dll_type) && !name.eq_ignore_ascii_case(root) {
+            continue;
+        }
+
+        // The loader refuses images with a later subsystem version than its own, the
+        // operating system version is the one the image was built for
+        let headers = [
+            ("subsystem version", info.file.subsystem_version),
+            ("operating system version", info.file.os_version),
+        ];
+        for (field, (major, minor)) in headers {
+            if let Some(version) = WindowsVersion::from_nt_version((major, minor)) {
+                requirements.push(Requirement {
+                    version,
+                    dll: name.clone(),
+                    reason: format!("{} {}.{}", field, major, minor),
+                });
+            }
+        }
+
+        for dependency in info.file.dependencies() {
+            if dependency.kind != DependencyKind::Import {
+                continue;
+            }
+            if let Some(version) = imported_dll_version(&dependency.name) {
+                requirements.push(Requirement {
+                    version,
+                    dll: name.clone(),
+                    reason: format!("imports {}", dependency.name),
+                });
+            }
+        }
+    }
+
+    let minimum = requirements
+        .iter()
+        .map(|requirement| requirement.version)
+        .max()
+        .unwrap_or(WindowsVersion::Xp);
+    requirements.retain(|requirement| requirement.version == minimum);
+    requirements.sort_by(|a, b| (&a.dll, &a.reason).cmp(&(&b.dll, &b.reason)));
+    (minimum, requirements)
+}
+
+// Api sets appeared in Windows 7, which only has the first level of each contract
+fn imported_dll_version(dll: &str) -> Option<WindowsVersion> {
+    let dll = dll.to_lowercase();
+    if let Some((_, version)) = SYSTEM_DLLS.iter().find(|(name, _)| *name == dll) {
+        return Some(*version);
+    }
+
+    let contract = dll.strip_suffix(".dll").unwrap_or(&dll);
+    if let Some((_, version)) = API_SETS.iter().find(|(name, _)| *name == contract) {
+        return Some(*version);
+    }
+
+    // The Universal C Runtime is also installed on earlier versions
+    if contract.starts_with("api-ms-win-crt-") {
+        return None;
+    }
+    if contract.starts_with("ext-ms-") {
+        return Some(WindowsVersion::Eight);
+    }
+    if contract.starts_with("api-ms-") {
+        if contract.ends_with("-l1-1-0") {
+            return Some(WindowsVersion::Seven);
+        }
+        return Some(WindowsVersion::Eight);
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn imported_dll_version() {
+        let version = super::imported_dll_version;
+        assert_eq!(version("KERNEL32.dll"), None);
+        assert_eq!(version("D3D12.dll"), Some(WindowsVersion::Ten));
+        assert_eq!(version("api-ms-win-core-synch-l1-1-0.dll"), Some(WindowsVersion::Seven));
+        assert_eq!(version("api-ms-win-core-synch-l1-2-0.dll"), Some(WindowsVersion::Eight));
+        assert_eq!(version("api-ms-win-core-path-l1-1-0.dll"), Some(WindowsVersion::Eight));
+        assert_eq!(version("api-ms-win-core-file-l1-2-2.dll"), Some(WindowsVersion::Ten));
+        assert_eq!(version("api-ms-win-crt-runtime-l1-1-0.dll"), None);
+    }
+
+    #[test]
+    fn from_nt_version() {
+        assert_eq!(WindowsVersion::from_nt_version((5, 0)), None);
+        assert_eq!(WindowsVersion::from_nt_version((5, 2)), Some(WindowsVersion::Xp));
+        assert_eq!(WindowsVersion::from_nt_version((6, 1)), Some(WindowsVersion::Seven));
+        assert_eq!(WindowsVersion::from_nt_version((10, 0)), Some(WindowsVersion::Ten));
+    }
+}